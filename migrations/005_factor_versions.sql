@@ -0,0 +1,75 @@
+-- factor 表按数据版本去重
+-- 版本: v0.5.0
+-- 日期: 2026-10-18
+--
+-- 只执行一次（clickhouse-client --multiquery < 005_factor_versions.sql）。
+-- 第 0 步检查 factor 仍为 MergeTree，已迁移过时报错终止，不做任何修改。
+-- 在 RENAME 之前失败时可以直接重新执行；RENAME 之后失败时单独执行第 2、3 节。
+
+-- ============================================================
+-- 0. 执行前检查
+-- ============================================================
+
+SELECT throwIf(
+    (SELECT engine FROM system.tables WHERE database = 'kaipanla' AND name = 'factor') != 'MergeTree',
+    'kaipanla.factor 不是 MergeTree，005 已执行过'
+);
+
+-- 上次中断留下的临时表
+DROP TABLE IF EXISTS kaipanla.factor_replacing;
+
+-- ============================================================
+-- 1. factor 表改为 ReplacingMergeTree(data_version)
+-- ============================================================
+
+-- 多数据源对账在采用值变化时以更高的 data_version 重新写入同一 (date, code)，
+-- 合并后只保留版本号最大的一行；查询需加 FINAL 才能在合并前看到去重结果。
+CREATE TABLE kaipanla.factor_replacing (
+    date Date COMMENT '交易日期',
+    code FixedString(6) COMMENT '股票代码',
+    open Float64 COMMENT '开盘价',
+    high Float64 COMMENT '最高价',
+    low Float64 COMMENT '最低价',
+    close Float64 COMMENT '收盘价',
+    preclose Float64 COMMENT '昨收价',
+    factor Float64 COMMENT '复权因子',
+    volume Float64 COMMENT '成交量',
+    amount Float64 COMMENT '成交额',
+    data_version UInt32 DEFAULT 1 COMMENT '数据版本号（对账后内容变化时递增）',
+    data_source Enum8('api'=1, 'file'=2, 'manual'=3) DEFAULT 'api' COMMENT '数据来源',
+    quality_score Enum8('good'=1, 'suspect'=2, 'error'=3) DEFAULT 'good' COMMENT '数据质量评分',
+    created_at DateTime DEFAULT now() COMMENT '数据创建时间'
+) ENGINE = ReplacingMergeTree(data_version)
+PARTITION BY toYYYYMM(date)
+ORDER BY (date, code)
+COMMENT '日线数据表 - 同一交易日只保留最新版本';
+
+INSERT INTO kaipanla.factor_replacing
+SELECT date, code, open, high, low, close, preclose, factor, volume, amount,
+       data_version, data_source, quality_score, created_at
+FROM kaipanla.factor;
+
+RENAME TABLE kaipanla.factor TO kaipanla.factor_merge_tree,
+             kaipanla.factor_replacing TO kaipanla.factor;
+
+OPTIMIZE TABLE kaipanla.factor FINAL;
+
+-- ============================================================
+-- 2. 删除按写入次数累计的质量统计
+-- ============================================================
+
+-- 物化视图在每次 INSERT 时累加，重新写入的版本会被重复计数（且随 RENAME 挂在旧表上）；
+-- 每日质量统计改为查询时对 factor FINAL 分组。
+DROP VIEW IF EXISTS kaipanla.mv_daily_quality_stats;
+
+-- ============================================================
+-- 3. 清理旧表
+-- ============================================================
+
+-- 新表每个 (date, code) 一行，与旧表去重后的行数一致时删除旧表，否则报错保留旧表以便核对
+SELECT throwIf(
+    (SELECT uniqExact(date, code) FROM kaipanla.factor_merge_tree) != (SELECT count() FROM kaipanla.factor FINAL),
+    'kaipanla.factor 与 factor_merge_tree 行数不一致，旧表未删除'
+);
+
+DROP TABLE kaipanla.factor_merge_tree;
@@ -1,8 +1,12 @@
 use axum::{Json, Router};
+use axum::extract::State;
 use axum::response::IntoResponse;
 use axum::routing::{get, post};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::sync::Arc;
+use crate::collector::breaker::BreakerState;
+use crate::collector::pool::ServerSnapshot;
+use crate::collector::source::DataSource;
 
 /// 采集状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +23,29 @@ pub struct CollectionStatus {
 pub struct ServerStatus {
     pub host: String,
     pub status: String,  // "healthy" | "failed" | "unknown"
+    pub breaker: BreakerState,
+    pub latency_ms: Option<f64>,
+    pub fail_count: u32,
+}
+
+impl From<ServerSnapshot> for ServerStatus {
+    fn from(s: ServerSnapshot) -> Self {
+        let status = if !s.healthy {
+            "failed"
+        } else if s.latency_ms.is_none() {
+            "unknown"
+        } else {
+            "healthy"
+        };
+
+        Self {
+            host: s.addr,
+            status: status.to_string(),
+            breaker: s.breaker,
+            latency_ms: s.latency_ms,
+            fail_count: s.fail_count,
+        }
+    }
 }
 
 /// 启动采集请求
@@ -28,12 +55,12 @@ pub struct StartCollectionRequest {
     pub mode: String,  // "realtime" | "history"
 }
 
-pub fn create_router() -> Router {
+pub fn create_router(source: Arc<dyn DataSource>) -> Router {
     Router::new()
         .route("/api/v1/collection/start", post(start_collection))
         .route("/api/v1/collection/stop", post(stop_collection))
         .route("/api/v1/collection/status", get(get_collection_status))
-        .route("/api/v1/data/quality", get(get_data_quality))
+        .with_state(source)
 }
 
 /// 启动数据采集
@@ -60,40 +87,20 @@ async fn stop_collection() -> impl IntoResponse {
     }))
 }
 
-/// 获取采集状态
-async fn get_collection_status() -> impl IntoResponse {
-    // TODO: 实际查询状态
+/// 获取采集状态（含各服务器熔断状态）
+async fn get_collection_status(State(source): State<Arc<dyn DataSource>>) -> impl IntoResponse {
+    // TODO: 采集任务运行状态
     let status = CollectionStatus {
         is_running: false,
         last_update: None,
         success_count: 0,
         failed_count: 0,
-        servers: vec![
-            ServerStatus {
-                host: "124.71.187.122:7709".to_string(),
-                status: "healthy".to_string(),
-            }
-        ],
+        servers: source
+            .server_status()
+            .into_iter()
+            .map(ServerStatus::from)
+            .collect(),
     };
 
     Json(status)
 }
-
-/// 获取数据质量报告
-async fn get_data_quality(
-    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
-) -> impl IntoResponse {
-    let date = params.get("date").cloned().unwrap_or_default();
-
-    tracing::debug!("查询数据质量: date={}", date);
-
-    // TODO: 实际查询数据质量
-    Json(serde_json::json!({
-        "date": date,
-        "total_records": 0,
-        "good_quality": 0,
-        "suspect": 0,
-        "error": 0,
-        "issues": []
-    }))
-}
@@ -0,0 +1,90 @@
+use axum::{Json, Router};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use futures_util::stream::{self, Stream};
+use serde_json::json;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use crate::collector::importer::{HistoryImporter, ImportLanes};
+use crate::error::Result;
+
+pub fn create_router(importer: Arc<HistoryImporter>) -> Router {
+    Router::new()
+        .route("/api/v1/import/progress", get(get_progress))
+        .route("/api/v1/import/events", get(progress_events))
+        .route("/api/v1/import/pause", post(pause_import))
+        .route("/api/v1/import/resume", post(resume_import))
+        .route("/api/v1/import/cancel", post(cancel_import))
+        .route("/api/v1/import/lanes", get(get_lanes).put(set_lanes))
+        .with_state(importer)
+}
+
+/// 获取导入进度
+async fn get_progress(State(importer): State<Arc<HistoryImporter>>) -> impl IntoResponse {
+    Json(importer.get_progress().await)
+}
+
+/// 导入进度事件流（SSE，事件名 `progress`）
+async fn progress_events(
+    State(importer): State<Arc<HistoryImporter>>,
+) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
+    let events = stream::unfold(importer.subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(progress) => {
+                    let event = Event::default()
+                        .event("progress")
+                        .json_data(&progress)
+                        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()));
+                    return Some((Ok(event), receiver));
+                }
+                // 落后时跳过旧进度，下一条即为最新状态
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// 暂停导入
+async fn pause_import(State(importer): State<Arc<HistoryImporter>>) -> impl IntoResponse {
+    control_response(importer.pause().await, "paused")
+}
+
+/// 恢复导入
+async fn resume_import(State(importer): State<Arc<HistoryImporter>>) -> impl IntoResponse {
+    control_response(importer.resume().await, "resumed")
+}
+
+/// 取消导入
+async fn cancel_import(State(importer): State<Arc<HistoryImporter>>) -> impl IntoResponse {
+    importer.cancel().await;
+    control_response(Ok(()), "cancelled")
+}
+
+/// 获取优先通道
+async fn get_lanes(State(importer): State<Arc<HistoryImporter>>) -> impl IntoResponse {
+    Json(importer.lanes())
+}
+
+/// 设置优先通道
+async fn set_lanes(
+    State(importer): State<Arc<HistoryImporter>>,
+    Json(lanes): Json<ImportLanes>,
+) -> impl IntoResponse {
+    importer.set_lanes(lanes);
+    Json(importer.lanes())
+}
+
+fn control_response(result: Result<()>, status: &str) -> axum::response::Response {
+    match result {
+        Ok(()) => Json(json!({ "status": status })).into_response(),
+        Err(e) => (StatusCode::CONFLICT, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
@@ -1,4 +1,8 @@
+pub mod collection;
+pub mod import;
+pub mod quality;
 pub mod server;
 pub mod routes;
+pub mod validation;
 
 pub use server::ApiServer;
\ No newline at end of file
@@ -0,0 +1,74 @@
+use axum::{Json, Router};
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use crate::error::Result;
+use crate::models::quality::{DataQualityReport, ReportFormat};
+use crate::service::quality_service::{report_range, QualityService};
+
+/// 质量报告查询参数
+///
+/// `start` / `end` 为 `YYYY-MM-DD`，`date` 为单日的简写；`format` 为 `json` 或 `csv` 时以附件下载。
+#[derive(Debug, Default, Deserialize)]
+pub struct QualityQuery {
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub date: Option<String>,
+    pub format: Option<String>,
+}
+
+pub fn create_router(service: Arc<QualityService>) -> Router {
+    Router::new()
+        .route("/api/v1/data/quality", get(get_data_quality))
+        .with_state(service)
+}
+
+/// 获取数据质量报告
+async fn get_data_quality(
+    State(service): State<Arc<QualityService>>,
+    Query(query): Query<QualityQuery>,
+) -> axum::response::Response {
+    tracing::debug!("查询数据质量: {:?}", query);
+
+    let format = match query.format.as_deref().map(ReportFormat::parse).transpose() {
+        Ok(format) => format,
+        Err(e) => return bad_request(e),
+    };
+    let report = match build_report(&service, &query).await {
+        Ok(report) => report,
+        Err(e) => return bad_request(e),
+    };
+
+    let Some(format) = format else {
+        return Json(report).into_response();
+    };
+    match report.export(format) {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}\"", report.file_name(format)),
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+async fn build_report(service: &QualityService, query: &QualityQuery) -> Result<DataQualityReport> {
+    let start = query.start.as_deref().or(query.date.as_deref());
+    let end = query.end.as_deref().or(query.date.as_deref());
+    let (start, end) = report_range(start, end)?;
+    service.report(start, end).await
+}
+
+fn bad_request(e: crate::error::AppError) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string() }))).into_response()
+}
@@ -1,16 +1,35 @@
 use axum::{Json, Router};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::routing::get;
+use serde::Deserialize;
 use serde_json::json;
+use std::sync::Arc;
+use crate::collector::tdx::parse_date;
+use crate::error::AppError;
+use crate::models::{AdjustMode, KLinePeriod};
+use crate::service::QuoteService;
 
-pub fn create_router() -> Router {
+/// K 线查询参数
+#[derive(Debug, Deserialize)]
+pub struct KLineQuery {
+    pub period: Option<KLinePeriod>,
+    pub start: String,
+    pub end: String,
+    pub adjust: Option<AdjustMode>,
+}
+
+pub fn create_router(quote_service: Arc<QuoteService>) -> Router {
     Router::new()
         .route("/health", get(health_check))
         .route("/api/v1/ping", get(ping))
         .route("/api/v1/quote/:code", get(get_quote))
+        .route("/api/v1/kline/:code", get(get_kline))
         .route("/api/v1/moneyflow/:code", get(get_money_flow))
         .route("/api/v1/dragon-tiger", get(get_dragon_tiger_list))
         .route("/api/v1/auction/anomalies", get(get_auction_anomalies))
+        .with_state(quote_service)
 }
 
 async fn health_check() -> impl IntoResponse {
@@ -39,6 +58,40 @@ async fn get_quote(axum::extract::Path(code): axum::extract::Path<String>) -> im
     }))
 }
 
+/// 获取 K 线（`?period=Day&start=2024-01-01&end=2024-06-30&adjust=forward`）
+async fn get_kline(
+    State(service): State<Arc<QuoteService>>,
+    Path(code): Path<String>,
+    Query(query): Query<KLineQuery>,
+) -> impl IntoResponse {
+    let result = async {
+        let start = parse_date(&query.start)?;
+        let end = parse_date(&query.end)?;
+        service
+            .get_kline(
+                &code,
+                query.period.unwrap_or(KLinePeriod::Day),
+                start,
+                end,
+                query.adjust.unwrap_or_default(),
+            )
+            .await
+    }
+    .await;
+
+    match result {
+        Ok(bars) => Json(bars).into_response(),
+        Err(e) => {
+            let status = match e {
+                AppError::Parse(_) => StatusCode::BAD_REQUEST,
+                AppError::NotFound(_) => StatusCode::NOT_FOUND,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(json!({ "error": e.to_string() }))).into_response()
+        }
+    }
+}
+
 /// 获取资金流向
 async fn get_money_flow(axum::extract::Path(code): axum::extract::Path<String>) -> impl IntoResponse {
     // TODO: 调用 MoneyFlowService
@@ -1,23 +1,69 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
+use crate::api::{collection, import, quality, validation};
 use crate::api::routes::create_router;
 use crate::config::ApiConfig;
 use crate::error::{Result, AppError};
+use crate::collector::importer::HistoryImporter;
+use crate::collector::rules::RuleEngine;
+use crate::collector::source::DataSource;
+use crate::service::{QualityService, QuoteService};
 
 pub struct ApiServer {
     addr: SocketAddr,
+    quote_service: Arc<QuoteService>,
+    source: Arc<dyn DataSource>,
+    importer: Option<Arc<HistoryImporter>>,
+    quality: Option<Arc<QualityService>>,
+    rules: Option<Arc<RuleEngine>>,
 }
 
 impl ApiServer {
-    pub fn new(config: &ApiConfig) -> Self {
+    pub fn new(config: &ApiConfig, quote_service: Arc<QuoteService>, source: Arc<dyn DataSource>) -> Self {
         let addr = format!("{}:{}", config.host, config.port)
             .parse()
             .expect("无效的地址");
 
-        Self { addr }
+        Self {
+            addr,
+            quote_service,
+            source,
+            importer: None,
+            quality: None,
+            rules: None,
+        }
+    }
+
+    /// 提供历史导入的控制与进度接口
+    pub fn with_importer(mut self, importer: Arc<HistoryImporter>) -> Self {
+        self.importer = Some(importer);
+        self
+    }
+
+    /// 提供数据质量报告接口
+    pub fn with_quality_service(mut self, quality: Arc<QualityService>) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// 提供验证规则的查看与重新加载接口
+    pub fn with_rule_engine(mut self, rules: Arc<RuleEngine>) -> Self {
+        self.rules = Some(rules);
+        self
     }
 
     pub async fn run(self) -> Result<()> {
-        let app = create_router();
+        let mut app = create_router(self.quote_service)
+            .merge(collection::create_router(self.source));
+        if let Some(importer) = self.importer {
+            app = app.merge(import::create_router(importer));
+        }
+        if let Some(quality) = self.quality {
+            app = app.merge(quality::create_router(quality));
+        }
+        if let Some(rules) = self.rules {
+            app = app.merge(validation::create_router(rules));
+        }
 
         let listener = tokio::net::TcpListener::bind(self.addr)
             .await
@@ -0,0 +1,42 @@
+use axum::{Json, Router};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use serde_json::json;
+use std::sync::Arc;
+use crate::collector::rules::RuleEngine;
+use crate::config::ValidationConfig;
+use crate::error::Result;
+
+pub fn create_router(rules: Arc<RuleEngine>) -> Router {
+    Router::new()
+        .route("/api/v1/validation/rules", get(get_rules).put(set_rules))
+        .route("/api/v1/validation/reload", post(reload_rules))
+        .with_state(rules)
+}
+
+/// 获取当前验证规则
+async fn get_rules(State(rules): State<Arc<RuleEngine>>) -> impl IntoResponse {
+    Json(rules.current().config().clone())
+}
+
+/// 替换验证规则（不写回配置文件）
+async fn set_rules(
+    State(rules): State<Arc<RuleEngine>>,
+    Json(config): Json<ValidationConfig>,
+) -> impl IntoResponse {
+    rules_response(rules.reload(&config).map(|_| config))
+}
+
+/// 重新读取配置文件中的验证规则
+async fn reload_rules(State(rules): State<Arc<RuleEngine>>) -> impl IntoResponse {
+    rules_response(rules.reload_from_file())
+}
+
+fn rules_response(result: Result<ValidationConfig>) -> axum::response::Response {
+    match result {
+        Ok(config) => Json(config).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string() }))).into_response(),
+    }
+}
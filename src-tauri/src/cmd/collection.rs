@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
+use crate::models::quality::{DataQualityReport, ReportFormat};
+use crate::service::quality_service::{report_range, QualityService};
 use crate::Result;
+use std::sync::Arc;
 
 /// 采集状态
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,25 +13,6 @@ pub struct CollectionStatus {
     pub failed_count: u64,
 }
 
-/// 数据质量报告
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DataQualityReport {
-    pub date: String,
-    pub total_records: u64,
-    pub good_quality: u64,
-    pub suspect: u64,
-    pub error: u64,
-    pub issues: Vec<QualityIssue>,
-}
-
-/// 质量问题
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct QualityIssue {
-    pub code: String,
-    pub issue_type: String,
-    pub description: String,
-}
-
 /// 启动数据采集
 #[tauri::command]
 pub async fn start_collection(
@@ -65,17 +49,33 @@ pub async fn get_collection_status() -> Result<CollectionStatus> {
 }
 
 /// 获取数据质量报告
+///
+/// `start` / `end` 为 `YYYY-MM-DD`，`date` 为单日的简写；只给一端时为单日，都不给时为今天。
 #[tauri::command]
-pub async fn get_data_quality(date: Option<String>) -> Result<DataQualityReport> {
-    tracing::debug!("查询数据质量: date={:?}", date);
+pub async fn get_data_quality(
+    service: tauri::State<'_, Arc<QualityService>>,
+    date: Option<String>,
+    start: Option<String>,
+    end: Option<String>,
+) -> Result<DataQualityReport> {
+    tracing::debug!("查询数据质量: date={:?}, start={:?}, end={:?}", date, start, end);
 
-    // TODO: 实际查询数据质量
-    Ok(DataQualityReport {
-        date: date.unwrap_or_default(),
-        total_records: 0,
-        good_quality: 0,
-        suspect: 0,
-        error: 0,
-        issues: vec![],
-    })
+    let start = start.as_deref().or(date.as_deref());
+    let end = end.as_deref().or(date.as_deref());
+    let (start, end) = report_range(start, end)?;
+    service.report(start, end).await
+}
+
+/// 导出数据质量报告，返回文件名与内容（`format` 为 `json` 或 `csv`）
+#[tauri::command]
+pub async fn export_data_quality(
+    service: tauri::State<'_, Arc<QualityService>>,
+    start: Option<String>,
+    end: Option<String>,
+    format: String,
+) -> Result<(String, String)> {
+    let format = ReportFormat::parse(&format)?;
+    let (start, end) = report_range(start.as_deref(), end.as_deref())?;
+    let report = service.report(start, end).await?;
+    Ok((report.file_name(format), report.export(format)?))
 }
@@ -0,0 +1,73 @@
+//! 历史导入相关的 Tauri 命令与进度事件
+
+use crate::collector::importer::{HistoryImporter, ImportLanes, ImportProgress};
+use std::result::Result;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast::error::RecvError;
+
+/// 导入进度事件名
+pub const IMPORT_PROGRESS_EVENT: &str = "import-progress";
+
+/// 把导入进度转发为前端事件（在应用 `setup` 中调用一次）
+pub fn forward_progress_events(app: AppHandle, importer: Arc<HistoryImporter>) {
+    let mut events = importer.subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(progress) => {
+                    if let Err(e) = app.emit(IMPORT_PROGRESS_EVENT, &progress) {
+                        tracing::warn!("推送导入进度失败: {}", e);
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// 获取导入进度
+#[tauri::command]
+pub async fn get_import_progress(
+    importer: tauri::State<'_, Arc<HistoryImporter>>,
+) -> Result<ImportProgress, String> {
+    Ok(importer.get_progress().await)
+}
+
+/// 暂停导入
+#[tauri::command]
+pub async fn pause_import(importer: tauri::State<'_, Arc<HistoryImporter>>) -> Result<(), String> {
+    importer.pause().await.map_err(|e| e.to_string())
+}
+
+/// 恢复导入
+#[tauri::command]
+pub async fn resume_import(importer: tauri::State<'_, Arc<HistoryImporter>>) -> Result<(), String> {
+    importer.resume().await.map_err(|e| e.to_string())
+}
+
+/// 取消导入
+#[tauri::command]
+pub async fn cancel_import(importer: tauri::State<'_, Arc<HistoryImporter>>) -> Result<(), String> {
+    importer.cancel().await;
+    Ok(())
+}
+
+/// 获取优先通道
+#[tauri::command]
+pub async fn get_import_lanes(
+    importer: tauri::State<'_, Arc<HistoryImporter>>,
+) -> Result<ImportLanes, String> {
+    Ok(importer.lanes())
+}
+
+/// 设置优先通道（自选股、近期活跃股票）
+#[tauri::command]
+pub async fn set_import_lanes(
+    importer: tauri::State<'_, Arc<HistoryImporter>>,
+    lanes: ImportLanes,
+) -> Result<ImportLanes, String> {
+    importer.set_lanes(lanes);
+    Ok(importer.lanes())
+}
@@ -1 +1,4 @@
-pub mod quote;
\ No newline at end of file
+pub mod collection;
+pub mod import;
+pub mod quote;
+pub mod validation;
@@ -1,5 +1,9 @@
+use crate::collector::tdx::parse_date;
+use crate::models::{AdjustMode, KLine, KLinePeriod, Security, Stock};
+use crate::service::{QuoteService, SecurityService};
 use serde::{Deserialize, Serialize};
 use std::result::Result;
+use std::sync::Arc;
 
 /// 股票实时行情
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +33,37 @@ pub async fn get_quote(code: String) -> Result<Quote, String> {
 
 /// 获取股票列表命令
 #[tauri::command]
-pub async fn get_stock_list() -> Result<Vec<Quote>, String> {
-    Ok(vec![])
-}
\ No newline at end of file
+pub async fn get_stock_list(service: tauri::State<'_, Arc<QuoteService>>) -> Result<Vec<Stock>, String> {
+    service.get_stock_list().await.map_err(|e| e.to_string())
+}
+
+/// 查询证券主数据命令（按股票代码）
+#[tauri::command]
+pub async fn get_security(
+    service: tauri::State<'_, Arc<SecurityService>>,
+    code: String,
+) -> Result<Security, String> {
+    service
+        .get_by_code(&code)
+        .await
+        .ok_or_else(|| format!("未找到证券: {}", code))
+}
+
+/// 获取 K 线命令（日期格式 `%Y-%m-%d`，`adjust` 缺省为不复权）
+#[tauri::command]
+pub async fn get_kline(
+    service: tauri::State<'_, Arc<QuoteService>>,
+    code: String,
+    period: KLinePeriod,
+    start: String,
+    end: String,
+    adjust: Option<AdjustMode>,
+) -> Result<Vec<KLine>, String> {
+    let start = parse_date(&start).map_err(|e| e.to_string())?;
+    let end = parse_date(&end).map_err(|e| e.to_string())?;
+
+    service
+        .get_kline(&code, period, start, end, adjust.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,30 @@
+//! 数据验证规则相关的 Tauri 命令
+
+use crate::collector::rules::RuleEngine;
+use crate::config::ValidationConfig;
+use std::result::Result;
+use std::sync::Arc;
+
+/// 获取当前验证规则
+#[tauri::command]
+pub async fn get_validation_rules(rules: tauri::State<'_, Arc<RuleEngine>>) -> Result<ValidationConfig, String> {
+    Ok(rules.current().config().clone())
+}
+
+/// 替换验证规则（不写回配置文件）
+#[tauri::command]
+pub async fn set_validation_rules(
+    rules: tauri::State<'_, Arc<RuleEngine>>,
+    config: ValidationConfig,
+) -> Result<ValidationConfig, String> {
+    rules.reload(&config).map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+/// 重新读取配置文件中的验证规则
+#[tauri::command]
+pub async fn reload_validation_rules(
+    rules: tauri::State<'_, Arc<RuleEngine>>,
+) -> Result<ValidationConfig, String> {
+    rules.reload_from_file().map_err(|e| e.to_string())
+}
@@ -0,0 +1,191 @@
+//! 熔断器 - 按服务器隔离持续失败
+//!
+//! - Closed：正常放行，连续失败达到阈值后进入 Open
+//! - Open：拒绝请求，冷却期满后进入 HalfOpen
+//! - HalfOpen：只放行一次试探请求，成功则恢复 Closed，失败或被取消则重新 Open
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,    // 正常
+    Open,      // 熔断
+    HalfOpen,  // 试探
+}
+
+/// 熔断器
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    threshold: u32,
+    cooldown: Duration,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+impl CircuitBreaker {
+    /// 创建熔断器
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            threshold: threshold.max(1),
+            cooldown,
+            opened_at: None,
+            trial_in_flight: false,
+        }
+    }
+
+    /// 当前状态（冷却期满的 Open 视为 HalfOpen）
+    pub fn state(&self, now: Instant) -> BreakerState {
+        match self.state {
+            BreakerState::Open if self.cooldown_elapsed(now) => BreakerState::HalfOpen,
+            state => state,
+        }
+    }
+
+    /// 连续失败次数
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// 是否可以发起请求（不改变状态）
+    pub fn is_callable(&self, now: Instant) -> bool {
+        match self.state(now) {
+            BreakerState::Closed => true,
+            BreakerState::HalfOpen => !self.trial_in_flight,
+            BreakerState::Open => false,
+        }
+    }
+
+    /// 申请发起请求，HalfOpen 下占用唯一的试探名额
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        match self.state(now) {
+            BreakerState::Closed => true,
+            BreakerState::Open => false,
+            BreakerState::HalfOpen => {
+                if self.trial_in_flight {
+                    return false;
+                }
+                self.state = BreakerState::HalfOpen;
+                self.trial_in_flight = true;
+                true
+            }
+        }
+    }
+
+    /// 记录成功
+    pub fn on_success(&mut self) {
+        self.state = BreakerState::Closed;
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.trial_in_flight = false;
+    }
+
+    /// 记录失败，返回是否因此进入 Open
+    pub fn on_failure(&mut self, now: Instant) -> bool {
+        self.consecutive_failures += 1;
+        let should_open = self.state == BreakerState::HalfOpen || self.consecutive_failures >= self.threshold;
+        if should_open {
+            self.trip(now);
+        }
+        should_open
+    }
+
+    /// 放弃进行中的试探（请求未完成即被取消），重新 Open 等待下一次冷却
+    pub fn abandon_trial(&mut self, now: Instant) {
+        if self.trial_in_flight {
+            self.trip(now);
+        }
+    }
+
+    /// 立即熔断（如启动探测失败）
+    pub fn trip(&mut self, now: Instant) {
+        self.state = BreakerState::Open;
+        self.opened_at = Some(now);
+        self.trial_in_flight = false;
+    }
+
+    fn cooldown_elapsed(&self, now: Instant) -> bool {
+        self.opened_at
+            .is_some_and(|opened| now.duration_since(opened) >= self.cooldown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(3, Duration::from_secs(10));
+
+        assert!(!breaker.on_failure(now));
+        assert!(!breaker.on_failure(now));
+        assert_eq!(breaker.state(now), BreakerState::Closed);
+        assert!(breaker.on_failure(now));
+        assert_eq!(breaker.state(now), BreakerState::Open);
+        assert!(!breaker.try_acquire(now));
+
+        // 成功会清零连续失败
+        let mut other = CircuitBreaker::new(3, Duration::from_secs(10));
+        other.on_failure(now);
+        other.on_failure(now);
+        other.on_success();
+        assert!(!other.on_failure(now));
+    }
+
+    #[test]
+    fn test_half_open_single_trial() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(11);
+        let mut breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        breaker.on_failure(now);
+
+        assert_eq!(breaker.state(later), BreakerState::HalfOpen);
+        assert!(breaker.is_callable(later));
+        assert!(breaker.try_acquire(later));
+        // 试探进行中，不再放行
+        assert!(!breaker.is_callable(later));
+        assert!(!breaker.try_acquire(later));
+
+        breaker.on_success();
+        assert_eq!(breaker.state(later), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_failure_reopens() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(11);
+        let mut breaker = CircuitBreaker::new(5, Duration::from_secs(10));
+        breaker.trip(now);
+
+        assert!(breaker.try_acquire(later));
+        assert!(breaker.on_failure(later));
+        assert_eq!(breaker.state(later), BreakerState::Open);
+        assert_eq!(breaker.state(later + Duration::from_secs(10)), BreakerState::HalfOpen);
+    }
+
+    #[test]
+    fn test_abandoned_trial_reopens() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(11);
+        let mut breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        breaker.trip(now);
+
+        assert!(breaker.try_acquire(later));
+        breaker.abandon_trial(later);
+        assert_eq!(breaker.state(later), BreakerState::Open);
+        assert!(breaker.try_acquire(later + Duration::from_secs(10)));
+
+        // 未在试探中时不改变状态
+        breaker.on_success();
+        breaker.abandon_trial(later);
+        assert_eq!(breaker.state(later), BreakerState::Closed);
+    }
+}
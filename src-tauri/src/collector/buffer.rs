@@ -1,15 +1,14 @@
-use crate::models::quote::KLine;
 use tokio::sync::mpsc;
 
-/// 数据缓冲区
-pub struct DataBuffer {
-    sender: mpsc::Sender<KLine>,
+/// 数据缓冲区（采集与写入之间的有界队列）
+pub struct DataBuffer<T> {
+    sender: mpsc::Sender<T>,
     capacity: usize,
 }
 
-impl DataBuffer {
+impl<T> DataBuffer<T> {
     /// 创建新的缓冲区
-    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<KLine>) {
+    pub fn new(capacity: usize) -> (Self, mpsc::Receiver<T>) {
         let (sender, receiver) = mpsc::channel(capacity);
 
         let buffer = Self {
@@ -21,7 +20,7 @@ impl DataBuffer {
     }
 
     /// 发送数据到缓冲区
-    pub async fn send(&self, data: KLine) -> crate::Result<()> {
+    pub async fn send(&self, data: T) -> crate::Result<()> {
         self.sender
             .send(data)
             .await
@@ -39,6 +38,11 @@ impl DataBuffer {
         self.capacity - self.sender.capacity()
     }
 
+    /// 检查缓冲区是否为空
+    pub async fn is_empty(&self) -> bool {
+        self.sender.capacity() == self.capacity
+    }
+
     /// 检查缓冲区是否已满
     pub async fn is_full(&self) -> bool {
         self.sender.capacity() == 0
@@ -48,6 +52,7 @@ impl DataBuffer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::quote::KLine;
     use chrono::Utc;
 
     #[tokio::test]
@@ -73,7 +78,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_buffer_capacity() {
-        let (buffer, _) = DataBuffer::new(100);
+        let (buffer, _) = DataBuffer::<KLine>::new(100);
         assert_eq!(buffer.capacity(), 100);
     }
 }
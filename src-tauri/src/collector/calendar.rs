@@ -0,0 +1,243 @@
+//! A 股交易日历 - 节假日休市与交易日推算
+//!
+//! 休市安排来自随程序打包的 `resources/trading_calendar.toml`，也可加载外部文件。
+//! 周一至周五且非节假日即为交易日；调休上班的周末交易所仍休市。
+//! 文件未覆盖的年份只排除周末，调用方可用 [`TradingCalendar::covers`] 判断。
+
+use crate::collector::clock;
+use crate::config::DataSourceConfig;
+use crate::error::{AppError, Result};
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// 随程序打包的休市安排
+const BUNDLED: &str = include_str!("../../resources/trading_calendar.toml");
+
+/// 单年休市安排
+#[derive(Debug, Deserialize)]
+struct YearSchedule {
+    #[serde(default)]
+    holidays: Vec<String>,
+    #[serde(default)]
+    workdays: Vec<String>,
+}
+
+/// 交易日历
+#[derive(Debug, Clone)]
+pub struct TradingCalendar {
+    holidays: BTreeSet<NaiveDate>,
+    workdays: BTreeSet<NaiveDate>,  // 调休上班的周末
+    years: BTreeSet<i32>,
+}
+
+impl TradingCalendar {
+    /// 随程序打包的日历
+    pub fn bundled() -> Self {
+        Self::from_toml(BUNDLED).expect("内置交易日历格式错误")
+    }
+
+    /// 从文件加载
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        let calendar = Self::from_toml(&content)?;
+        tracing::info!(
+            "加载交易日历 {}，覆盖 {:?}",
+            path.as_ref().display(),
+            calendar.years
+        );
+        Ok(calendar)
+    }
+
+    /// 按配置加载：配置了 `calendar_path` 时读取外部文件，否则使用内置日历
+    pub fn from_config(config: &DataSourceConfig) -> Result<Self> {
+        match &config.calendar_path {
+            Some(path) => Self::load(path),
+            None => Ok(Self::bundled()),
+        }
+    }
+
+    /// 解析 TOML 格式的休市安排
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let schedule: BTreeMap<String, YearSchedule> =
+            toml::from_str(content).map_err(|e| AppError::Config(format!("交易日历: {}", e)))?;
+
+        let mut calendar = Self {
+            holidays: BTreeSet::new(),
+            workdays: BTreeSet::new(),
+            years: BTreeSet::new(),
+        };
+        for (year, entry) in schedule {
+            let year: i32 = year
+                .parse()
+                .map_err(|_| AppError::Config(format!("交易日历年份无效: {}", year)))?;
+            calendar.years.insert(year);
+            for item in &entry.holidays {
+                calendar.holidays.extend(parse_dates(item)?);
+            }
+            for item in &entry.workdays {
+                calendar.workdays.extend(parse_dates(item)?);
+            }
+        }
+
+        Ok(calendar)
+    }
+
+    /// 日历是否覆盖该日期所在年份
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.years.contains(&date.year())
+    }
+
+    /// 是否为法定节假日
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date)
+    }
+
+    /// 是否为调休上班的周末（不开市）
+    pub fn is_makeup_workday(&self, date: NaiveDate) -> bool {
+        self.workdays.contains(&date)
+    }
+
+    /// 是否为交易日
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.is_holiday(date)
+    }
+
+    /// 北京时间今天是否为交易日
+    pub fn is_trading_today(&self) -> bool {
+        self.is_trading_day(clock::now_local().date())
+    }
+
+    /// 严格晚于 `date` 的下一个交易日
+    pub fn next_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        let mut day = date;
+        loop {
+            day = day + Days::new(1);
+            if self.is_trading_day(day) {
+                return day;
+            }
+        }
+    }
+
+    /// 严格早于 `date` 的上一个交易日
+    pub fn prev_trading_day(&self, date: NaiveDate) -> NaiveDate {
+        let mut day = date;
+        loop {
+            day = day - Days::new(1);
+            if self.is_trading_day(day) {
+                return day;
+            }
+        }
+    }
+
+    /// `start` 到 `end`（含首尾）之间的全部交易日，按日期升序
+    pub fn trading_days_between(&self, start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+        start
+            .iter_days()
+            .take_while(|d| *d <= end)
+            .filter(|d| self.is_trading_day(*d))
+            .collect()
+    }
+
+    /// `date` 之前第 `n` 个交易日（不含 `date` 本身，`n` 为 0 时返回 `date`）
+    pub fn nth_trading_day_before(&self, date: NaiveDate, n: usize) -> NaiveDate {
+        (0..n).fold(date, |day, _| self.prev_trading_day(day))
+    }
+}
+
+impl Default for TradingCalendar {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+/// 解析单日 `YYYY-MM-DD` 或闭区间 `YYYY-MM-DD..YYYY-MM-DD`
+fn parse_dates(item: &str) -> Result<Vec<NaiveDate>> {
+    let parse = |s: &str| {
+        NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+            .map_err(|_| AppError::Config(format!("交易日历日期无效: {}", item)))
+    };
+
+    match item.split_once("..") {
+        Some((start, end)) => {
+            let (start, end) = (parse(start)?, parse(end)?);
+            if start > end {
+                return Err(AppError::Config(format!("交易日历区间起止颠倒: {}", item)));
+            }
+            Ok(start.iter_days().take_while(|d| *d <= end).collect())
+        }
+        None => Ok(vec![parse(item)?]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_holidays_and_makeup_workdays() {
+        let calendar = TradingCalendar::bundled();
+        assert!(calendar.covers(date(2024, 6, 3)));
+        assert!(!calendar.covers(date(2030, 1, 2)));
+
+        // 2024 春节：2-09 除夕至 2-16 休市，2-19 开市
+        assert!(calendar.is_trading_day(date(2024, 2, 8)));
+        assert!(!calendar.is_trading_day(date(2024, 2, 9)));
+        assert!(!calendar.is_trading_day(date(2024, 2, 16)));
+        assert!(calendar.is_trading_day(date(2024, 2, 19)));
+
+        // 国庆长假
+        assert!(!calendar.is_trading_day(date(2024, 10, 7)));
+        assert!(calendar.is_trading_day(date(2024, 10, 8)));
+
+        // 调休上班的周日仍不开市
+        assert!(calendar.is_makeup_workday(date(2024, 2, 18)));
+        assert!(!calendar.is_trading_day(date(2024, 2, 18)));
+    }
+
+    #[test]
+    fn test_trading_day_arithmetic() {
+        let calendar = TradingCalendar::bundled();
+
+        // 跨国庆
+        assert_eq!(calendar.next_trading_day(date(2024, 9, 30)), date(2024, 10, 8));
+        assert_eq!(calendar.prev_trading_day(date(2024, 10, 8)), date(2024, 9, 30));
+        // 跨周末
+        assert_eq!(calendar.next_trading_day(date(2024, 6, 7)), date(2024, 6, 11));
+
+        let days = calendar.trading_days_between(date(2024, 9, 26), date(2024, 10, 9));
+        assert_eq!(
+            days,
+            vec![date(2024, 9, 26), date(2024, 9, 27), date(2024, 9, 30), date(2024, 10, 8), date(2024, 10, 9)]
+        );
+        assert!(calendar.trading_days_between(date(2024, 10, 1), date(2024, 10, 7)).is_empty());
+
+        assert_eq!(calendar.nth_trading_day_before(date(2024, 10, 9), 0), date(2024, 10, 9));
+        assert_eq!(calendar.nth_trading_day_before(date(2024, 10, 9), 2), date(2024, 9, 30));
+        // 2025 春节前后
+        assert_eq!(calendar.nth_trading_day_before(date(2025, 2, 6), 2), date(2025, 1, 27));
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let calendar = TradingCalendar::from_toml(
+            r#"
+            [2030]
+            holidays = ["2030-01-01", "2030-02-04..2030-02-08"]
+            workdays = ["2030-02-09"]
+            "#,
+        )
+        .unwrap();
+        assert!(calendar.covers(date(2030, 3, 1)));
+        assert!(!calendar.is_trading_day(date(2030, 2, 6)));
+        assert_eq!(calendar.next_trading_day(date(2030, 2, 1)), date(2030, 2, 11));
+
+        assert!(TradingCalendar::from_toml("[2030]\nholidays = [\"2030-02-08..2030-02-04\"]").is_err());
+        assert!(TradingCalendar::from_toml("[abc]\nholidays = []").is_err());
+    }
+}
@@ -0,0 +1,57 @@
+//! 交易所时钟 - 北京时间（Asia/Shanghai，UTC+8，无夏令时）与 UTC 换算
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// 北京时间偏移
+pub fn shanghai() -> FixedOffset {
+    FixedOffset::east_opt(8 * 3600).expect("UTC+8 为合法偏移")
+}
+
+/// 北京时间转 UTC
+pub fn to_utc(local: NaiveDateTime) -> DateTime<Utc> {
+    shanghai()
+        .from_local_datetime(&local)
+        .single()
+        .expect("固定偏移不存在歧义时间")
+        .with_timezone(&Utc)
+}
+
+/// UTC 转北京时间
+pub fn to_local(datetime: DateTime<Utc>) -> NaiveDateTime {
+    datetime.with_timezone(&shanghai()).naive_local()
+}
+
+/// UTC 时间对应的北京日期
+pub fn local_date(datetime: DateTime<Utc>) -> NaiveDate {
+    to_local(datetime).date()
+}
+
+/// 当前北京时间
+pub fn now_local() -> NaiveDateTime {
+    to_local(Utc::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    #[test]
+    fn test_shanghai_roundtrip() {
+        let local = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+        let utc = to_utc(local);
+
+        assert_eq!(utc.hour(), 1);
+        assert_eq!(to_local(utc), local);
+    }
+
+    #[test]
+    fn test_local_date_crosses_midnight() {
+        // UTC 2024-01-01 20:00 即北京时间 2024-01-02 04:00
+        let utc = Utc.with_ymd_and_hms(2024, 1, 1, 20, 0, 0).unwrap();
+        assert_eq!(local_date(utc), NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+    }
+}
@@ -0,0 +1,169 @@
+//! K 线覆盖索引 - 记录每只股票已向数据源确认过的交易日
+//!
+//! 每个交易日一行：`has_bar` 为真表示已有日线，为假表示数据源确认当日无数据（停牌、
+//! 上市前）。缺失区间按交易日历计算，相邻的缺失交易日合并为一个请求区间，
+//! 日常补数与断线后修复只请求真正缺少的部分，重复执行不会重复请求。
+
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::db::LocalDb;
+use crate::error::Result;
+use crate::models::quote::KLine;
+use chrono::{Duration, NaiveDate};
+use rusqlite::params;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// K 线覆盖索引
+pub struct CoverageIndex {
+    db: Arc<LocalDb>,
+}
+
+impl CoverageIndex {
+    pub fn new(db: Arc<LocalDb>) -> Self {
+        Self { db }
+    }
+
+    /// `[start, end]` 内已覆盖的日期
+    pub fn covered_days(&self, code: &str, start: NaiveDate, end: NaiveDate) -> Result<BTreeSet<NaiveDate>> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT trade_date FROM kline_coverage WHERE code = ?1 AND trade_date BETWEEN ?2 AND ?3",
+            )?;
+            let rows = stmt.query_map(params![code, start.to_string(), end.to_string()], |row| {
+                row.get::<_, String>(0)
+            })?;
+
+            let mut days = BTreeSet::new();
+            for row in rows {
+                if let Ok(date) = row?.parse() {
+                    days.insert(date);
+                }
+            }
+            Ok(days)
+        })
+    }
+
+    /// 记录一次成功请求：区间内的交易日全部标记为已覆盖，有日线的标记 `has_bar`
+    pub fn record(
+        &self,
+        calendar: &TradingCalendar,
+        code: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        klines: &[KLine],
+    ) -> Result<()> {
+        let bars: BTreeSet<NaiveDate> = klines.iter().map(|k| clock::local_date(k.datetime)).collect();
+        let days: BTreeSet<NaiveDate> = calendar
+            .trading_days_between(start, end)
+            .into_iter()
+            .chain(bars.iter().copied())
+            .collect();
+
+        self.db.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR REPLACE INTO kline_coverage (code, trade_date, has_bar) VALUES (?1, ?2, ?3)",
+                )?;
+                for day in &days {
+                    stmt.execute(params![code, day.to_string(), bars.contains(day)])?;
+                }
+            }
+            tx.commit()
+        })
+    }
+
+    /// `[start, end]` 内尚未覆盖的交易日，合并为连续区间（从近到远）
+    ///
+    /// 每个区间首尾相差不超过 `max_span_days` 天。
+    pub fn missing_ranges(
+        &self,
+        calendar: &TradingCalendar,
+        code: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        max_span_days: i64,
+    ) -> Result<Vec<(NaiveDate, NaiveDate)>> {
+        let covered = self.covered_days(code, start, end)?;
+        Ok(missing_runs(calendar, start, end, max_span_days, |day| covered.contains(&day)))
+    }
+}
+
+/// 按交易日历把未覆盖的交易日合并为区间，从近到远排列
+pub(crate) fn missing_runs(
+    calendar: &TradingCalendar,
+    start: NaiveDate,
+    end: NaiveDate,
+    max_span_days: i64,
+    is_covered: impl Fn(NaiveDate) -> bool,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let max_span = Duration::days(max_span_days.max(1));
+    let mut ranges: Vec<(NaiveDate, NaiveDate)> = Vec::new();
+
+    // 从近到远扫描，遇到已覆盖的交易日或跨度超限时断开
+    for day in calendar.trading_days_between(start, end).into_iter().rev() {
+        if is_covered(day) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some((run_start, run_end))
+                if calendar.next_trading_day(day) == *run_start && *run_end - day <= max_span =>
+            {
+                *run_start = day;
+            }
+            _ => ranges.push((day, day)),
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::csv_source::fixture_source;
+    use crate::collector::source::DataSource;
+    use crate::models::quote::KLinePeriod;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_missing_ranges_follow_calendar() {
+        let calendar = TradingCalendar::bundled();
+        let coverage = CoverageIndex::new(Arc::new(LocalDb::open_in_memory().unwrap()));
+
+        // 2024-05-27 至 06-14：端午 6-10 休市，共 14 个交易日
+        let (start, end) = (date(2024, 5, 27), date(2024, 6, 14));
+        assert_eq!(
+            coverage.missing_ranges(&calendar, "000001", start, end, 30).unwrap(),
+            vec![(date(2024, 5, 27), date(2024, 6, 14))]
+        );
+
+        // 样本只有 6-03 至 6-07 五根日线，请求区间内的其余交易日确认无数据
+        let klines = fixture_source()
+            .get_kline("000001", KLinePeriod::Day, date(2024, 6, 1), date(2024, 6, 7))
+            .await
+            .unwrap();
+        coverage.record(&calendar, "000001", date(2024, 6, 1), date(2024, 6, 7), &klines).unwrap();
+        assert_eq!(coverage.covered_days("000001", start, end).unwrap().len(), 5);
+
+        // 周末与端午不算缺口
+        assert_eq!(
+            coverage.missing_ranges(&calendar, "000001", start, end, 30).unwrap(),
+            vec![(date(2024, 6, 11), date(2024, 6, 14)), (date(2024, 5, 27), date(2024, 5, 31))]
+        );
+
+        // 区间跨度上限
+        assert_eq!(
+            coverage.missing_ranges(&calendar, "000001", date(2024, 6, 11), end, 1).unwrap(),
+            vec![(date(2024, 6, 13), date(2024, 6, 14)), (date(2024, 6, 11), date(2024, 6, 12))]
+        );
+        assert!(coverage
+            .missing_ranges(&calendar, "000001", date(2024, 6, 3), date(2024, 6, 10), 30)
+            .unwrap()
+            .is_empty());
+    }
+}
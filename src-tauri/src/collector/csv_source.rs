@@ -0,0 +1,493 @@
+//! 本地 CSV 数据源 - 从样本目录读取行情，服务与 API 可完全离线运行
+//!
+//! 目录结构（带表头的 UTF-8 CSV，时间均为北京时间）：
+//!
+//! ```text
+//! <root>/
+//! ├── securities.csv                       market,code,name[,volunit,decimal_point,pre_close]
+//! ├── finance.csv                          market,code,list_date,total_shares,float_shares[,updated_date]
+//! ├── quotes.csv                           code,name,price,preclose,open,high,low,volume,amount,timestamp
+//! │                                        [,bid1..bid5,bid_vol1..bid_vol5,ask1..ask5,ask_vol1..ask_vol5]
+//! ├── auctions.csv                         code,name,price,preclose,volume,amount,timestamp
+//! ├── kline/<周期>/<代码>.csv               datetime,open,high,low,close,volume,amount
+//! └── transactions/<代码>/<YYYYMMDD>.csv    time,price,volume,amount[,num],direction
+//! ```
+//!
+//! - 周期目录：`1min`、`5min`、`day`、`week`、`month`
+//! - K 线 `datetime` 可只写日期，视为当日 15:00 收盘
+//! - 逐笔 `direction` 取 `B`（主动买）、`S`（主动卖）、`N`（中性）
+//! - 当日逐笔取该股票最新一个日期的文件
+
+use crate::collector::clock;
+use crate::collector::protocol::{FinanceInfo, SecurityInfo};
+use crate::collector::source::DataSource;
+use crate::error::{AppError, Result};
+use crate::models::auction::Auction;
+use crate::models::money_flow::{TradeDirection, Transaction};
+use crate::models::quote::{KLine, KLinePeriod, Quote};
+use crate::models::stock::Market;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use csv::StringRecord;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 本地 CSV 数据源
+#[derive(Debug, Clone)]
+pub struct CsvDataSource {
+    root: PathBuf,
+}
+
+impl CsvDataSource {
+    /// 创建数据源，`root` 为样本根目录
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 样本根目录
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 读取整张表
+    async fn read_table(&self, relative: impl AsRef<Path>) -> Result<Table> {
+        let path = self.root.join(relative);
+        let bytes = match tokio::fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AppError::NotFound(format!("CSV 文件不存在: {}", path.display())));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(bytes.as_slice());
+        let csv_error = |e: csv::Error| AppError::Parse(format!("{}: {}", path.display(), e));
+
+        let headers = reader.headers().map_err(csv_error)?.clone();
+        let rows = reader
+            .records()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(csv_error)?;
+
+        Ok(Table { path, headers, rows })
+    }
+
+    /// 最新一个逐笔成交日期
+    async fn latest_transaction_date(&self, code: &str) -> Result<NaiveDate> {
+        let dir = self.root.join("transactions").join(code);
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AppError::NotFound(format!("{} 无逐笔成交样本", code)));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut latest = None;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let date = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| NaiveDate::parse_from_str(s, "%Y%m%d").ok());
+            if path.extension().is_some_and(|e| e == "csv") {
+                latest = latest.max(date);
+            }
+        }
+
+        latest.ok_or_else(|| AppError::NotFound(format!("{} 无逐笔成交样本", code)))
+    }
+}
+
+#[async_trait]
+impl DataSource for CsvDataSource {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    async fn get_all_securities(&self, market: Market) -> Result<Vec<SecurityInfo>> {
+        let table = self.read_table("securities.csv").await?;
+        let mut securities = Vec::new();
+
+        for row in table.rows() {
+            if row.parse_with("market", parse_market)? != market {
+                continue;
+            }
+            securities.push(SecurityInfo {
+                market,
+                code: row.get("code")?.to_string(),
+                name: row.get("name")?.to_string(),
+                volunit: row.optional("volunit")?.unwrap_or(100),
+                decimal_point: row.optional("decimal_point")?.unwrap_or(2),
+                pre_close: row.optional("pre_close")?.unwrap_or(0.0),
+            });
+        }
+
+        Ok(securities)
+    }
+
+    async fn get_finance_info(&self, market: Market, code: &str) -> Result<FinanceInfo> {
+        let table = self.read_table("finance.csv").await?;
+
+        for row in table.rows() {
+            if row.get("code")? != code || row.parse_with("market", parse_market)? != market {
+                continue;
+            }
+            return Ok(FinanceInfo {
+                market,
+                code: code.to_string(),
+                list_date: row.optional_with("list_date", parse_date)?,
+                updated_date: row.optional_with("updated_date", parse_date)?,
+                total_shares: row.parse("total_shares")?,
+                float_shares: row.parse("float_shares")?,
+            });
+        }
+
+        Err(AppError::NotFound(format!("{} 无财务数据样本", code)))
+    }
+
+    async fn get_quotes(&self, codes: &[String]) -> Result<Vec<Quote>> {
+        let table = self.read_table("quotes.csv").await?;
+        let mut quotes = HashMap::new();
+
+        for row in table.rows() {
+            let levels = |prefix: &str| -> Result<[f64; 5]> {
+                let mut values = [0.0; 5];
+                for (i, value) in values.iter_mut().enumerate() {
+                    *value = row.optional(&format!("{}{}", prefix, i + 1))?.unwrap_or(0.0);
+                }
+                Ok(values)
+            };
+
+            let quote = Quote {
+                code: row.get("code")?.to_string(),
+                name: row.get("name")?.to_string(),
+                price: row.parse("price")?,
+                preclose: row.parse("preclose")?,
+                open: row.parse("open")?,
+                high: row.parse("high")?,
+                low: row.parse("low")?,
+                volume: row.parse("volume")?,
+                amount: row.parse("amount")?,
+                bid: levels("bid")?,
+                bid_vol: levels("bid_vol")?,
+                ask: levels("ask")?,
+                ask_vol: levels("ask_vol")?,
+                timestamp: row.parse_with("timestamp", parse_datetime)?,
+            };
+            quotes.insert(quote.code.clone(), quote);
+        }
+
+        Ok(codes.iter().filter_map(|code| quotes.remove(code)).collect())
+    }
+
+    async fn get_kline(
+        &self,
+        code: &str,
+        period: KLinePeriod,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<KLine>> {
+        let relative = Path::new("kline").join(period_dir(period)).join(format!("{}.csv", code));
+        let table = self.read_table(relative).await?;
+        let mut bars = Vec::new();
+
+        for row in table.rows() {
+            let datetime = row.parse_with("datetime", parse_datetime)?;
+            let date = clock::local_date(datetime);
+            if date < start || date > end {
+                continue;
+            }
+            bars.push(KLine {
+                datetime,
+                code: code.to_string(),
+                open: row.parse("open")?,
+                high: row.parse("high")?,
+                low: row.parse("low")?,
+                close: row.parse("close")?,
+                volume: row.parse("volume")?,
+                amount: row.parse("amount")?,
+            });
+        }
+
+        bars.sort_by_key(|bar| bar.datetime);
+        Ok(bars)
+    }
+
+    async fn get_transactions(&self, code: &str, date: Option<NaiveDate>) -> Result<Vec<Transaction>> {
+        let date = match date {
+            Some(date) => date,
+            None => self.latest_transaction_date(code).await?,
+        };
+        let relative = Path::new("transactions")
+            .join(code)
+            .join(format!("{}.csv", date.format("%Y%m%d")));
+        let table = self.read_table(relative).await?;
+        let mut transactions = Vec::new();
+
+        for row in table.rows() {
+            let time = row.parse_with("time", parse_time)?;
+            transactions.push(Transaction {
+                code: code.to_string(),
+                datetime: clock::to_utc(date.and_time(time)),
+                price: row.parse("price")?,
+                volume: row.parse("volume")?,
+                amount: row.parse("amount")?,
+                num: row.optional("num")?.unwrap_or(0),
+                direction: row.parse_with("direction", parse_direction)?,
+            });
+        }
+
+        transactions.sort_by_key(|t| t.datetime);
+        Ok(transactions)
+    }
+
+    async fn get_auctions(&self, codes: &[String]) -> Result<Vec<Auction>> {
+        let table = self.read_table("auctions.csv").await?;
+        let mut auctions = HashMap::new();
+
+        for row in table.rows() {
+            let auction = Auction {
+                code: row.get("code")?.to_string(),
+                name: row.get("name")?.to_string(),
+                price: row.parse("price")?,
+                preclose: row.parse("preclose")?,
+                volume: row.parse("volume")?,
+                amount: row.parse("amount")?,
+                timestamp: row.parse_with("timestamp", parse_datetime)?,
+            };
+            auctions.insert(auction.code.clone(), auction);
+        }
+
+        Ok(codes.iter().filter_map(|code| auctions.remove(code)).collect())
+    }
+}
+
+/// 已读入内存的 CSV 表
+struct Table {
+    path: PathBuf,
+    headers: StringRecord,
+    rows: Vec<StringRecord>,
+}
+
+impl Table {
+    fn rows(&self) -> impl Iterator<Item = Row<'_>> {
+        self.rows.iter().map(move |record| Row { table: self, record })
+    }
+}
+
+/// 按列名取值的行视图
+struct Row<'a> {
+    table: &'a Table,
+    record: &'a StringRecord,
+}
+
+impl Row<'_> {
+    /// 取列值，列缺失或为空时返回 None
+    fn field(&self, column: &str) -> Option<&str> {
+        let index = self.table.headers.iter().position(|h| h == column)?;
+        self.record.get(index).filter(|v| !v.is_empty())
+    }
+
+    /// 取必填列
+    fn get(&self, column: &str) -> Result<&str> {
+        self.field(column).ok_or_else(|| self.error(column, "缺少必填列"))
+    }
+
+    /// 按 `FromStr` 解析必填列
+    fn parse<T: FromStr>(&self, column: &str) -> Result<T> {
+        self.parse_with(column, |v| v.parse().ok())
+    }
+
+    /// 按 `FromStr` 解析可选列
+    fn optional<T: FromStr>(&self, column: &str) -> Result<Option<T>> {
+        self.field(column)
+            .map(|v| v.parse().map_err(|_| self.error(column, v)))
+            .transpose()
+    }
+
+    /// 用自定义函数解析可选列
+    fn optional_with<T>(&self, column: &str, parse: impl FnOnce(&str) -> Option<T>) -> Result<Option<T>> {
+        self.field(column)
+            .map(|v| parse(v).ok_or_else(|| self.error(column, v)))
+            .transpose()
+    }
+
+    /// 用自定义函数解析必填列
+    fn parse_with<T>(&self, column: &str, parse: impl FnOnce(&str) -> Option<T>) -> Result<T> {
+        let value = self.get(column)?;
+        parse(value).ok_or_else(|| self.error(column, value))
+    }
+
+    fn error(&self, column: &str, detail: &str) -> AppError {
+        let line = self.record.position().map_or(0, |p| p.line());
+        AppError::Parse(format!(
+            "{} 第 {} 行 {} 列解析失败: {}",
+            self.table.path.display(),
+            line,
+            column,
+            detail
+        ))
+    }
+}
+
+/// K 线周期对应的目录名
+fn period_dir(period: KLinePeriod) -> &'static str {
+    match period {
+        KLinePeriod::Minute1 => "1min",
+        KLinePeriod::Minute5 => "5min",
+        KLinePeriod::Day => "day",
+        KLinePeriod::Week => "week",
+        KLinePeriod::Month => "month",
+    }
+}
+
+fn parse_market(value: &str) -> Option<Market> {
+    match value.to_ascii_uppercase().as_str() {
+        "SH" => Some(Market::SH),
+        "SZ" => Some(Market::SZ),
+        "BJ" => Some(Market::BJ),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+/// 北京时间 `%Y-%m-%d %H:%M[:%S]`，只有日期时取 15:00
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(15, 0, 0))
+        })?;
+    Some(clock::to_utc(local))
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M"))
+        .ok()
+}
+
+fn parse_direction(value: &str) -> Option<TradeDirection> {
+    match value.to_ascii_uppercase().as_str() {
+        "B" => Some(TradeDirection::Buy),
+        "S" => Some(TradeDirection::Sell),
+        "N" => Some(TradeDirection::Neutral),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn fixture_source() -> CsvDataSource {
+    CsvDataSource::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/csv"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_securities_by_market() {
+        let source = fixture_source();
+
+        let sh = source.get_all_securities(Market::SH).await.unwrap();
+        assert!(sh.iter().all(|s| s.market == Market::SH));
+        assert!(sh.iter().any(|s| s.code == "000001" && s.name == "上证指数"));
+
+        let sz = source.get_all_securities(Market::SZ).await.unwrap();
+        let pingan = sz.iter().find(|s| s.code == "000001").unwrap();
+        assert_eq!(pingan.name, "平安银行");
+        assert_eq!(pingan.volunit, 100);
+    }
+
+    #[tokio::test]
+    async fn test_finance_info() {
+        let source = fixture_source();
+
+        let info = source.get_finance_info(Market::SZ, "300750").await.unwrap();
+        assert_eq!(info.list_date, Some(date(2018, 6, 11)));
+        assert!(info.float_shares < info.total_shares);
+
+        let missing = source.get_finance_info(Market::SH, "000001").await;
+        assert!(matches!(missing, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_quotes_keep_request_order() {
+        let source = fixture_source();
+        let codes = vec!["600036".to_string(), "999999".to_string(), "000001".to_string()];
+
+        let quotes = source.get_quotes(&codes).await.unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].code, "600036");
+        assert_eq!(quotes[1].code, "000001");
+        assert_eq!(quotes[1].bid[0], 10.49);
+        assert_eq!(quotes[1].ask_vol[4], 5000.0);
+    }
+
+    #[tokio::test]
+    async fn test_kline_range() {
+        let source = fixture_source();
+
+        let bars = source
+            .get_kline("600000", KLinePeriod::Day, date(2024, 6, 4), date(2024, 6, 6))
+            .await
+            .unwrap();
+        assert_eq!(bars.len(), 3);
+        assert_eq!(clock::local_date(bars[0].datetime), date(2024, 6, 4));
+        assert_eq!(clock::to_local(bars[0].datetime).hour(), 15);
+
+        let minutes = source
+            .get_kline("600000", KLinePeriod::Minute1, date(2024, 6, 7), date(2024, 6, 7))
+            .await
+            .unwrap();
+        assert!(minutes.windows(2).all(|w| w[0].datetime < w[1].datetime));
+
+        let missing = source
+            .get_kline("600036", KLinePeriod::Day, date(2024, 6, 3), date(2024, 6, 7))
+            .await;
+        assert!(matches!(missing, Err(AppError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_transactions() {
+        let source = fixture_source();
+
+        // 未指定日期取最新文件
+        let today = source.get_transactions("000001", None).await.unwrap();
+        assert_eq!(clock::local_date(today[0].datetime), date(2024, 6, 7));
+        assert_eq!(today[0].direction, TradeDirection::Neutral);
+        assert!(today.iter().any(|t| t.direction == TradeDirection::Sell));
+
+        let history = source
+            .get_transactions("000001", Some(date(2024, 6, 6)))
+            .await
+            .unwrap();
+        assert!(history.iter().all(|t| clock::local_date(t.datetime) == date(2024, 6, 6)));
+        assert_eq!(history[0].num, 0);
+    }
+
+    #[tokio::test]
+    async fn test_auctions() {
+        let source = fixture_source();
+        let auctions = source.get_auctions(&["000001".to_string()]).await.unwrap();
+
+        assert_eq!(auctions.len(), 1);
+        assert_eq!(auctions[0].preclose, 10.0);
+        assert_eq!(clock::to_local(auctions[0].timestamp).minute(), 25);
+    }
+}
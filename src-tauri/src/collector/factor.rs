@@ -0,0 +1,384 @@
+//! 复权因子计算 - 由日线与股本变迁记录生成 `factor` 表数据
+//!
+//! 除权日参考昨收（除权价）：
+//!
+//! ```text
+//! preclose = (前收盘 - 每股派现 + 每股配股 × 配股价) / (1 + 每股送转 + 每股配股)
+//! ```
+//!
+//! 复权因子采用后复权累乘口径：首日为 1，每遇除权日乘以 `前收盘 / 除权价`。
+//! 后复权价 = 原始价 × factor；前复权价 = 原始价 × factor / 最新 factor。
+
+use crate::collector::clock;
+use crate::collector::parser::{DayData, GbbqEvent, GbbqRecord};
+use crate::collector::validator::QualityScore;
+use crate::models::quote::{AdjustMode, KLine};
+use crate::models::stock::{Market, SecurityType};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// `kaipanla.factor` 表的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactorData {
+    pub date: NaiveDate,
+    pub code: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub preclose: f64,  // 昨收（除权日为除权参考价）
+    pub factor: f64,    // 后复权因子
+    pub volume: f64,
+    pub amount: f64,
+    #[serde(default)]
+    pub data_source: FactorSource,
+    #[serde(default = "first_version")]
+    pub data_version: u32,  // 数据版本号，对账后内容变化时递增
+    #[serde(default)]
+    pub quality_score: QualityScore,  // 采用值的验证结果
+}
+
+/// 日线数据来源（与 `factor.data_source` 一致）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FactorSource {
+    #[default]
+    Api = 1,     // 行情接口（通达信服务器等）
+    File = 2,    // 本地文件（通达信 .day 等）
+    Manual = 3,  // 人工修正
+}
+
+impl FactorSource {
+    /// 由 ClickHouse 枚举值转换
+    pub fn from_value(value: i8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Api),
+            2 => Some(Self::File),
+            3 => Some(Self::Manual),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Api => "api",
+            Self::File => "file",
+            Self::Manual => "manual",
+        }
+    }
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+/// 单次除权除息（每股口径）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExRight {
+    pub date: NaiveDate,
+    pub cash: f64,
+    pub rights_price: f64,
+    pub bonus: f64,
+    pub rights: f64,
+}
+
+impl ExRight {
+    /// 由 gbbq 记录转换，非除权除息记录返回 None
+    pub fn from_gbbq(record: &GbbqRecord) -> Option<Self> {
+        match record.event {
+            GbbqEvent::Dividend { cash, rights_price, bonus, rights } => Some(Self {
+                date: record.date,
+                cash: cash / 10.0,
+                rights_price,
+                bonus: bonus / 10.0,
+                rights: rights / 10.0,
+            }),
+            GbbqEvent::ShareChange { .. } => None,
+        }
+    }
+
+    /// 根据前收盘计算除权参考价
+    pub fn ex_price(&self, close: f64) -> f64 {
+        (close - self.cash + self.rights * self.rights_price) / (1.0 + self.bonus + self.rights)
+    }
+}
+
+/// 复权因子计算器
+pub struct FactorCalculator;
+
+impl FactorCalculator {
+    /// 计算每日复权因子与昨收
+    ///
+    /// `days` 为 `market` 市场同一证券按日期升序的日线；`events` 可包含其他证券或股本变动记录，
+    /// 会按市场、代码和类别过滤（同一代码在沪深两市可能是不同证券）。除权参考价按证券类型的
+    /// 价格精度取整。停牌期间的除权事件落到复牌后的第一个交易日；首日没有前收盘，昨收取开盘价。
+    pub fn calculate(market: Market, days: &[DayData], events: &[GbbqRecord]) -> Vec<FactorData> {
+        let Some(first) = days.first() else {
+            return Vec::new();
+        };
+        let decimals = SecurityType::classify(market, &first.code).map_or(2, |kind| kind.price_decimals());
+
+        let mut ex_rights: Vec<ExRight> = events
+            .iter()
+            .filter(|e| e.market == market && e.code == first.code)
+            .filter_map(ExRight::from_gbbq)
+            .collect();
+        ex_rights.sort_by_key(|e| e.date);
+
+        // 跳过首日及之前的事件，它们已体现在首日价格中
+        let mut pending = ex_rights.iter().skip_while(|e| e.date <= first.date).peekable();
+
+        let mut factor = 1.0;
+        let mut result: Vec<FactorData> = Vec::with_capacity(days.len());
+
+        for day in days {
+            let preclose = match result.last() {
+                None => day.open,
+                Some(prev) => {
+                    let mut preclose = prev.close;
+                    while let Some(event) = pending.next_if(|e| e.date <= day.date) {
+                        preclose = round_price(event.ex_price(preclose), decimals);
+                    }
+                    if preclose > 0.0 && preclose != prev.close {
+                        factor *= prev.close / preclose;
+                    }
+                    preclose
+                }
+            };
+
+            result.push(FactorData {
+                date: day.date,
+                code: day.code.clone(),
+                open: day.open,
+                high: day.high,
+                low: day.low,
+                close: day.close,
+                preclose,
+                factor,
+                volume: day.volume,
+                amount: day.amount,
+                data_source: FactorSource::default(),
+                data_version: 1,
+                quality_score: QualityScore::Good,
+            });
+        }
+
+        result
+    }
+}
+
+/// 对 K 线序列复权
+///
+/// `factors` 为同一证券按日期升序的全部复权因子，每根 K 线取其北京日期当日或之前最近一日的因子，
+/// 早于首条因子的 K 线按 1 处理。前复权以 `factors` 中最新的因子为基准，因此最新价格不变。
+/// 成交量、成交额不做调整。
+pub fn adjust_klines(bars: &mut [KLine], factors: &[FactorData], mode: AdjustMode) {
+    let Some(latest) = factors.last() else {
+        return;
+    };
+
+    let base = match mode {
+        AdjustMode::None => return,
+        AdjustMode::Forward => latest.factor,
+        AdjustMode::Backward => 1.0,
+    };
+
+    for bar in bars.iter_mut() {
+        let date = clock::local_date(bar.datetime);
+        let index = factors.partition_point(|f| f.date <= date);
+        let factor = match index {
+            0 => 1.0,
+            i => factors[i - 1].factor,
+        };
+
+        let ratio = factor / base;
+        bar.open *= ratio;
+        bar.high *= ratio;
+        bar.low *= ratio;
+        bar.close *= ratio;
+    }
+}
+
+/// 按交易所最小价位保留 `decimals` 位小数
+fn round_price(price: f64, decimals: u32) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    (price * scale).round() / scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: (i32, u32, u32), close: f64) -> DayData {
+        DayData {
+            date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+            code: "600000".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000.0,
+            amount: close * 1000.0,
+        }
+    }
+
+    fn dividend(code: &str, date: (i32, u32, u32), cash: f64, bonus: f64) -> GbbqRecord {
+        GbbqRecord {
+            market: Market::SH,
+            code: code.to_string(),
+            date: NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap(),
+            category: 1,
+            event: GbbqEvent::Dividend { cash, rights_price: 0.0, bonus, rights: 0.0 },
+        }
+    }
+
+    #[test]
+    fn test_no_events() {
+        let days = vec![day((2024, 1, 2), 10.0), day((2024, 1, 3), 10.5)];
+        let rows = FactorCalculator::calculate(Market::SH, &days, &[]);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].preclose, 10.0);
+        assert_eq!(rows[1].preclose, 10.0);
+        assert!(rows.iter().all(|r| r.factor == 1.0));
+    }
+
+    #[test]
+    fn test_ex_dividend_and_bonus() {
+        let days = vec![
+            day((2024, 6, 3), 10.0),
+            day((2024, 6, 4), 10.0),
+            day((2024, 6, 5), 5.0),
+            day((2024, 6, 6), 5.1),
+        ];
+        // 10 派 2 送 10：除权价 (10 - 0.2) / 2 = 4.9
+        let events = vec![
+            dividend("600000", (2024, 6, 5), 2.0, 10.0),
+            dividend("000001", (2024, 6, 4), 5.0, 0.0),
+        ];
+
+        let rows = FactorCalculator::calculate(Market::SH, &days, &events);
+        assert_eq!(rows[2].preclose, 4.9);
+        assert_eq!(rows[1].factor, 1.0);
+        assert!((rows[2].factor - 10.0 / 4.9).abs() < 1e-12);
+        assert_eq!(rows[3].factor, rows[2].factor);
+        assert_eq!(rows[3].preclose, 5.0);
+
+        // 后复权价连续：除权日前后涨跌与原始涨跌幅一致
+        let adj_prev = rows[1].close * rows[1].factor;
+        let adj_ex = rows[2].close * rows[2].factor;
+        assert!(((adj_ex / adj_prev) - (5.0 / 4.9)).abs() < 1e-12);
+    }
+
+    fn kline(date: NaiveDate, hour: u32, close: f64) -> KLine {
+        KLine {
+            datetime: clock::to_utc(date.and_hms_opt(hour, 0, 0).unwrap()),
+            code: "600000".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000.0,
+            amount: close * 1000.0,
+        }
+    }
+
+    /// 合成除权序列：6-05 10 派 10 元（除权价 9.0），6-07 10 送 10（除权价 4.6）
+    fn ex_dividend_series() -> (Vec<KLine>, Vec<FactorData>) {
+        let closes = [((2024, 6, 3), 10.0), ((2024, 6, 4), 10.0), ((2024, 6, 5), 9.2), ((2024, 6, 6), 9.2), ((2024, 6, 7), 4.7)];
+        let days: Vec<DayData> = closes.iter().map(|&(d, c)| day(d, c)).collect();
+        let events = vec![
+            dividend("600000", (2024, 6, 5), 10.0, 0.0),
+            dividend("600000", (2024, 6, 7), 0.0, 10.0),
+        ];
+        let factors = FactorCalculator::calculate(Market::SH, &days, &events);
+        let bars = days.iter().map(|d| kline(d.date, 15, d.close)).collect();
+        (bars, factors)
+    }
+
+    #[test]
+    fn test_adjust_none() {
+        let (mut bars, factors) = ex_dividend_series();
+        let raw: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        adjust_klines(&mut bars, &factors, AdjustMode::None);
+        assert_eq!(bars.iter().map(|b| b.close).collect::<Vec<_>>(), raw);
+    }
+
+    #[test]
+    fn test_adjust_forward() {
+        let (mut bars, factors) = ex_dividend_series();
+        assert_eq!(factors[2].preclose, 9.0);
+        assert_eq!(factors[4].preclose, 4.6);
+
+        adjust_klines(&mut bars, &factors, AdjustMode::Forward);
+
+        // 最新价格不变，历史价格按两次除权连续折算
+        assert!((bars[4].close - 4.7).abs() < 1e-9);
+        assert!((bars[3].close - 4.6).abs() < 1e-9);
+        assert!((bars[1].close - 10.0 * 0.9 * 0.5).abs() < 1e-9);
+        assert!((bars[0].close - bars[1].close).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_adjust_backward() {
+        let (mut bars, factors) = ex_dividend_series();
+        adjust_klines(&mut bars, &factors, AdjustMode::Backward);
+
+        // 首日价格不变，除权日涨跌幅与按昨收计算一致
+        assert_eq!(bars[0].close, 10.0);
+        assert!((bars[2].close / bars[1].close - 9.2 / 9.0).abs() < 1e-9);
+        assert!((bars[4].close / bars[3].close - 4.7 / 4.6).abs() < 1e-9);
+        assert_eq!(bars[4].volume, 1000.0);
+    }
+
+    #[test]
+    fn test_adjust_intraday_uses_local_date() {
+        let (_, factors) = ex_dividend_series();
+        // 北京时间 6-05 09:31 属于除权日
+        let date = NaiveDate::from_ymd_opt(2024, 6, 5).unwrap();
+        let mut bars = vec![kline(date, 9, 9.1)];
+        adjust_klines(&mut bars, &factors, AdjustMode::Backward);
+        assert!((bars[0].close - 9.1 * 10.0 / 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_event_during_suspension() {
+        // 6-05 停牌，除权落到 6-06
+        let days = vec![day((2024, 6, 4), 10.0), day((2024, 6, 6), 9.5)];
+        let events = vec![dividend("600000", (2024, 6, 5), 5.0, 0.0)];
+
+        let rows = FactorCalculator::calculate(Market::SH, &days, &events);
+        assert_eq!(rows[1].preclose, 9.5);
+        assert!((rows[1].factor - 10.0 / 9.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_events_filtered_by_market() {
+        // 上证指数 000001 与平安银行 000001 代码相同
+        let index: Vec<DayData> = [((2024, 6, 3), 3000.0), ((2024, 6, 4), 3010.0)]
+            .iter()
+            .map(|&(d, c)| DayData { code: "000001".to_string(), ..day(d, c) })
+            .collect();
+        let mut event = dividend("000001", (2024, 6, 4), 5.0, 0.0);
+        event.market = Market::SZ;
+
+        let rows = FactorCalculator::calculate(Market::SH, &index, std::slice::from_ref(&event));
+        assert!(rows.iter().all(|r| r.factor == 1.0));
+        let rows = FactorCalculator::calculate(Market::SZ, &index, &[event]);
+        assert!(rows[1].factor > 1.0);
+    }
+
+    #[test]
+    fn test_etf_ex_price_keeps_three_decimals() {
+        let days: Vec<DayData> = [((2024, 6, 3), 1.85), ((2024, 6, 4), 1.84)]
+            .iter()
+            .map(|&(d, c)| DayData { code: "159915".to_string(), ..day(d, c) })
+            .collect();
+        // 10 派 0.12 元：除权价 1.838（按两位取整会得到 1.84）
+        let mut event = dividend("159915", (2024, 6, 4), 0.12, 0.0);
+        event.market = Market::SZ;
+
+        let rows = FactorCalculator::calculate(Market::SZ, &days, &[event]);
+        assert_eq!(rows[1].preclose, 1.838);
+    }
+}
@@ -0,0 +1,114 @@
+//! 行情事件流 - 采集器（或回放）发布，行情服务与 WebSocket 推送订阅
+
+use crate::models::auction::Auction;
+use crate::models::money_flow::Transaction;
+use crate::models::quote::Quote;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// 默认缓冲事件数，订阅者落后超过该数量时丢弃最旧的事件
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// 行情事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum MarketEvent {
+    Quote(Quote),              // 实时行情快照
+    Transaction(Transaction),  // 逐笔成交
+    Auction(Auction),          // 集合竞价快照
+}
+
+impl MarketEvent {
+    /// 证券代码
+    pub fn code(&self) -> &str {
+        match self {
+            MarketEvent::Quote(q) => &q.code,
+            MarketEvent::Transaction(t) => &t.code,
+            MarketEvent::Auction(a) => &a.code,
+        }
+    }
+
+    /// 推送频道名
+    pub fn channel(&self) -> &'static str {
+        match self {
+            MarketEvent::Quote(_) => "quote",
+            MarketEvent::Transaction(_) => "transaction",
+            MarketEvent::Auction(_) => "auction",
+        }
+    }
+}
+
+/// 行情事件广播
+#[derive(Debug, Clone)]
+pub struct MarketFeed {
+    sender: broadcast::Sender<MarketEvent>,
+}
+
+impl MarketFeed {
+    /// 创建事件流，`capacity` 为每个订阅者的缓冲上限
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// 发布事件，返回收到事件的订阅者数（无订阅者时丢弃）
+    pub fn publish(&self, event: MarketEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// 订阅事件流（只接收订阅之后发布的事件）
+    pub fn subscribe(&self) -> broadcast::Receiver<MarketEvent> {
+        self.sender.subscribe()
+    }
+
+    /// 当前订阅者数
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for MarketFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn auction(code: &str) -> MarketEvent {
+        MarketEvent::Auction(Auction {
+            code: code.to_string(),
+            name: "平安银行".to_string(),
+            price: 10.2,
+            preclose: 10.0,
+            volume: 5200.0,
+            amount: 5304000.0,
+            timestamp: Utc::now(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_publish_subscribe() {
+        let feed = MarketFeed::default();
+        assert_eq!(feed.publish(auction("000001")), 0);
+
+        let mut rx = feed.subscribe();
+        assert_eq!(feed.publish(auction("600036")), 1);
+
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.code(), "600036");
+        assert_eq!(event.channel(), "auction");
+    }
+
+    #[test]
+    fn test_event_serde_tag() {
+        let json = serde_json::to_string(&auction("000001")).unwrap();
+        assert!(json.starts_with(r#"{"type":"auction","data":{"#));
+
+        let event: MarketEvent = serde_json::from_str(&json).unwrap();
+        assert!(matches!(event, MarketEvent::Auction(_)));
+    }
+}
@@ -6,18 +6,50 @@
 //! - 批次管理：100股票×30天/批
 //! - 断点续传：记录导入进度，支持中断恢复
 //! - 用户取消：随时可以取消导入任务
+//! - 并发导入：有界工作池并发请求（每台服务器的请求预算由连接池控制，见 `TdxClient::with_server_budget`）
+//! - 暂停恢复：暂停期间不再发出新请求，恢复后从原处继续
+//! - 优先通道：自选股、近期活跃股票先于其余股票导入
+//!
+//! 每只股票的一个日期区间是一个导入单元。单元按批次顺序提交给工作池，最多
+//! `concurrency` 个同时进行；完成结果按提交顺序处理，批次只有在它之前的单元全部
+//! 完成后才计入进度，因此保存的进度不会越过未完成的单元。
+//!
+//! 配置本地数据库后，每次成功请求都记入 [`CoverageIndex`]。各阶段按交易日历计算
+//! 每只股票尚未覆盖的交易日，只请求这些缺口，因此每日补数与断线后修复都只请求
+//! 缺少的部分，重复导入不会重复请求。整体进度在每批次后保存，重启后调用
+//! [`HistoryImporter::restore`] 读回进度，再次 `start_import` 时沿用中断前的截止日期。
+//! 进度每次变化都会推送给 [`HistoryImporter::subscribe`] 的订阅者。
+//!
+//! 日终任务 [`DailyBarJob`] 以当日为截止日期对仍在交易的股票执行一次导入，即每日补数。
 
-use crate::collector::tdx::TdxClient;
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::collector::coverage::{self, CoverageIndex};
+use crate::collector::jobs::DailyJob;
+use crate::collector::session::SessionPhase;
+use crate::collector::source::DataSource;
+use crate::collector::writer::{BatchSink, LogSink};
+use crate::db::LocalDb;
 use crate::error::{AppError, Result};
+use crate::models::quote::{KLine, KLinePeriod};
 use crate::models::stock::Stock;
-use chrono::{Duration, Utc};
+use crate::service::SecurityService;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use futures_util::stream::{self, StreamExt};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::{broadcast, watch, Notify};
 use tracing::{debug, info, warn};
 
+/// 默认并发请求数
+const DEFAULT_CONCURRENCY: usize = 8;
+
 /// 导入进度跟踪
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportProgress {
     pub total_stocks: usize,        // 总股票数
     pub imported_stocks: usize,     // 已导入股票数
@@ -28,11 +60,41 @@ pub struct ImportProgress {
     pub end_date: String,           // 结束日期
     pub stage: ImportStage,         // 当前阶段
     pub is_running: bool,           // 是否正在运行
+    #[serde(default)]
+    pub is_paused: bool,            // 是否已暂停
     pub error_count: usize,         // 错误计数
 }
 
+/// 导入优先通道，按先后顺序导入，未列出的股票最后导入
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportLanes {
+    #[serde(default)]
+    pub watchlist: Vec<String>,         // 自选股
+    #[serde(default)]
+    pub recently_active: Vec<String>,   // 近期活跃股票
+}
+
+impl ImportLanes {
+    /// 按通道重排：自选股、近期活跃股票各按通道内顺序，其余保持原顺序
+    pub fn prioritize(&self, stocks: Vec<Stock>) -> Vec<Stock> {
+        let rank = |code: &str| {
+            self.watchlist
+                .iter()
+                .position(|c| c == code)
+                .map(|i| (0, i))
+                .or_else(|| self.recently_active.iter().position(|c| c == code).map(|i| (1, i)))
+                .unwrap_or((2, 0))
+        };
+
+        let mut stocks = stocks;
+        stocks.sort_by_key(|stock| rank(&stock.code));
+        stocks
+    }
+}
+
 /// 导入阶段
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ImportStage {
     Idle,                   // 空闲
     ImportingRecent,        // 导入最近1个月
@@ -42,38 +104,189 @@ pub enum ImportStage {
     Cancelled,              // 已取消
 }
 
+impl ImportStage {
+    /// 存储名称（与 migrations/003 的枚举一致）
+    fn as_str(self) -> &'static str {
+        match self {
+            ImportStage::Idle => "idle",
+            ImportStage::ImportingRecent => "importing_recent",
+            ImportStage::ImportingHistory => "importing_history",
+            ImportStage::Completed => "completed",
+            ImportStage::Failed => "failed",
+            ImportStage::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [
+            ImportStage::Idle,
+            ImportStage::ImportingRecent,
+            ImportStage::ImportingHistory,
+            ImportStage::Completed,
+            ImportStage::Failed,
+            ImportStage::Cancelled,
+        ]
+        .into_iter()
+        .find(|stage| stage.as_str() == value)
+    }
+
+    /// 是否为未完成的导入（可续传）
+    pub fn is_unfinished(self) -> bool {
+        matches!(
+            self,
+            ImportStage::ImportingRecent | ImportStage::ImportingHistory | ImportStage::Failed | ImportStage::Cancelled
+        )
+    }
+}
+
+impl Default for ImportProgress {
+    fn default() -> Self {
+        Self {
+            total_stocks: 0,
+            imported_stocks: 0,
+            total_batches: 0,
+            imported_batches: 0,
+            current_code: String::new(),
+            start_date: String::new(),
+            end_date: String::new(),
+            stage: ImportStage::Idle,
+            is_running: false,
+            is_paused: false,
+            error_count: 0,
+        }
+    }
+}
+
 /// 历史数据导入器
 pub struct HistoryImporter {
-    tdx_client: Arc<TdxClient>,
+    source: Arc<dyn DataSource>,
+    sink: Arc<dyn BatchSink<KLine>>,
+    stores_bars: bool,                           // 是否设置了写入目标（缺省的 LogSink 丢弃数据）
+    local_db: Option<Arc<LocalDb>>,
+    coverage: Option<CoverageIndex>,
+    calendar: Arc<TradingCalendar>,
+    end_date: Option<NaiveDate>,                 // 导入截止日期，缺省为今天
+    resume_anchor: std::sync::Mutex<Option<NaiveDate>>,  // 续传时沿用的截止日期
     progress: Arc<tokio::sync::RwLock<ImportProgress>>,
     is_cancelled: Arc<AtomicBool>,
+    cancel_notify: Arc<Notify>,
+    paused: watch::Sender<bool>,
+    lanes: std::sync::RwLock<ImportLanes>,
+    events: broadcast::Sender<ImportProgress>,
     batch_size: usize,           // 每批次股票数（默认100）
     days_per_batch: i64,         // 每批次天数（默认30天）
+    concurrency: usize,          // 同时进行的请求数（默认8）
+}
+
+/// 导入单元：一只股票的一个缺失区间
+struct ImportUnit {
+    stock: Stock,
+    range: Option<(NaiveDate, NaiveDate)>,  // 为空表示该股票在本阶段已全部覆盖，只用于记账
+    closes_batch: bool,   // 是否为所在批次的最后一个单元
+    stocks_done: usize,   // 完成后计入已导入的股票数
 }
 
 impl HistoryImporter {
     /// 创建新的导入器
-    pub fn new(tdx_client: Arc<TdxClient>) -> Self {
+    pub fn new(source: Arc<dyn DataSource>) -> Self {
         Self {
-            tdx_client,
-            progress: Arc::new(tokio::sync::RwLock::new(ImportProgress {
-                total_stocks: 0,
-                imported_stocks: 0,
-                total_batches: 0,
-                imported_batches: 0,
-                current_code: String::new(),
-                start_date: String::new(),
-                end_date: String::new(),
-                stage: ImportStage::Idle,
-                is_running: false,
-                error_count: 0,
-            })),
+            source,
+            sink: Arc::new(LogSink),
+            stores_bars: false,
+            local_db: None,
+            coverage: None,
+            calendar: Arc::new(TradingCalendar::bundled()),
+            end_date: None,
+            resume_anchor: std::sync::Mutex::new(None),
+            progress: Arc::new(tokio::sync::RwLock::new(ImportProgress::default())),
             is_cancelled: Arc::new(AtomicBool::new(false)),
+            cancel_notify: Arc::new(Notify::new()),
+            paused: watch::Sender::new(false),
+            lanes: std::sync::RwLock::new(ImportLanes::default()),
+            events: broadcast::channel(64).0,
             batch_size: 100,
             days_per_batch: 30,
+            concurrency: DEFAULT_CONCURRENCY,
         }
     }
 
+    /// 设置 K 线写入目标
+    pub fn with_sink(mut self, sink: Arc<dyn BatchSink<KLine>>) -> Self {
+        self.sink = sink;
+        self.stores_bars = true;
+        self
+    }
+
+    /// 在本地数据库中保存进度与覆盖索引（覆盖索引需同时设置 `with_sink`）
+    pub fn with_local_db(mut self, db: Arc<LocalDb>) -> Self {
+        self.coverage = Some(CoverageIndex::new(db.clone()));
+        self.local_db = Some(db);
+        self
+    }
+
+    /// 设置计算缺失交易日使用的交易日历
+    pub fn with_calendar(mut self, calendar: Arc<TradingCalendar>) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// 设置导入截止日期（默认最近一个已收盘的交易日）
+    pub fn with_end_date(mut self, date: NaiveDate) -> Self {
+        self.end_date = Some(date);
+        self
+    }
+
+    /// 读回上次保存的进度，返回是否有未完成的导入
+    ///
+    /// 有未完成的导入时，下次 `start_import` 沿用其截止日期，使日期区间与断点一致。
+    pub async fn restore(&self) -> Result<bool> {
+        let Some(db) = &self.local_db else {
+            return Ok(false);
+        };
+
+        let saved = db.with_conn(|conn| {
+            conn.query_row(
+                "SELECT stage, total_stocks, imported_stocks, total_batches, imported_batches,
+                        current_code, start_date, end_date, anchor_date, error_count
+                 FROM import_progress WHERE id = 0",
+                [],
+                |row| {
+                    let stage: String = row.get(0)?;
+                    let anchor: String = row.get(8)?;
+                    let progress = ImportProgress {
+                        total_stocks: row.get::<_, i64>(1)? as usize,
+                        imported_stocks: row.get::<_, i64>(2)? as usize,
+                        total_batches: row.get::<_, i64>(3)? as usize,
+                        imported_batches: row.get::<_, i64>(4)? as usize,
+                        current_code: row.get(5)?,
+                        start_date: row.get(6)?,
+                        end_date: row.get(7)?,
+                        stage: ImportStage::parse(&stage).unwrap_or(ImportStage::Idle),
+                        is_running: false,
+                        is_paused: false,
+                        error_count: row.get::<_, i64>(9)? as usize,
+                    };
+                    Ok((progress, anchor.parse::<NaiveDate>().ok()))
+                },
+            )
+            .optional()
+        })?;
+
+        let Some((progress, anchor)) = saved else {
+            return Ok(false);
+        };
+        let unfinished = progress.stage.is_unfinished();
+        if unfinished {
+            info!(
+                "发现未完成的历史导入: {:?}，已完成 {}/{} 批",
+                progress.stage, progress.imported_batches, progress.total_batches
+            );
+            *self.resume_anchor.lock().expect("续传状态锁") = anchor;
+        }
+        *self.progress.write().await = progress;
+        Ok(unfinished)
+    }
+
     /// 开始导入历史数据
     ///
     /// 导入策略：
@@ -88,45 +301,43 @@ impl HistoryImporter {
             if progress.is_running {
                 return Err(AppError::Config("导入任务已在运行中".to_string()));
             }
-            progress.is_running = true;
-            progress.stage = ImportStage::ImportingRecent;
-            progress.total_stocks = stocks.len();
+            *progress = ImportProgress {
+                is_running: true,
+                stage: ImportStage::ImportingRecent,
+                total_stocks: stocks.len(),
+                ..ImportProgress::default()
+            };
+            self.publish(&progress);
         }
 
         self.is_cancelled.store(false, Ordering::SeqCst);
+        self.paused.send_replace(false);
+
+        // 续传时沿用中断前的截止日期
+        let anchor = self
+            .end_date
+            .or_else(|| self.resume_anchor.lock().expect("续传状态锁").take())
+            .unwrap_or_else(|| self.default_end_date());
 
         // Step 1: 导入最近1个月数据
         let recent_start = Instant::now();
-        match self.import_recent_month(stocks.clone()).await {
+        match self.import_recent_month(&stocks, anchor).await {
             Ok(_) => {
                 let duration = recent_start.elapsed();
                 info!("最近1个月数据导入完成，耗时: {:.2}s", duration.as_secs_f64());
             }
-            Err(e) => {
-                // 检查是否被取消
-                if self.is_cancelled.load(Ordering::SeqCst) {
-                    let mut progress = self.progress.write().await;
-                    progress.stage = ImportStage::Cancelled;
-                    progress.is_running = false;
-                    return Err(AppError::Config("导入已取消".to_string()));
-                }
-
-                warn!("最近1个月数据导入失败: {}", e);
-                let mut progress = self.progress.write().await;
-                progress.stage = ImportStage::Failed;
-                progress.is_running = false;
-                return Err(e);
-            }
+            Err(e) => return Err(self.finish_with_error(e, anchor).await),
         }
 
         // Step 2: 后台回填3年数据（如果没被取消）
         if !self.is_cancelled.load(Ordering::SeqCst) {
             let mut progress = self.progress.write().await;
             progress.stage = ImportStage::ImportingHistory;
+            self.publish(&progress);
         }
 
         let history_start = Instant::now();
-        match self.import_history_3years(stocks.clone()).await {
+        match self.import_history_3years(&stocks, anchor).await {
             Ok(_) => {
                 let duration = history_start.elapsed();
                 info!("3年历史数据导入完成，耗时: {:.2}s", duration.as_secs_f64());
@@ -135,29 +346,48 @@ impl HistoryImporter {
                 let mut progress = self.progress.write().await;
                 progress.stage = ImportStage::Completed;
                 progress.is_running = false;
+                progress.is_paused = false;
+                self.save_progress(&progress, anchor)?;
             }
-            Err(e) => {
-                if self.is_cancelled.load(Ordering::SeqCst) {
-                    let mut progress = self.progress.write().await;
-                    progress.stage = ImportStage::Cancelled;
-                    progress.is_running = false;
-                    return Err(AppError::Config("导入已取消".to_string()));
-                }
-
-                warn!("历史数据导入失败: {}", e);
-                let mut progress = self.progress.write().await;
-                progress.stage = ImportStage::Failed;
-                progress.is_running = false;
-                return Err(e);
-            }
+            Err(e) => return Err(self.finish_with_error(e, anchor).await),
         }
 
         Ok(())
     }
 
+    /// 最近一个已收盘的交易日（当日收盘前日线未定）
+    fn default_end_date(&self) -> NaiveDate {
+        let now = clock::now_local();
+        match SessionPhase::at(&self.calendar, now) {
+            SessionPhase::AfterClose => now.date(),
+            _ => self.calendar.prev_trading_day(now.date()),
+        }
+    }
+
+    /// 导入中止：区分取消与失败，保存进度以便续传
+    async fn finish_with_error(&self, e: AppError, anchor: NaiveDate) -> AppError {
+        let mut progress = self.progress.write().await;
+        progress.is_running = false;
+        progress.is_paused = false;
+
+        let e = if self.is_cancelled.load(Ordering::SeqCst) {
+            progress.stage = ImportStage::Cancelled;
+            AppError::Config("导入已取消".to_string())
+        } else {
+            warn!("历史数据导入失败: {}", e);
+            progress.stage = ImportStage::Failed;
+            e
+        };
+
+        if let Err(save_error) = self.save_progress(&progress, anchor) {
+            warn!("保存导入进度失败: {}", save_error);
+        }
+        e
+    }
+
     /// 导入最近1个月数据
-    async fn import_recent_month(&self, stocks: Vec<Stock>) -> Result<()> {
-        let end_date = Utc::now().date_naive();
+    async fn import_recent_month(&self, stocks: &[Stock], anchor: NaiveDate) -> Result<()> {
+        let end_date = anchor;
         let start_date = end_date - Duration::days(30);
 
         let start_str = start_date.format("%Y-%m-%d").to_string();
@@ -165,72 +395,24 @@ impl HistoryImporter {
 
         info!("导入最近1个月数据: {} 到 {}", start_str, end_str);
 
+        // 计算总批次数
+        let total_batches = stocks.len().div_ceil(self.batch_size);
         {
             let mut progress = self.progress.write().await;
-            progress.start_date = start_str.clone();
-            progress.end_date = end_str.clone();
+            progress.start_date = start_str;
+            progress.end_date = end_str;
             progress.imported_stocks = 0;
             progress.imported_batches = 0;
-        }
-
-        // 计算总批次数
-        let total_batches = (stocks.len() + self.batch_size - 1) / self.batch_size;
-        {
-            let mut progress = self.progress.write().await;
             progress.total_batches = total_batches;
         }
 
-        // 分批导入
-        for (batch_idx, batch) in stocks.chunks(self.batch_size).enumerate() {
-            // 检查是否取消
-            if self.is_cancelled.load(Ordering::SeqCst) {
-                return Err(AppError::Config("导入已取消".to_string()));
-            }
-
-            info!("导入批次 {}/{}，包含 {} 只股票", batch_idx + 1, total_batches, batch.len());
-
-            for stock in batch {
-                // 更新当前股票
-                {
-                    let mut progress = self.progress.write().await;
-                    progress.current_code = stock.code.clone();
-                }
-
-                // 获取历史数据
-                match self.tdx_client.get_daily_data(&stock.code, &start_str, &end_str).await {
-                    Ok(klines) => {
-                        debug!("获取 {} 的K线数据 {} 条", stock.code, klines.len());
-                        // TODO: 写入 ClickHouse
-                        // 在 Task 4 实现
-                    }
-                    Err(e) => {
-                        warn!("获取 {} 数据失败: {}", stock.code, e);
-                        let mut progress = self.progress.write().await;
-                        progress.error_count += 1;
-                    }
-                }
-            }
-
-            // 更新进度
-            {
-                let mut progress = self.progress.write().await;
-                progress.imported_batches = batch_idx + 1;
-                progress.imported_stocks = (batch_idx + 1) * self.batch_size;
-                if progress.imported_stocks > stocks.len() {
-                    progress.imported_stocks = stocks.len();
-                }
-            }
-
-            // 每批次之间稍微延迟，避免对服务器压力过大
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        }
-
-        Ok(())
+        let units = self.plan_units(stocks, start_date, end_date)?;
+        self.run_units(units, anchor).await
     }
 
     /// 导入3年历史数据（后台任务）
-    async fn import_history_3years(&self, stocks: Vec<Stock>) -> Result<()> {
-        let end_date = Utc::now().date_naive() - Duration::days(30); // 从1个月前开始
+    async fn import_history_3years(&self, stocks: &[Stock], anchor: NaiveDate) -> Result<()> {
+        let end_date = anchor - Duration::days(30); // 从1个月前开始
         let start_date = end_date - Duration::days(365 * 3); // 3年
 
         let start_str = start_date.format("%Y-%m-%d").to_string();
@@ -238,103 +420,252 @@ impl HistoryImporter {
 
         info!("导入3年历史数据: {} 到 {}", start_str, end_str);
 
+        // 计算总批次数
+        let total_batches = stocks.len().div_ceil(self.batch_size);
         {
             let mut progress = self.progress.write().await;
-            progress.start_date = start_str.clone();
-            progress.end_date = end_str.clone();
+            progress.start_date = start_str;
+            progress.end_date = end_str;
             progress.imported_stocks = 0;
             progress.imported_batches = 0;
+            progress.total_batches = total_batches;
         }
 
-        // 计算3年数据需要分成多少个30天批次
-        let total_days = (end_date - start_date).num_days();
-        let date_batches = (total_days + self.days_per_batch - 1) / self.days_per_batch;
+        let units = self.plan_units(stocks, start_date, end_date)?;
+        self.run_units(units, anchor).await
+    }
 
-        // 计算总批次数（股票批次 × 日期批次）
-        let stock_batches = (stocks.len() + self.batch_size - 1) / self.batch_size;
-        let total_batches = stock_batches * date_batches as usize;
+    /// 按优先通道排序后分批规划导入单元：每只股票 `[start, end]` 内未覆盖的交易日合并为区间，
+    /// 从近到远排列，每个区间不超过 `days_per_batch` 天
+    ///
+    /// 一批的最后一个单元完成时计入批次与已导入股票数。
+    fn plan_units(&self, stocks: &[Stock], start: NaiveDate, end: NaiveDate) -> Result<Vec<ImportUnit>> {
+        // 没有覆盖索引时所有股票的缺口相同，只计算一次
+        let uncovered = match self.coverage() {
+            Some(_) => Vec::new(),
+            None => coverage::missing_runs(&self.calendar, start, end, self.days_per_batch, |_| false),
+        };
 
-        {
-            let mut progress = self.progress.write().await;
-            progress.total_batches = total_batches;
-        }
+        let stocks = self.lanes().prioritize(stocks.to_vec());
+        let mut units = Vec::with_capacity(stocks.len());
+        let mut requested = 0;
+        for batch in stocks.chunks(self.batch_size) {
+            let first = units.len();
+            for stock in batch {
+                let ranges = match self.coverage() {
+                    Some(coverage) => {
+                        coverage.missing_ranges(&self.calendar, &stock.code, start, end, self.days_per_batch)?
+                    }
+                    None => uncovered.clone(),
+                };
+                requested += ranges.len();
 
-        let mut batch_idx = 0;
+                if ranges.is_empty() {
+                    units.push(ImportUnit {
+                        stock: stock.clone(),
+                        range: None,
+                        closes_batch: false,
+                        stocks_done: 0,
+                    });
+                }
+                units.extend(ranges.into_iter().map(|range| ImportUnit {
+                    stock: stock.clone(),
+                    range: Some(range),
+                    closes_batch: false,
+                    stocks_done: 0,
+                }));
+            }
 
-        // 按日期分批导入
-        let mut current_end = end_date;
-        while current_end > start_date {
-            // 检查是否取消
-            if self.is_cancelled.load(Ordering::SeqCst) {
-                return Err(AppError::Config("导入已取消".to_string()));
+            if let Some(last) = units[first..].last_mut() {
+                last.closes_batch = true;
+                last.stocks_done = batch.len();
             }
+        }
+
+        info!("{} 只股票共 {} 个缺失区间", stocks.len(), requested);
+        Ok(units)
+    }
+
+    /// 以有界并发执行导入单元，结果按提交顺序记入进度
+    ///
+    /// 取消时立即返回，未完成的请求随工作池一起丢弃。
+    async fn run_units(&self, units: Vec<ImportUnit>, anchor: NaiveDate) -> Result<()> {
+        let mut results = stream::iter(units)
+            .map(|unit| async move {
+                let result = self.import_range(&unit).await;
+                (unit, result)
+            })
+            .buffered(self.concurrency);
 
-            let current_start = if current_end - Duration::days(self.days_per_batch) < start_date {
-                start_date
-            } else {
-                current_end - Duration::days(self.days_per_batch)
+        loop {
+            let next = tokio::select! {
+                biased;
+                _ = self.cancelled() => return Err(AppError::Config("导入已取消".to_string())),
+                next = results.next() => next,
+            };
+            let Some((unit, result)) = next else {
+                break;
             };
+            result?;
 
-            let start_str = current_start.format("%Y-%m-%d").to_string();
-            let end_str = current_end.format("%Y-%m-%d").to_string();
+            // 更新进度
+            let mut progress = self.progress.write().await;
+            progress.current_code = unit.stock.code;
+            progress.imported_stocks += unit.stocks_done;
+            if unit.closes_batch {
+                progress.imported_batches += 1;
+                debug!("批次 {}/{} 完成", progress.imported_batches, progress.total_batches);
+                self.save_progress(&progress, anchor)?;
+            }
+        }
 
-            info!("导入日期批次: {} 到 {}", start_str, end_str);
+        Ok(())
+    }
 
-            // 按股票分批导入
-            for stock_chunk in stocks.chunks(self.batch_size) {
-                // 检查是否取消
-                if self.is_cancelled.load(Ordering::SeqCst) {
-                    return Err(AppError::Config("导入已取消".to_string()));
-                }
+    /// 等待取消
+    async fn cancelled(&self) {
+        let notified = self.cancel_notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if !self.is_cancelled.load(Ordering::SeqCst) {
+            notified.await;
+        }
+    }
 
-                batch_idx += 1;
+    /// 导入单只股票的一个缺失区间
+    ///
+    /// 获取失败只计入错误数，不记入覆盖索引，下次导入时会重试。
+    /// 覆盖索引：只在 K 线确实写入时使用，否则被丢弃的交易日会记为已覆盖而不再请求
+    fn coverage(&self) -> Option<&CoverageIndex> {
+        self.coverage.as_ref().filter(|_| self.stores_bars)
+    }
 
-                for stock in stock_chunk {
-                    // 更新当前股票
-                    {
-                        let mut progress = self.progress.write().await;
-                        progress.current_code = stock.code.clone();
-                    }
+    async fn import_range(&self, unit: &ImportUnit) -> Result<()> {
+        let code = &unit.stock.code;
+        let Some((start, end)) = unit.range else {
+            return Ok(());
+        };
 
-                    // 获取历史数据
-                    match self.tdx_client.get_daily_data(&stock.code, &start_str, &end_str).await {
-                        Ok(klines) => {
-                            debug!("获取 {} 的K线数据 {} 条", stock.code, klines.len());
-                            // TODO: 写入 ClickHouse
-                            // 在 Task 4 实现
-                        }
-                        Err(e) => {
-                            warn!("获取 {} 数据失败: {}", stock.code, e);
-                            let mut progress = self.progress.write().await;
-                            progress.error_count += 1;
-                        }
-                    }
-                }
+        // 暂停期间不发出新请求（取消时由 `run_units` 直接丢弃）
+        let _ = self.paused.subscribe().wait_for(|paused| !paused).await;
 
-                // 更新进度
-                {
-                    let mut progress = self.progress.write().await;
-                    progress.imported_batches = batch_idx;
+        // 获取历史数据
+        match self.source.get_kline(code, KLinePeriod::Day, start, end).await {
+            Ok(klines) => {
+                debug!("获取 {} 的K线数据 {} 条", code, klines.len());
+                if !klines.is_empty() {
+                    self.sink.write_batch(&klines).await?;
+                }
+                if let Some(coverage) = self.coverage() {
+                    coverage.record(&self.calendar, code, start, end, &klines)?;
                 }
-
-                // 延迟避免服务器压力
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
             }
-
-            current_end = current_start - Duration::days(1);
+            Err(e) => {
+                warn!("获取 {} 数据失败: {}", code, e);
+                let mut progress = self.progress.write().await;
+                progress.error_count += 1;
+            }
         }
 
         Ok(())
     }
 
+    /// 推送进度事件（没有订阅者时忽略）
+    fn publish(&self, progress: &ImportProgress) {
+        let _ = self.events.send(progress.clone());
+    }
+
+    /// 推送并保存进度
+    fn save_progress(&self, progress: &ImportProgress, anchor: NaiveDate) -> Result<()> {
+        self.publish(progress);
+        let Some(db) = &self.local_db else {
+            return Ok(());
+        };
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO import_progress
+                 (id, stage, total_stocks, imported_stocks, total_batches, imported_batches,
+                  current_code, start_date, end_date, anchor_date, error_count, updated_at)
+                 VALUES (0, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    progress.stage.as_str(),
+                    progress.total_stocks as i64,
+                    progress.imported_stocks as i64,
+                    progress.total_batches as i64,
+                    progress.imported_batches as i64,
+                    progress.current_code,
+                    progress.start_date,
+                    progress.end_date,
+                    anchor.to_string(),
+                    progress.error_count as i64,
+                    Utc::now().to_rfc3339(),
+                ],
+            )
+            .map(|_| ())
+        })
+    }
+
     /// 取消导入
     pub async fn cancel(&self) {
         info!("取消历史数据导入");
         self.is_cancelled.store(true, Ordering::SeqCst);
+        self.cancel_notify.notify_waiters();
 
         let mut progress = self.progress.write().await;
         progress.stage = ImportStage::Cancelled;
         progress.is_running = false;
+        progress.is_paused = false;
+        self.publish(&progress);
+    }
+
+    /// 暂停导入：进行中的请求照常完成，之后不再发出新请求
+    pub async fn pause(&self) -> Result<()> {
+        let mut progress = self.progress.write().await;
+        if !progress.is_running {
+            return Err(AppError::Config("没有正在运行的导入任务".to_string()));
+        }
+        if !progress.is_paused {
+            info!("暂停历史数据导入");
+            self.paused.send_replace(true);
+            progress.is_paused = true;
+            self.publish(&progress);
+        }
+        Ok(())
+    }
+
+    /// 恢复已暂停的导入
+    pub async fn resume(&self) -> Result<()> {
+        let mut progress = self.progress.write().await;
+        if !progress.is_running {
+            return Err(AppError::Config("没有正在运行的导入任务".to_string()));
+        }
+        if progress.is_paused {
+            info!("恢复历史数据导入");
+            self.paused.send_replace(false);
+            progress.is_paused = false;
+            self.publish(&progress);
+        }
+        Ok(())
+    }
+
+    /// 当前优先通道
+    pub fn lanes(&self) -> ImportLanes {
+        self.lanes.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 设置优先通道，从下一阶段开始生效
+    pub fn set_lanes(&self, lanes: ImportLanes) {
+        info!(
+            "设置导入优先通道: 自选股 {} 只，近期活跃 {} 只",
+            lanes.watchlist.len(),
+            lanes.recently_active.len()
+        );
+        *self.lanes.write().unwrap_or_else(|e| e.into_inner()) = lanes;
+    }
+
+    /// 订阅进度事件
+    pub fn subscribe(&self) -> broadcast::Receiver<ImportProgress> {
+        self.events.subscribe()
     }
 
     /// 获取当前导入进度
@@ -353,11 +684,167 @@ impl HistoryImporter {
         self.days_per_batch = days;
         self
     }
+
+    /// 设置同时进行的请求数
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+}
+
+/// 日终日线补数任务：以交易日为截止日期导入证券主数据中仍在交易的股票
+///
+/// 覆盖索引记录已请求的交易日，每次只请求缺少的部分（首次运行时即完整的历史导入）。
+pub struct DailyBarJob {
+    source: Arc<dyn DataSource>,
+    sink: Arc<dyn BatchSink<KLine>>,
+    db: Arc<LocalDb>,
+    calendar: Arc<TradingCalendar>,
+    securities: Arc<SecurityService>,
+}
+
+impl DailyBarJob {
+    pub fn new(
+        source: Arc<dyn DataSource>,
+        sink: Arc<dyn BatchSink<KLine>>,
+        db: Arc<LocalDb>,
+        calendar: Arc<TradingCalendar>,
+        securities: Arc<SecurityService>,
+    ) -> Self {
+        Self {
+            source,
+            sink,
+            db,
+            calendar,
+            securities,
+        }
+    }
+}
+
+#[async_trait]
+impl DailyJob for DailyBarJob {
+    async fn run(&self, date: NaiveDate) -> Result<()> {
+        HistoryImporter::new(self.source.clone())
+            .with_sink(self.sink.clone())
+            .with_local_db(self.db.clone())
+            .with_calendar(self.calendar.clone())
+            .with_end_date(date)
+            .start_import(self.securities.stocks().await)
+            .await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collector::csv_source::{fixture_source, CsvDataSource};
+    use crate::collector::protocol::{FinanceInfo, SecurityInfo};
+    use crate::models::auction::Auction;
+    use crate::models::money_flow::Transaction;
+    use crate::models::quote::Quote;
+    use crate::models::stock::Market;
+    use std::sync::atomic::AtomicUsize;
+
+    /// 模拟远程数据源：每次 K 线请求耗时 `delay`，任意代码都返回 000001 的样本
+    struct SlowSource {
+        inner: CsvDataSource,
+        delay: std::time::Duration,
+        calls: AtomicUsize,
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+        requested: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl SlowSource {
+        fn new(delay_ms: u64) -> Self {
+            Self {
+                inner: fixture_source(),
+                delay: std::time::Duration::from_millis(delay_ms),
+                calls: AtomicUsize::new(0),
+                in_flight: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+                requested: std::sync::Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl DataSource for SlowSource {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn get_all_securities(&self, market: Market) -> Result<Vec<SecurityInfo>> {
+            self.inner.get_all_securities(market).await
+        }
+
+        async fn get_finance_info(&self, market: Market, code: &str) -> Result<FinanceInfo> {
+            self.inner.get_finance_info(market, code).await
+        }
+
+        async fn get_quotes(&self, codes: &[String]) -> Result<Vec<Quote>> {
+            self.inner.get_quotes(codes).await
+        }
+
+        async fn get_kline(&self, code: &str, period: KLinePeriod, start: NaiveDate, end: NaiveDate) -> Result<Vec<KLine>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.requested.lock().unwrap().push(code.to_string());
+            let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            self.inner.get_kline("000001", period, start, end).await
+        }
+
+        async fn get_transactions(&self, code: &str, date: Option<NaiveDate>) -> Result<Vec<Transaction>> {
+            self.inner.get_transactions(code, date).await
+        }
+
+        async fn get_auctions(&self, codes: &[String]) -> Result<Vec<Auction>> {
+            self.inner.get_auctions(codes).await
+        }
+    }
+
+    fn many_stocks(n: usize) -> Vec<Stock> {
+        (0..n).map(|i| stock(&format!("{:06}", i + 1), Market::SZ)).collect()
+    }
+
+    /// 记录写入 K 线的内存目标
+    #[derive(Default)]
+    struct MemorySink {
+        bars: std::sync::Mutex<Vec<KLine>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BatchSink<KLine> for MemorySink {
+        async fn write_batch(&self, batch: &[KLine]) -> Result<()> {
+            self.bars.lock().unwrap().extend_from_slice(batch);
+            Ok(())
+        }
+    }
+
+    fn stock(code: &str, market: Market) -> Stock {
+        Stock {
+            code: code.to_string(),
+            name: code.to_string(),
+            market,
+        }
+    }
+
+    fn importer(db: &Arc<LocalDb>, sink: &Arc<MemorySink>) -> HistoryImporter {
+        HistoryImporter::new(Arc::new(fixture_source()))
+            .with_sink(sink.clone())
+            .with_local_db(db.clone())
+            .with_days_per_batch(400)
+    }
+
+    /// 截止日期前 1 个月 + 3 年内未覆盖的区间数
+    fn gaps(db: &Arc<LocalDb>, code: &str, end: NaiveDate) -> usize {
+        CoverageIndex::new(db.clone())
+            .missing_ranges(&TradingCalendar::bundled(), code, end - Duration::days(30 + 365 * 3), end, 400)
+            .unwrap()
+            .len()
+    }
 
     #[test]
     fn test_import_stage() {
@@ -370,7 +857,267 @@ mod tests {
 
     #[tokio::test]
     async fn test_importer_creation() {
-        // 需要真实的 TdxClient，这里暂时跳过
-        // 实际测试在集成测试中进行
+        let source = crate::collector::csv_source::fixture_source();
+        let importer = HistoryImporter::new(Arc::new(source))
+            .with_batch_size(50)
+            .with_days_per_batch(10);
+
+        let progress = importer.get_progress().await;
+        assert_eq!(progress.stage, ImportStage::Idle);
+        assert!(!progress.is_running);
+        assert_eq!(importer.batch_size, 50);
+    }
+
+    #[tokio::test]
+    async fn test_coverage_skips_imported_days() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let sink = Arc::new(MemorySink::default());
+        let end = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        let stocks = vec![
+            stock("000001", Market::SZ),
+            stock("600000", Market::SH),
+            stock("999999", Market::SH),
+        ];
+
+        importer(&db, &sink).with_end_date(end).start_import(stocks.clone()).await.unwrap();
+
+        // 有样本的股票全部覆盖；999999 获取失败，不记入覆盖索引
+        assert_eq!(gaps(&db, "000001", end), 0);
+        assert_eq!(gaps(&db, "600000", end), 0);
+        assert!(gaps(&db, "999999", end) > 0);
+        assert_eq!(sink.bars.lock().unwrap().len(), 10);
+
+        // 已完成导入不视为可续传
+        let second = importer(&db, &sink);
+        assert!(!second.restore().await.unwrap());
+        let restored = second.get_progress().await;
+        assert_eq!(restored.stage, ImportStage::Completed);
+        assert_eq!(restored.imported_stocks, 3);
+        let errors = restored.error_count;
+        assert!(errors > 0);
+
+        // 相同区间再次导入只重试失败的股票
+        let second = second.with_end_date(end);
+        second.start_import(stocks).await.unwrap();
+        assert_eq!(sink.bars.lock().unwrap().len(), 10);
+        assert_eq!(second.get_progress().await.error_count, errors);
+    }
+
+    #[tokio::test]
+    async fn test_resume_uses_saved_end_date() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let sink = Arc::new(MemorySink::default());
+        let end = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        let stocks = vec![stock("000001", Market::SZ)];
+
+        let first = importer(&db, &sink).with_end_date(end);
+        first.start_import(stocks.clone()).await.unwrap();
+        let history = first.get_progress().await;
+        assert_eq!(history.imported_stocks, 1);
+        assert_eq!(history.imported_batches, history.total_batches);
+
+        // 模拟在历史阶段中断
+        db.with_conn(|conn| conn.execute("UPDATE import_progress SET stage = 'importing_history'", []))
+            .unwrap();
+
+        // 重启后未指定截止日期，沿用保存的日期，全部交易日已覆盖
+        let resumed = importer(&db, &sink);
+        assert!(resumed.restore().await.unwrap());
+        assert_eq!(resumed.get_progress().await.stage, ImportStage::ImportingHistory);
+        resumed.start_import(stocks).await.unwrap();
+
+        assert_eq!(sink.bars.lock().unwrap().len(), 5);
+        assert_eq!(gaps(&db, "000001", end), 0);
+        assert_eq!(resumed.get_progress().await.stage, ImportStage::Completed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrent_import_speedup() {
+        let end = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        let stocks = many_stocks(16);
+
+        let mut elapsed = Vec::new();
+        for concurrency in [1, 8] {
+            let source = Arc::new(SlowSource::new(50));
+            let sink = Arc::new(MemorySink::default());
+            let importer = HistoryImporter::new(source.clone())
+                .with_sink(sink.clone())
+                .with_end_date(end)
+                .with_batch_size(5)
+                .with_days_per_batch(400)
+                .with_concurrency(concurrency);
+
+            let started = tokio::time::Instant::now();
+            importer.start_import(stocks.clone()).await.unwrap();
+            elapsed.push(started.elapsed());
+
+            // 16 只股票 × (1 个近期区间 + 3 个历史区间)，只有近期区间有样本
+            assert_eq!(source.calls.load(Ordering::SeqCst), 64);
+            assert_eq!(source.peak.load(Ordering::SeqCst), concurrency);
+            assert_eq!(sink.bars.lock().unwrap().len(), 16 * 5);
+
+            let progress = importer.get_progress().await;
+            assert_eq!(progress.stage, ImportStage::Completed);
+            assert_eq!(progress.imported_stocks, 16);
+            assert_eq!(progress.total_batches, 4);
+            assert_eq!(progress.imported_batches, progress.total_batches);
+        }
+
+        assert_eq!(elapsed[0], std::time::Duration::from_millis(64 * 50));
+        assert!(elapsed[1] * 6 < elapsed[0], "并发导入耗时 {:?}，串行 {:?}", elapsed[1], elapsed[0]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_mid_flight() {
+        let end = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+
+        // 请求进行中取消：已完成的批次保留，其余请求不再发出
+        let source = Arc::new(SlowSource::new(1000));
+        let sink = Arc::new(MemorySink::default());
+        let importer = Arc::new(
+            HistoryImporter::new(source.clone())
+                .with_sink(sink.clone())
+                .with_end_date(end)
+                .with_batch_size(4)
+                .with_concurrency(4),
+        );
+        let task = tokio::spawn({
+            let importer = importer.clone();
+            async move { importer.start_import(many_stocks(16)).await }
+        });
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        importer.cancel().await;
+        assert!(task.await.unwrap().is_err());
+
+        assert_eq!(source.calls.load(Ordering::SeqCst), 8);
+        assert_eq!(sink.bars.lock().unwrap().len(), 4 * 5);
+        let progress = importer.get_progress().await;
+        assert_eq!(progress.stage, ImportStage::Cancelled);
+        assert_eq!(progress.imported_batches, 1);
+        assert!(!progress.is_running);
+    }
+
+    #[tokio::test]
+    async fn test_daily_top_up_requests_only_new_days() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let source = Arc::new(SlowSource::new(0));
+        let sink = Arc::new(MemorySink::default());
+        let importer = |end| {
+            HistoryImporter::new(source.clone())
+                .with_sink(sink.clone())
+                .with_local_db(db.clone())
+                .with_days_per_batch(400)
+                .with_end_date(end)
+        };
+
+        // 未设置写入目标时数据被丢弃，不记入覆盖索引
+        let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        HistoryImporter::new(source.clone())
+            .with_local_db(db.clone())
+            .with_days_per_batch(400)
+            .with_end_date(friday)
+            .start_import(many_stocks(3))
+            .await
+            .unwrap();
+        assert!(gaps(&db, "000001", friday) > 0);
+        source.calls.store(0, Ordering::SeqCst);
+
+        importer(friday).start_import(many_stocks(3)).await.unwrap();
+        let initial = source.calls.load(Ordering::SeqCst);
+        assert_eq!(initial, 3 * 4);
+
+        // 一周后补数：端午 6-10 休市，只请求 6-11 至 6-14
+        let next_friday = NaiveDate::from_ymd_opt(2024, 6, 14).unwrap();
+        importer(next_friday).start_import(many_stocks(3)).await.unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), initial + 3);
+
+        // 重复执行不再请求
+        let again = importer(next_friday);
+        again.start_import(many_stocks(3)).await.unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), initial + 3);
+        let progress = again.get_progress().await;
+        assert_eq!(progress.stage, ImportStage::Completed);
+        assert_eq!(progress.imported_stocks, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_resume_with_priority_lanes() {
+        let source = Arc::new(SlowSource::new(1000));
+        let importer = Arc::new(
+            HistoryImporter::new(source.clone())
+                .with_end_date(NaiveDate::from_ymd_opt(2024, 6, 7).unwrap())
+                .with_days_per_batch(400)
+                .with_batch_size(2)
+                .with_concurrency(2),
+        );
+        importer.set_lanes(ImportLanes {
+            watchlist: vec!["000005".to_string()],
+            recently_active: vec!["000003".to_string(), "000009".to_string()],
+        });
+        assert!(importer.pause().await.is_err());
+
+        let mut events = importer.subscribe();
+        let task = tokio::spawn({
+            let importer = importer.clone();
+            async move { importer.start_import(many_stocks(6)).await }
+        });
+
+        // 前两个请求进行中时暂停，之后不再发出请求
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        importer.pause().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+        let paused = importer.get_progress().await;
+        assert!(paused.is_paused);
+        assert_eq!(paused.imported_batches, 1);
+
+        importer.resume().await.unwrap();
+        task.await.unwrap().unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), 6 * 4);
+
+        // 自选股、近期活跃股票在前，其余按原顺序（同时发出的两个请求先后不定）
+        let mut requested = source.requested.lock().unwrap().clone();
+        requested[2..4].sort();
+        requested[4..6].sort();
+        assert_eq!(requested[..6], ["000005", "000003", "000001", "000002", "000004", "000006"]);
+
+        let mut received = Vec::new();
+        while let Ok(progress) = events.try_recv() {
+            received.push(progress);
+        }
+        assert_eq!(received.first().unwrap().stage, ImportStage::ImportingRecent);
+        assert!(received.iter().any(|p| p.is_paused));
+        let last = received.last().unwrap();
+        assert_eq!(last.stage, ImportStage::Completed);
+        assert!(!last.is_paused);
+    }
+
+    #[tokio::test]
+    async fn test_daily_bar_job_tops_up_listed_stocks() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let securities = Arc::new(SecurityService::new(db.clone()).unwrap());
+        securities.sync(&fixture_source()).await.unwrap();
+        let listed = securities.stocks().await.len();
+
+        let source = Arc::new(SlowSource::new(0));
+        let sink = Arc::new(MemorySink::default());
+        let job = DailyBarJob::new(
+            source.clone(),
+            sink.clone(),
+            db.clone(),
+            Arc::new(TradingCalendar::bundled()),
+            securities,
+        );
+
+        let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        job.run(friday).await.unwrap();
+        assert_eq!(gaps(&db, "000001", friday), 0);
+        assert!(!sink.bars.lock().unwrap().is_empty());
+
+        // 下一交易日只为每只股票请求一次
+        source.calls.store(0, Ordering::SeqCst);
+        job.run(NaiveDate::from_ymd_opt(2024, 6, 11).unwrap()).await.unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), listed);
     }
 }
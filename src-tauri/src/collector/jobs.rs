@@ -0,0 +1,513 @@
+//! 日终任务调度 - 收盘后按交易日历运行具名任务
+//!
+//! 每个任务在交易日的固定北京时间（如 15:30）之后运行一次，可声明依赖的前置任务，
+//! 前置任务成功后才会运行。应用关闭期间错过的交易日在下次检查时按日期顺序补跑，
+//! 失败的任务距上次失败超过 `retry_delay` 后才重试，每次运行的状态与耗时写入本地数据库
+//! `job_runs` 表。
+
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::db::LocalDb;
+use crate::error::{AppError, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// 默认补跑最近的交易日数
+const DEFAULT_CATCH_UP_DAYS: usize = 5;
+
+/// 默认单个交易日的最多尝试次数
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// 默认失败后的重试间隔
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// 后台检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 日终任务
+#[async_trait]
+pub trait DailyJob: Send + Sync {
+    /// 处理指定交易日的数据
+    async fn run(&self, date: NaiveDate) -> Result<()>;
+}
+
+/// 由异步闭包实现的任务
+pub struct FnJob<F>(pub F);
+
+#[async_trait]
+impl<F, Fut> DailyJob for FnJob<F>
+where
+    F: Fn(NaiveDate) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    async fn run(&self, date: NaiveDate) -> Result<()> {
+        (self.0)(date).await
+    }
+}
+
+/// 任务定义
+pub struct JobSpec {
+    name: String,
+    at: NaiveTime,
+    depends_on: Vec<String>,
+    job: Arc<dyn DailyJob>,
+}
+
+impl JobSpec {
+    /// 交易日北京时间 `at` 之后运行的任务
+    pub fn new(name: impl Into<String>, at: NaiveTime, job: Arc<dyn DailyJob>) -> Self {
+        Self {
+            name: name.into(),
+            at,
+            depends_on: Vec::new(),
+            job,
+        }
+    }
+
+    /// 依赖的前置任务（同一交易日成功后才运行）
+    pub fn after(mut self, name: impl Into<String>) -> Self {
+        self.depends_on.push(name.into());
+        self
+    }
+
+    /// 任务名
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// 运行结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Succeeded,  // 成功
+    Failed,     // 失败，未超过尝试次数时等待重试间隔后重试
+    Skipped,    // 前置任务最终失败，不再运行
+}
+
+impl JobStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Skipped => "skipped",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "succeeded" => Some(JobStatus::Succeeded),
+            "failed" => Some(JobStatus::Failed),
+            "skipped" => Some(JobStatus::Skipped),
+            _ => None,
+        }
+    }
+}
+
+/// 单次运行记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobRun {
+    pub job: String,
+    pub trade_date: NaiveDate,
+    pub status: JobStatus,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+/// 某任务在某交易日的累计状态
+#[derive(Debug, Default, Clone, Copy)]
+struct RunState {
+    succeeded: bool,
+    skipped: bool,
+    failures: u32,
+    last_failure: Option<DateTime<Utc>>,  // 最近一次失败的开始时间
+}
+
+/// 日终任务调度器
+pub struct JobScheduler {
+    calendar: Arc<TradingCalendar>,
+    db: Arc<LocalDb>,
+    jobs: Vec<JobSpec>,
+    catch_up_days: usize,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl JobScheduler {
+    /// 创建调度器
+    pub fn new(calendar: Arc<TradingCalendar>, db: Arc<LocalDb>) -> Self {
+        Self {
+            calendar,
+            db,
+            jobs: Vec::new(),
+            catch_up_days: DEFAULT_CATCH_UP_DAYS,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+
+    /// 设置补跑的交易日数（不含今天）
+    pub fn with_catch_up_days(mut self, days: usize) -> Self {
+        self.catch_up_days = days;
+        self
+    }
+
+    /// 设置单个交易日的最多尝试次数
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// 设置失败后的重试间隔（从上次失败的开始时间算起）
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// 注册任务，前置任务必须已注册（因此不会形成环）
+    pub fn register(&mut self, spec: JobSpec) -> Result<()> {
+        if self.jobs.iter().any(|j| j.name == spec.name) {
+            return Err(AppError::Config(format!("任务重复注册: {}", spec.name)));
+        }
+        if let Some(dep) = spec.depends_on.iter().find(|d| !self.jobs.iter().any(|j| &j.name == *d)) {
+            return Err(AppError::Config(format!("任务 {} 的前置任务 {} 未注册", spec.name, dep)));
+        }
+
+        self.jobs.push(spec);
+        Ok(())
+    }
+
+    /// 运行截至北京时间 `now` 所有到期且未完成的任务，返回本次的运行记录
+    ///
+    /// 从 `catch_up_days` 个交易日前开始按日期顺序检查，同一交易日内按注册顺序运行。
+    pub async fn run_due(&self, now: NaiveDateTime) -> Result<Vec<JobRun>> {
+        let today = now.date();
+        let since = self.calendar.nth_trading_day_before(today, self.catch_up_days);
+        let mut states = self.load_states(since)?;
+        let mut runs = Vec::new();
+
+        for date in self.calendar.trading_days_between(since, today) {
+            for spec in &self.jobs {
+                if date == today && now.time() < spec.at {
+                    continue;
+                }
+                let state = states.get(&(spec.name.clone(), date)).copied().unwrap_or_default();
+                if state.succeeded || state.skipped || state.failures >= self.max_attempts {
+                    continue;
+                }
+                if self.waiting_retry(&state) {
+                    continue;
+                }
+
+                let deps: Vec<RunState> = spec
+                    .depends_on
+                    .iter()
+                    .map(|d| states.get(&(d.clone(), date)).copied().unwrap_or_default())
+                    .collect();
+                let blocked = deps
+                    .iter()
+                    .any(|d| d.skipped || (!d.succeeded && d.failures >= self.max_attempts));
+                if !blocked && !deps.iter().all(|d| d.succeeded) {
+                    // 前置任务尚未运行或等待重试
+                    continue;
+                }
+
+                let run = if blocked {
+                    tracing::warn!("{} 的前置任务失败，跳过 {}", date, spec.name);
+                    JobRun {
+                        job: spec.name.clone(),
+                        trade_date: date,
+                        status: JobStatus::Skipped,
+                        started_at: Utc::now(),
+                        duration_ms: 0,
+                        error: Some("前置任务失败".to_string()),
+                    }
+                } else {
+                    self.execute(spec, date).await
+                };
+
+                self.save(&run)?;
+                let state = states.entry((spec.name.clone(), date)).or_default();
+                match run.status {
+                    JobStatus::Succeeded => state.succeeded = true,
+                    JobStatus::Failed => {
+                        state.failures += 1;
+                        state.last_failure = Some(run.started_at);
+                    }
+                    JobStatus::Skipped => state.skipped = true,
+                }
+                runs.push(run);
+            }
+        }
+
+        Ok(runs)
+    }
+
+    /// 后台定期检查到期任务
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            tracing::info!("日终任务调度器已启动，共 {} 个任务", self.jobs.len());
+            loop {
+                if let Err(e) = self.run_due(clock::now_local()).await {
+                    tracing::error!("日终任务检查失败: {}", e);
+                }
+                tokio::time::sleep(CHECK_INTERVAL).await;
+            }
+        })
+    }
+
+    /// 运行历史，按开始时间倒序；`job` 为空时返回全部任务
+    pub fn history(&self, job: Option<&str>, limit: usize) -> Result<Vec<JobRun>> {
+        self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT job, trade_date, status, started_at, duration_ms, error FROM job_runs
+                 WHERE ?1 IS NULL OR job = ?1
+                 ORDER BY id DESC LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![job, limit as i64], |row| {
+                let text = |idx: usize| -> rusqlite::Result<String> { row.get(idx) };
+                let invalid = |idx: usize| {
+                    rusqlite::Error::InvalidColumnType(idx, "job_runs".to_string(), rusqlite::types::Type::Text)
+                };
+                Ok(JobRun {
+                    job: row.get(0)?,
+                    trade_date: text(1)?.parse().map_err(|_| invalid(1))?,
+                    status: JobStatus::parse(&text(2)?).ok_or_else(|| invalid(2))?,
+                    started_at: DateTime::parse_from_rfc3339(&text(3)?)
+                        .map_err(|_| invalid(3))?
+                        .with_timezone(&Utc),
+                    duration_ms: row.get::<_, i64>(4)? as u64,
+                    error: row.get(5)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// 上次失败后是否仍在重试间隔内
+    fn waiting_retry(&self, state: &RunState) -> bool {
+        state.last_failure.is_some_and(|failed| {
+            Utc::now().signed_duration_since(failed).to_std().unwrap_or_default() < self.retry_delay
+        })
+    }
+
+    async fn execute(&self, spec: &JobSpec, date: NaiveDate) -> JobRun {
+        tracing::info!("运行日终任务 {} ({})", spec.name, date);
+        let started_at = Utc::now();
+        let timer = Instant::now();
+        let result = spec.job.run(date).await;
+        let duration_ms = timer.elapsed().as_millis() as u64;
+
+        let (status, error) = match result {
+            Ok(()) => (JobStatus::Succeeded, None),
+            Err(e) => {
+                tracing::error!("日终任务 {} ({}) 失败: {}", spec.name, date, e);
+                (JobStatus::Failed, Some(e.to_string()))
+            }
+        };
+
+        JobRun {
+            job: spec.name.clone(),
+            trade_date: date,
+            status,
+            started_at,
+            duration_ms,
+            error,
+        }
+    }
+
+    fn save(&self, run: &JobRun) -> Result<()> {
+        self.db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO job_runs (job, trade_date, status, started_at, duration_ms, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    run.job,
+                    run.trade_date.to_string(),
+                    run.status.as_str(),
+                    run.started_at.to_rfc3339(),
+                    run.duration_ms as i64,
+                    run.error,
+                ],
+            )
+            .map(|_| ())
+        })
+    }
+
+    /// 读取 `since` 以来各任务各交易日的状态
+    fn load_states(&self, since: NaiveDate) -> Result<HashMap<(String, NaiveDate), RunState>> {
+        let rows: Vec<(String, String, String, String)> = self.db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT job, trade_date, status, started_at FROM job_runs WHERE trade_date >= ?1 ORDER BY id",
+            )?;
+            let rows = stmt.query_map([since.to_string()], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?;
+            rows.collect()
+        })?;
+
+        let mut states: HashMap<_, RunState> = HashMap::new();
+        for (job, date, status, started_at) in rows {
+            let Ok(date) = date.parse::<NaiveDate>() else {
+                continue;
+            };
+            let state = states.entry((job, date)).or_default();
+            match JobStatus::parse(&status) {
+                Some(JobStatus::Succeeded) => state.succeeded = true,
+                Some(JobStatus::Failed) => {
+                    state.failures += 1;
+                    state.last_failure = DateTime::parse_from_rfc3339(&started_at)
+                        .ok()
+                        .map(|t| t.with_timezone(&Utc));
+                }
+                Some(JobStatus::Skipped) => state.skipped = true,
+                None => {}
+            }
+        }
+        Ok(states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn at(m: u32, d: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, m, d)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn hm(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    /// 记录调用顺序的任务
+    fn recording_job(name: &'static str, log: &Arc<Mutex<Vec<String>>>) -> Arc<dyn DailyJob> {
+        let log = log.clone();
+        Arc::new(FnJob(move |date: NaiveDate| {
+            let log = log.clone();
+            async move {
+                log.lock().unwrap().push(format!("{} {}", name, date));
+                Ok(())
+            }
+        }))
+    }
+
+    fn scheduler(db: Arc<LocalDb>) -> JobScheduler {
+        JobScheduler::new(Arc::new(TradingCalendar::bundled()), db).with_catch_up_days(2)
+    }
+
+    #[tokio::test]
+    async fn test_runs_after_time_with_dependencies() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let mut jobs = scheduler(db);
+        jobs.register(JobSpec::new("daily_bars", hm(15, 30), recording_job("daily_bars", &log)))
+            .unwrap();
+        jobs.register(
+            JobSpec::new("factors", hm(15, 30), recording_job("factors", &log)).after("daily_bars"),
+        )
+        .unwrap();
+
+        // 首次运行补跑前两个交易日（6-05、6-06），6-07 未到 15:30
+        let runs = jobs.run_due(at(6, 7, 15, 0)).await.unwrap();
+        assert_eq!(runs.len(), 4);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["daily_bars 2024-06-05", "factors 2024-06-05", "daily_bars 2024-06-06", "factors 2024-06-06"]
+        );
+
+        let runs = jobs.run_due(at(6, 7, 15, 31)).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs.iter().all(|r| r.trade_date == at(6, 7, 0, 0).date()));
+
+        // 已完成的不重复运行，周末无任务
+        assert!(jobs.run_due(at(6, 8, 16, 0)).await.unwrap().is_empty());
+
+        // 前置任务不存在时拒绝注册
+        let orphan = JobSpec::new("report", hm(18, 0), recording_job("report", &log)).after("missing");
+        assert!(jobs.register(orphan).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failure_retries_then_skips_dependents() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let mut jobs = scheduler(db.clone())
+            .with_catch_up_days(0)
+            .with_max_attempts(2)
+            .with_retry_delay(Duration::ZERO);
+        let failing: Arc<dyn DailyJob> =
+            Arc::new(FnJob(|_| async { Err(AppError::Network("服务器无响应".to_string())) }));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        jobs.register(JobSpec::new("dragon_tiger", hm(18, 0), failing)).unwrap();
+        jobs.register(
+            JobSpec::new("report", hm(18, 0), recording_job("report", &log)).after("dragon_tiger"),
+        )
+        .unwrap();
+
+        let first = jobs.run_due(at(6, 7, 18, 0)).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].status, JobStatus::Failed);
+
+        // 第二次失败后达到上限，依赖任务记为跳过
+        let second = jobs.run_due(at(6, 7, 18, 1)).await.unwrap();
+        let statuses: Vec<_> = second.iter().map(|r| (r.job.as_str(), r.status)).collect();
+        assert_eq!(statuses, vec![("dragon_tiger", JobStatus::Failed), ("report", JobStatus::Skipped)]);
+        assert!(jobs.run_due(at(6, 7, 18, 2)).await.unwrap().is_empty());
+        assert!(log.lock().unwrap().is_empty());
+
+        // 历史持久化在本地数据库
+        let history = JobScheduler::new(Arc::new(TradingCalendar::bundled()), db)
+            .history(Some("dragon_tiger"), 10)
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].error.as_deref(), Some("网络错误: 服务器无响应"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_waits_for_delay() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let flaky: Arc<dyn DailyJob> = Arc::new(FnJob(move |_| {
+            let attempt = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                match attempt {
+                    0 => Err(AppError::Network("服务器无响应".to_string())),
+                    _ => Ok(()),
+                }
+            }
+        }));
+        let mut jobs = scheduler(db.clone())
+            .with_catch_up_days(0)
+            .with_retry_delay(Duration::from_millis(200));
+        jobs.register(JobSpec::new("daily_bars", hm(15, 30), flaky.clone())).unwrap();
+
+        let first = jobs.run_due(at(6, 7, 15, 30)).await.unwrap();
+        assert_eq!(first[0].status, JobStatus::Failed);
+
+        // 重试间隔内的检查不再运行，重启后按持久化的失败时间继续等待
+        assert!(jobs.run_due(at(6, 7, 15, 31)).await.unwrap().is_empty());
+        let mut restarted = scheduler(db)
+            .with_catch_up_days(0)
+            .with_retry_delay(Duration::from_millis(200));
+        restarted.register(JobSpec::new("daily_bars", hm(15, 30), flaky)).unwrap();
+        assert!(restarted.run_due(at(6, 7, 15, 32)).await.unwrap().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let retried = restarted.run_due(at(6, 7, 15, 33)).await.unwrap();
+        assert_eq!(retried[0].status, JobStatus::Succeeded);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
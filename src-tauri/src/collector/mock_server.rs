@@ -0,0 +1,423 @@
+//! 测试用通达信模拟服务器
+//!
+//! 在本地随机端口上实现 7709 协议的子集，按 `MockData` 返回数据，
+//! 响应 body 超过 64 字节时使用 zlib 压缩，以覆盖客户端的解压路径。
+
+use crate::collector::clock;
+use crate::collector::protocol::{self, FinanceInfo, SecurityInfo};
+use crate::models::money_flow::{TradeDirection, Transaction};
+use crate::models::quote::{KLine, KLinePeriod, Quote};
+use crate::models::stock::Market;
+use chrono::{Datelike, NaiveDate, Timelike};
+use std::collections::HashMap;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+
+/// 模拟服务器数据
+#[derive(Debug, Clone, Default)]
+pub struct MockData {
+    pub securities: Vec<SecurityInfo>,
+    pub quotes: Vec<Quote>,
+    /// (代码, 通达信 K 线类别) -> 按时间升序的 K 线
+    pub bars: HashMap<(String, u16), Vec<KLine>>,
+    /// (代码, 日期，None 为当日) -> 按时间升序的逐笔成交
+    pub transactions: HashMap<(String, Option<NaiveDate>), Vec<Transaction>>,
+    /// 代码 -> 基本财务信息
+    pub finance: HashMap<String, FinanceInfo>,
+    /// 每个响应前的延迟，用于模拟慢速服务器
+    pub delay: Duration,
+}
+
+impl MockData {
+    /// 添加 K 线（按时间升序）
+    pub fn with_bars(mut self, code: &str, period: KLinePeriod, bars: Vec<KLine>) -> Self {
+        self.bars.insert((code.to_string(), protocol::bar_category(period)), bars);
+        self
+    }
+
+    /// 添加基本财务信息
+    pub fn with_finance(mut self, info: FinanceInfo) -> Self {
+        self.finance.insert(info.code.clone(), info);
+        self
+    }
+
+    /// 设置响应延迟
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// 添加逐笔成交（按时间升序）
+    pub fn with_transactions(mut self, code: &str, date: Option<NaiveDate>, list: Vec<Transaction>) -> Self {
+        self.transactions.insert((code.to_string(), date), list);
+        self
+    }
+}
+
+/// 通达信模拟服务器
+pub struct MockTdxServer {
+    addr: SocketAddr,
+    requests: Arc<AtomicUsize>,
+    handle: JoinHandle<()>,
+}
+
+impl MockTdxServer {
+    /// 在 127.0.0.1 随机端口启动
+    pub async fn start(data: MockData) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let data = Arc::new(data);
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        // 连接任务挂在 JoinSet 上，服务器销毁时一并断开
+        let handle = tokio::spawn(async move {
+            let mut connections = JoinSet::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.spawn(serve(stream, data.clone(), counter.clone()));
+                while connections.try_join_next().is_some() {}
+            }
+        });
+
+        Self { addr, requests, handle }
+    }
+
+    /// 服务器地址（host:port）
+    pub fn addr(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// 已处理的请求数（含握手）
+    pub fn request_count(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for MockTdxServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// 获取一个无人监听的本地地址（连接会被拒绝）
+pub async fn closed_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    addr.to_string()
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    data: Arc<MockData>,
+    counter: Arc<AtomicUsize>,
+) -> std::io::Result<()> {
+    loop {
+        let mut head = [0u8; 12];
+        stream.read_exact(&mut head).await?;
+
+        let seq = [head[1], head[2], head[3], head[4]];
+        let len = u16::from_le_bytes([head[6], head[7]]) as usize;
+        let cmd = u16::from_le_bytes([head[10], head[11]]);
+
+        let mut body = vec![0u8; len.saturating_sub(2)];
+        stream.read_exact(&mut body).await?;
+        counter.fetch_add(1, Ordering::SeqCst);
+
+        if !data.delay.is_zero() {
+            tokio::time::sleep(data.delay).await;
+        }
+
+        let response = match handle(cmd, &body, &data) {
+            Some(response) => response,
+            None => return Ok(()),
+        };
+
+        stream.write_all(&frame(seq, cmd, response)).await?;
+    }
+}
+
+fn handle(cmd: u16, body: &[u8], data: &MockData) -> Option<Vec<u8>> {
+    match cmd {
+        protocol::CMD_SETUP | protocol::CMD_SETUP_EXT => Some(vec![0u8; 16]),
+        protocol::CMD_SECURITY_COUNT => {
+            let market = protocol::market_from_id(body[0])?;
+            let count = securities_of(data, market).count() as u16;
+            Some(count.to_le_bytes().to_vec())
+        }
+        protocol::CMD_SECURITY_LIST => {
+            let market = protocol::market_from_id(body[0])?;
+            let start = u16::from_le_bytes([body[2], body[3]]) as usize;
+            let page: Vec<&SecurityInfo> = securities_of(data, market)
+                .skip(start)
+                .take(protocol::SECURITY_LIST_PAGE_SIZE)
+                .collect();
+            Some(encode_security_list(&page))
+        }
+        protocol::CMD_QUOTES => {
+            let count = u16::from_le_bytes([body[8], body[9]]) as usize;
+            let quotes: Vec<(u8, &Quote)> = body[10..]
+                .chunks(7)
+                .take(count)
+                .filter_map(|chunk| {
+                    let code = std::str::from_utf8(&chunk[1..7]).ok()?;
+                    let quote = data.quotes.iter().find(|q| q.code == code)?;
+                    Some((chunk[0], quote))
+                })
+                .collect();
+            Some(encode_quotes(&quotes))
+        }
+        protocol::CMD_BARS => {
+            let code = std::str::from_utf8(&body[2..8]).ok()?.to_string();
+            let category = u16::from_le_bytes([body[8], body[9]]);
+            let start = u16::from_le_bytes([body[12], body[13]]) as usize;
+            let count = u16::from_le_bytes([body[14], body[15]]) as usize;
+
+            // start 为距最新一根的偏移
+            let bars = data.bars.get(&(code, category)).map(Vec::as_slice).unwrap_or(&[]);
+            let end = bars.len().saturating_sub(start);
+            let begin = end.saturating_sub(count);
+            Some(encode_bars(category, &bars[begin..end]))
+        }
+        protocol::CMD_TRANSACTIONS | protocol::CMD_HISTORY_TRANSACTIONS => {
+            let history = cmd == protocol::CMD_HISTORY_TRANSACTIONS;
+            let (date, rest) = if history {
+                let raw = u32::from_le_bytes([body[0], body[1], body[2], body[3]]);
+                let date = NaiveDate::from_ymd_opt((raw / 10000) as i32, (raw % 10000) / 100, raw % 100)?;
+                (Some(date), &body[4..])
+            } else {
+                (None, body)
+            };
+            let code = std::str::from_utf8(&rest[2..8]).ok()?.to_string();
+            let start = u16::from_le_bytes([rest[8], rest[9]]) as usize;
+            let count = u16::from_le_bytes([rest[10], rest[11]]) as usize;
+
+            let list = data.transactions.get(&(code, date)).map(Vec::as_slice).unwrap_or(&[]);
+            let end = list.len().saturating_sub(start);
+            let begin = end.saturating_sub(count);
+            Some(encode_transactions(history, &list[begin..end]))
+        }
+        protocol::CMD_FINANCE_INFO => {
+            let market = body[2];
+            let code = std::str::from_utf8(&body[3..9]).ok()?;
+            Some(encode_finance(market, code, data.finance.get(code)))
+        }
+        _ => None,
+    }
+}
+
+fn securities_of(data: &MockData, market: Market) -> impl Iterator<Item = &SecurityInfo> {
+    data.securities.iter().filter(move |s| s.market == market)
+}
+
+/// 组装响应包，必要时压缩
+fn frame(seq: [u8; 4], cmd: u16, body: Vec<u8>) -> Vec<u8> {
+    let unzip_size = body.len() as u16;
+    let payload = if body.len() > 64 {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&body).unwrap();
+        encoder.finish().unwrap()
+    } else {
+        body
+    };
+
+    let mut buf = vec![0xb1, 0xcb, 0x74, 0x00];
+    buf.extend_from_slice(&seq);
+    buf.push(0x00);
+    buf.extend_from_slice(&cmd.to_le_bytes());
+    buf.push(0x00);
+    buf.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    buf.extend_from_slice(&unzip_size.to_le_bytes());
+    buf.extend_from_slice(&payload);
+    buf
+}
+
+/// 无数据时股本与日期均为 0
+fn encode_finance(market: u8, code: &str, info: Option<&FinanceInfo>) -> Vec<u8> {
+    let yyyymmdd = |date: Option<NaiveDate>| {
+        date.map_or(0, |d| d.year() as u32 * 10000 + d.month() * 100 + d.day())
+    };
+
+    let mut buf = 1u16.to_le_bytes().to_vec();
+    buf.push(market);
+    buf.extend_from_slice(code.as_bytes());
+    buf.extend_from_slice(&((info.map_or(0.0, |i| i.float_shares) / 10000.0) as f32).to_le_bytes());
+    buf.extend_from_slice(&[0u8; 4]);
+    buf.extend_from_slice(&yyyymmdd(info.and_then(|i| i.updated_date)).to_le_bytes());
+    buf.extend_from_slice(&yyyymmdd(info.and_then(|i| i.list_date)).to_le_bytes());
+    buf.extend_from_slice(&((info.map_or(0.0, |i| i.total_shares) / 10000.0) as f32).to_le_bytes());
+    // 其余 29 个财务字段
+    buf.extend_from_slice(&[0u8; 29 * 4]);
+    buf
+}
+
+fn encode_security_list(list: &[&SecurityInfo]) -> Vec<u8> {
+    let mut buf = (list.len() as u16).to_le_bytes().to_vec();
+    for s in list {
+        buf.extend_from_slice(s.code.as_bytes());
+        buf.extend_from_slice(&s.volunit.to_le_bytes());
+        let (name, _, _) = encoding_rs::GBK.encode(&s.name);
+        let mut name = name.to_vec();
+        name.resize(8, 0);
+        buf.extend_from_slice(&name);
+        buf.extend_from_slice(&[0u8; 4]);
+        buf.push(s.decimal_point);
+        buf.extend_from_slice(&encode_volume(s.pre_close).to_le_bytes());
+        buf.extend_from_slice(&[0u8; 4]);
+    }
+    buf
+}
+
+fn encode_quotes(quotes: &[(u8, &Quote)]) -> Vec<u8> {
+    let mut buf = vec![0xb1, 0xcb];
+    buf.extend_from_slice(&(quotes.len() as u16).to_le_bytes());
+
+    for (market, q) in quotes {
+        let price = to_raw(q.price);
+        buf.push(*market);
+        buf.extend_from_slice(q.code.as_bytes());
+        buf.extend_from_slice(&1u16.to_le_bytes());
+
+        for value in [price, to_raw(q.preclose) - price, to_raw(q.open) - price, to_raw(q.high) - price, to_raw(q.low) - price] {
+            encode_price(value, &mut buf);
+        }
+        encode_price(0, &mut buf); // 服务器时间
+        encode_price(0, &mut buf);
+        encode_price(q.volume as i64, &mut buf);
+        encode_price(0, &mut buf); // 现量
+        buf.extend_from_slice(&encode_volume(q.amount).to_le_bytes());
+        for _ in 0..4 {
+            encode_price(0, &mut buf);
+        }
+        for level in 0..5 {
+            encode_price(to_raw(q.bid[level]) - price, &mut buf);
+            encode_price(to_raw(q.ask[level]) - price, &mut buf);
+            encode_price(q.bid_vol[level] as i64, &mut buf);
+            encode_price(q.ask_vol[level] as i64, &mut buf);
+        }
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        for _ in 0..4 {
+            encode_price(0, &mut buf);
+        }
+        buf.extend_from_slice(&[0u8; 4]);
+    }
+
+    buf
+}
+
+fn encode_bars(category: u16, bars: &[KLine]) -> Vec<u8> {
+    let mut buf = (bars.len() as u16).to_le_bytes().to_vec();
+    let mut pre_close = 0i64;
+
+    for bar in bars {
+        let local = clock::to_local(bar.datetime);
+        if protocol::is_intraday_category(category) {
+            let zipday = ((local.year() - 2004) << 11) as u32 + local.month() * 100 + local.day();
+            buf.extend_from_slice(&(zipday as u16).to_le_bytes());
+            buf.extend_from_slice(&((local.hour() * 60 + local.minute()) as u16).to_le_bytes());
+        } else {
+            let yyyymmdd = local.year() as u32 * 10000 + local.month() * 100 + local.day();
+            buf.extend_from_slice(&yyyymmdd.to_le_bytes());
+        }
+
+        let open = (bar.open * 1000.0).round() as i64;
+        let close = (bar.close * 1000.0).round() as i64;
+        encode_price(open - pre_close, &mut buf);
+        encode_price(close - open, &mut buf);
+        encode_price((bar.high * 1000.0).round() as i64 - open, &mut buf);
+        encode_price((bar.low * 1000.0).round() as i64 - open, &mut buf);
+        buf.extend_from_slice(&encode_volume(bar.volume).to_le_bytes());
+        buf.extend_from_slice(&encode_volume(bar.amount).to_le_bytes());
+        pre_close = close;
+    }
+
+    buf
+}
+
+fn encode_transactions(history: bool, list: &[Transaction]) -> Vec<u8> {
+    let mut buf = (list.len() as u16).to_le_bytes().to_vec();
+    if history {
+        buf.extend_from_slice(&[0u8; 4]);
+    }
+
+    let mut last_price = 0i64;
+    for t in list {
+        let local = clock::to_local(t.datetime);
+        buf.extend_from_slice(&((local.hour() * 60 + local.minute()) as u16).to_le_bytes());
+        let price = to_raw(t.price);
+        encode_price(price - last_price, &mut buf);
+        encode_price(t.volume as i64, &mut buf);
+        if !history {
+            encode_price(t.num as i64, &mut buf);
+        }
+        let direction = match t.direction {
+            TradeDirection::Buy => 0,
+            TradeDirection::Sell => 1,
+            TradeDirection::Neutral => 2,
+        };
+        encode_price(direction, &mut buf);
+        encode_price(0, &mut buf);
+        last_price = price;
+    }
+
+    buf
+}
+
+fn to_raw(price: f64) -> i64 {
+    (price * 100.0).round() as i64
+}
+
+/// `BodyReader::price` 的逆编码
+pub fn encode_price(value: i64, buf: &mut Vec<u8>) {
+    let mut n = value.unsigned_abs();
+    let mut first = (n & 0x3f) as u8;
+    if value < 0 {
+        first |= 0x40;
+    }
+    n >>= 6;
+    if n > 0 {
+        first |= 0x80;
+    }
+    buf.push(first);
+
+    while n > 0 {
+        let mut b = (n & 0x7f) as u8;
+        n >>= 7;
+        if n > 0 {
+            b |= 0x80;
+        }
+        buf.push(b);
+    }
+}
+
+/// `protocol::decode_volume` 的逆编码
+pub fn encode_volume(value: f64) -> u32 {
+    if value <= 0.0 {
+        return 0;
+    }
+
+    // 指数只能取奇数 e，尾数区间 [2^e, 2^(e+1)) 使用低 23 位，
+    // [2^(e+1), 2^(e+2)) 置 0x800000 位，步长翻倍
+    let mut exp = value.log2().floor() as i32;
+    let high_bit = exp % 2 == 0;
+    if high_bit {
+        exp -= 1;
+    }
+    let logpoint = ((exp + 127) / 2) as u32;
+
+    let mantissa = if high_bit {
+        ((value - 2f64.powi(exp + 1)) / 2f64.powi(exp - 22)).round() as u32 | 0x80_0000
+    } else {
+        ((value - 2f64.powi(exp)) / 2f64.powi(exp - 23)).round() as u32
+    };
+
+    (logpoint << 24) | (mantissa & 0xff_ffff)
+}
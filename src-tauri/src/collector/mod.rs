@@ -1,29 +1,114 @@
 //! 数据采集模块 - 集成 rustdx 获取通达信数据
 
-pub mod tdx;
+pub mod breaker;
+pub mod buffer;
+pub mod calendar;
+pub mod clock;
+pub mod coverage;
+pub mod csv_source;
+pub mod factor;
+pub mod feed;
+pub mod importer;
+pub mod jobs;
 pub mod parser;
+pub mod pipeline;
+pub mod pool;
+pub mod protocol;
+pub mod reconcile;
+pub mod replay;
+pub mod retry;
+pub mod rules;
+pub mod scheduler;
+pub mod series;
+pub mod session;
+pub mod source;
+pub mod tdx;
+pub mod validator;
+pub mod writer;
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock_server;
 
 use crate::config::DataSourceConfig;
 use crate::Result;
+use calendar::TradingCalendar;
+use pipeline::CollectionPipeline;
+use scheduler::CollectionScheduler;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 /// 数据采集器
+///
+/// 按配置的 `calendar_path` 加载一次交易日历，采集调度器直接使用；采集流水线、历史导入器、
+/// 日终任务调度与对账任务应通过 [`Collector::calendar`] 共用同一份日历。
 pub struct Collector {
-    _config: Arc<RwLock<DataSourceConfig>>,
+    calendar: Arc<TradingCalendar>,
+    scheduler: CollectionScheduler,
 }
 
 impl Collector {
-    /// 创建新的数据采集器
-    pub fn new(config: Arc<RwLock<DataSourceConfig>>) -> Self {
-        Self { _config: config }
+    /// 创建新的数据采集器，外部日历文件无法读取或格式错误时返回错误
+    pub async fn new(config: Arc<RwLock<DataSourceConfig>>) -> Result<Self> {
+        let calendar = Arc::new(TradingCalendar::from_config(&*config.read().await)?);
+        Ok(Self {
+            scheduler: CollectionScheduler::new(config).with_calendar(calendar.clone()),
+            calendar,
+        })
+    }
+
+    /// 交易日历（传给各组件的 `with_calendar` 与 `JobScheduler::new`）
+    pub fn calendar(&self) -> &Arc<TradingCalendar> {
+        &self.calendar
+    }
+
+    /// 设置每次采集执行的流水线（应以 [`Collector::calendar`] 构建）
+    pub fn with_pipeline(mut self, pipeline: Arc<CollectionPipeline>) -> Self {
+        self.scheduler = self.scheduler.with_pipeline(pipeline);
+        self
+    }
+
+    /// 采集调度器
+    pub fn scheduler(&self) -> &CollectionScheduler {
+        &self.scheduler
     }
 
     /// 启动数据采集任务
     pub async fn start(&self) -> Result<()> {
         tracing::info!("数据采集器启动");
+        self.scheduler.start().await
+    }
+
+    /// 停止数据采集，等待缓冲区写完
+    pub async fn stop(&self) -> Result<()> {
+        self.scheduler.stop().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[tokio::test]
+    async fn test_collector_uses_configured_calendar() {
+        let path = std::env::temp_dir().join(format!("kaipanla_calendar_{}.toml", std::process::id()));
+        std::fs::write(&path, "[2024]\nholidays = [\"2024-06-07\"]\n").unwrap();
+        let config = DataSourceConfig {
+            calendar_path: Some(path.clone()),
+            ..Default::default()
+        };
+
+        let collector = Collector::new(Arc::new(RwLock::new(config))).await.unwrap();
+        assert!(Arc::ptr_eq(collector.calendar(), collector.scheduler().calendar()));
+        assert!(!collector.calendar().is_trading_day(NaiveDate::from_ymd_opt(2024, 6, 7).unwrap()));
+        // 端午节只在内置日历中
+        assert!(collector.calendar().is_trading_day(NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()));
 
-        // TODO: 启动定时采集任务
-        Ok(())
+        std::fs::remove_file(&path).unwrap();
+        let missing = DataSourceConfig {
+            calendar_path: Some(path),
+            ..Default::default()
+        };
+        assert!(Collector::new(Arc::new(RwLock::new(missing))).await.is_err());
     }
 }
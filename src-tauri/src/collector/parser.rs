@@ -1,7 +1,56 @@
 //! 数据解析器 - 解析通达信数据格式
+//!
+//! 离线数据位于通达信安装目录的 `vipdoc/{sh,sz,bj}/lday/*.day`，
+//! 每条日线记录 32 字节（小端）：
+//!
+//! | 偏移 | 类型 | 含义 |
+//! |------|------|------|
+//! | 0  | u32 | 日期 yyyymmdd |
+//! | 4  | u32 | 开盘价 |
+//! | 8  | u32 | 最高价 |
+//! | 12 | u32 | 最低价 |
+//! | 16 | u32 | 收盘价 |
+//! | 20 | f32 | 成交额（元） |
+//! | 24 | u32 | 成交量（股） |
+//! | 28 | u32 | 保留 |
+//!
+//! 股票、指数价格单位为 0.01 元，基金、债券为 0.001 元。
+//!
+//! 分钟线位于 `vipdoc/{sh,sz,bj}/minline/*.lc1`（1 分钟）与 `fzline/*.lc5`（5 分钟），
+//! 每条同样 32 字节：u16 日期（`(年 - 2004) * 2048 + 月 * 100 + 日`）、u16 分钟数
+//! （北京时间自零点起，为该根 K 线的结束时刻）、f32 开高低收（元）、f32 成交额、
+//! u32 成交量、u32 保留。
+//!
+//! 股本变迁文件 `T0002/hq_cache/gbbq` 以 u32 记录数开头，之后每条 29 字节，
+//! 前 24 字节按 8 字节分组以 Blowfish 变体加密，后 5 字节为明文。解密后的布局：
+//!
+//! | 偏移 | 类型 | 含义 |
+//! |------|------|------|
+//! | 0  | u8  | 市场 |
+//! | 1  | [u8; 7] | 代码（6 位 + 结尾 0） |
+//! | 8  | u32 | 日期 yyyymmdd |
+//! | 12 | u8  | 类别（1 = 除权除息，其余为股本变动） |
+//! | 13 | f32 × 4 | 除权除息：分红、配股价、送转股、配股（每 10 股）；股本变动：前流通、前总股本、后流通、后总股本（万股） |
 
-use crate::Result;
-use chrono::NaiveDate;
+use crate::collector::protocol;
+use crate::error::{AppError, Result};
+use crate::collector::clock;
+use crate::models::quote::{KLine, KLinePeriod};
+use crate::models::stock::{Market, SecurityType};
+use chrono::{NaiveDate, NaiveTime};
+use std::path::{Path, PathBuf};
+
+/// .day 文件单条记录长度
+pub const DAY_RECORD_LEN: usize = 32;
+
+/// .lc1 / .lc5 文件单条记录长度
+pub const MINUTE_RECORD_LEN: usize = 32;
+
+/// gbbq 文件单条记录长度
+pub const GBBQ_RECORD_LEN: usize = 29;
+
+/// gbbq 密钥表长度：18 个轮密钥 + 4 个 256 项 S 盒
+pub const GBBQ_KEY_LEN: usize = 0x1048;
 
 /// 通达信日线数据
 #[derive(Debug, Clone)]
@@ -16,26 +65,713 @@ pub struct DayData {
     pub amount: f64,
 }
 
+/// vipdoc 目录下的一个日线文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayFile {
+    pub market: Market,
+    pub code: String,
+    pub path: PathBuf,
+}
+
+impl DayFile {
+    /// 读取并解析文件
+    pub fn load(&self) -> Result<Vec<DayData>> {
+        let data = std::fs::read(&self.path)?;
+        Parser::parse_day_data(self.market, &self.code, &data)
+    }
+}
+
+/// vipdoc 目录下的一个分钟线文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinuteFile {
+    pub market: Market,
+    pub code: String,
+    pub period: KLinePeriod,
+    pub path: PathBuf,
+}
+
+impl MinuteFile {
+    /// 读取并解析文件
+    pub fn load(&self) -> Result<Vec<KLine>> {
+        let data = std::fs::read(&self.path)?;
+        Parser::parse_minute_data(&self.code, self.period, &data)
+    }
+}
+
+/// 股本变迁事件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GbbqEvent {
+    /// 除权除息，数值均为每 10 股
+    Dividend {
+        cash: f64,         // 派现（元）
+        rights_price: f64, // 配股价（元）
+        bonus: f64,        // 送转股（股）
+        rights: f64,       // 配股（股）
+    },
+    /// 股本变动（万股）
+    ShareChange {
+        float_before: f64,
+        total_before: f64,
+        float_after: f64,
+        total_after: f64,
+    },
+}
+
+/// 股本变迁记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct GbbqRecord {
+    pub market: Market,
+    pub code: String,
+    pub date: NaiveDate,
+    pub category: u8,
+    pub event: GbbqEvent,
+}
+
+/// gbbq 解密密钥表
+///
+/// 通达信客户端内置的固定密钥表，布局为 18 个 u32 轮密钥后接 4 个 S 盒，
+/// 共 `GBBQ_KEY_LEN` 字节（小端），需从客户端或 pytdx 等工具导出后加载。
+#[derive(Clone)]
+pub struct GbbqKey {
+    words: Vec<u32>,
+}
+
+impl GbbqKey {
+    /// 从原始字节构造
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != GBBQ_KEY_LEN {
+            return Err(AppError::Parse(format!(
+                "gbbq 密钥长度应为 {} 字节，实际 {}",
+                GBBQ_KEY_LEN,
+                bytes.len()
+            )));
+        }
+
+        let words = bytes
+            .chunks_exact(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        Ok(Self { words })
+    }
+
+    /// 从文件加载
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// 轮函数
+    fn round(&self, x: u32) -> u32 {
+        let s = |boxed: usize, index: u32| self.words[18 + boxed * 256 + index as usize];
+        (s(0, x >> 24).wrapping_add(s(1, (x >> 16) & 0xff)) ^ s(2, (x >> 8) & 0xff))
+            .wrapping_add(s(3, x & 0xff))
+    }
+
+    /// 解密一个 8 字节分组
+    fn decrypt_block(&self, block: &[u8]) -> [u8; 8] {
+        let left = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+        let right = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+        let mut num = left ^ self.words[17];
+        let mut old = right;
+        for j in (1..=16).rev() {
+            let next = self.round(num) ^ self.words[j] ^ old;
+            old = num;
+            num = next;
+        }
+        old ^= self.words[0];
+
+        let mut out = [0u8; 8];
+        out[..4].copy_from_slice(&old.to_le_bytes());
+        out[4..].copy_from_slice(&num.to_le_bytes());
+        out
+    }
+
+    /// `decrypt_block` 的逆运算，用于构造测试数据
+    #[cfg(test)]
+    fn encrypt_block(&self, block: &[u8]) -> [u8; 8] {
+        let mut old = u32::from_le_bytes([block[0], block[1], block[2], block[3]]) ^ self.words[0];
+        let mut num = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+        for j in 1..=16 {
+            let prev = self.round(old) ^ self.words[j] ^ num;
+            num = old;
+            old = prev;
+        }
+
+        let mut out = [0u8; 8];
+        out[..4].copy_from_slice(&(num ^ self.words[17]).to_le_bytes());
+        out[4..].copy_from_slice(&old.to_le_bytes());
+        out
+    }
+}
+
+impl std::fmt::Debug for GbbqKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GbbqKey").finish_non_exhaustive()
+    }
+}
+
 /// 数据解析器
 pub struct Parser;
 
 impl Parser {
-    /// 解析日线数据（占位实现）
-    pub fn parse_day_data(input: &str) -> Result<Vec<DayData>> {
-        // TODO: 实现实际的解析逻辑
-        tracing::debug!("解析日线数据: {}", input);
+    /// 解析 .day 文件内容
+    pub fn parse_day_data(market: Market, code: &str, data: &[u8]) -> Result<Vec<DayData>> {
+        if !data.len().is_multiple_of(DAY_RECORD_LEN) {
+            return Err(AppError::Parse(format!(
+                "{} 日线文件长度 {} 不是 {} 的整数倍",
+                code,
+                data.len(),
+                DAY_RECORD_LEN
+            )));
+        }
+
+        let kind = SecurityType::classify(market, code)
+            .ok_or_else(|| AppError::Parse(format!("无法识别证券类型: {:?} {}", market, code)))?;
+        let divisor = price_divisor(kind);
+
+        tracing::debug!("解析日线数据: {:?} {}，共 {} 条", market, code, data.len() / DAY_RECORD_LEN);
+
+        data.chunks_exact(DAY_RECORD_LEN)
+            .map(|record| {
+                let u32_at = |i: usize| u32::from_le_bytes([record[i], record[i + 1], record[i + 2], record[i + 3]]);
+
+                let raw_date = u32_at(0);
+                let date = NaiveDate::from_ymd_opt(
+                    (raw_date / 10000) as i32,
+                    (raw_date % 10000) / 100,
+                    raw_date % 100,
+                )
+                .ok_or_else(|| AppError::Parse(format!("{} 日期无效: {}", code, raw_date)))?;
+
+                Ok(DayData {
+                    date,
+                    code: code.to_string(),
+                    open: u32_at(4) as f64 / divisor,
+                    high: u32_at(8) as f64 / divisor,
+                    low: u32_at(12) as f64 / divisor,
+                    close: u32_at(16) as f64 / divisor,
+                    amount: f32::from_le_bytes([record[20], record[21], record[22], record[23]]) as f64,
+                    volume: u32_at(24) as f64,
+                })
+            })
+            .collect()
+    }
+
+    /// 解析单个 .day 文件，从文件名（如 `sh600000.day`）推断市场和代码
+    pub fn parse_day_file(path: &Path) -> Result<Vec<DayData>> {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| AppError::Parse(format!("无效的文件名: {}", path.display())))?;
+
+        let (market, code) = split_file_stem(stem)
+            .ok_or_else(|| AppError::Parse(format!("无法从文件名识别代码: {}", stem)))?;
+
+        let data = std::fs::read(path)?;
+        Self::parse_day_data(market, &code, &data)
+    }
+
+    /// 扫描通达信目录下的全部日线文件
+    ///
+    /// `root` 可以是通达信安装目录或其中的 `vipdoc` 目录；市场由 `sh`/`sz`/`bj`
+    /// 子目录名决定，无法识别证券类型的文件会被跳过。
+    pub fn scan_day_files(root: &Path) -> Result<Vec<DayFile>> {
+        let files: Vec<DayFile> = scan_vipdoc(root, "lday", "day")?
+            .into_iter()
+            .map(|(market, code, path)| DayFile { market, code, path })
+            .collect();
+
+        if files.is_empty() {
+            return Err(AppError::NotFound(format!("未找到日线文件: {}", root.display())));
+        }
+
+        Ok(files)
+    }
+
+    /// 解析 .lc1 / .lc5 文件内容
+    pub fn parse_minute_data(code: &str, period: KLinePeriod, data: &[u8]) -> Result<Vec<KLine>> {
+        if !matches!(period, KLinePeriod::Minute1 | KLinePeriod::Minute5) {
+            return Err(AppError::Parse(format!("分钟线文件不支持周期 {:?}", period)));
+        }
+
+        if !data.len().is_multiple_of(MINUTE_RECORD_LEN) {
+            return Err(AppError::Parse(format!(
+                "{} 分钟线文件长度 {} 不是 {} 的整数倍",
+                code,
+                data.len(),
+                MINUTE_RECORD_LEN
+            )));
+        }
+
+        tracing::debug!("解析分钟线数据: {} {:?}，共 {} 条", code, period, data.len() / MINUTE_RECORD_LEN);
+
+        data.chunks_exact(MINUTE_RECORD_LEN)
+            .map(|record| {
+                let u16_at = |i: usize| u16::from_le_bytes([record[i], record[i + 1]]) as u32;
+                let f32_at = |i: usize| {
+                    f32::from_le_bytes([record[i], record[i + 1], record[i + 2], record[i + 3]]) as f64
+                };
+
+                let zipday = u16_at(0);
+                let minutes = u16_at(2);
+                let date = NaiveDate::from_ymd_opt(
+                    (zipday / 2048) as i32 + 2004,
+                    (zipday % 2048) / 100,
+                    (zipday % 2048) % 100,
+                );
+                let time = NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0);
+                let (Some(date), Some(time)) = (date, time) else {
+                    return Err(AppError::Parse(format!(
+                        "{} 分钟线时间无效: {} {}",
+                        code, zipday, minutes
+                    )));
+                };
+
+                Ok(KLine {
+                    datetime: clock::to_utc(date.and_time(time)),
+                    code: code.to_string(),
+                    open: f32_at(4),
+                    high: f32_at(8),
+                    low: f32_at(12),
+                    close: f32_at(16),
+                    amount: f32_at(20),
+                    volume: u32::from_le_bytes([record[24], record[25], record[26], record[27]]) as f64,
+                })
+            })
+            .collect()
+    }
+
+    /// 解析单个分钟线文件，从文件名（如 `sz000001.lc5`）推断代码和周期
+    pub fn parse_minute_file(path: &Path) -> Result<Vec<KLine>> {
+        let period = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(minute_period)
+            .ok_or_else(|| AppError::Parse(format!("不是分钟线文件: {}", path.display())))?;
+
+        let (_, code) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(split_file_stem)
+            .ok_or_else(|| AppError::Parse(format!("无法从文件名识别代码: {}", path.display())))?;
+
+        let data = std::fs::read(path)?;
+        Self::parse_minute_data(&code, period, &data)
+    }
+
+    /// 扫描通达信目录下的全部分钟线文件（`minline/*.lc1` 与 `fzline/*.lc5`）
+    pub fn scan_minute_files(root: &Path) -> Result<Vec<MinuteFile>> {
+        let mut files = Vec::new();
+        for (folder, extension, period) in [
+            ("minline", "lc1", KLinePeriod::Minute1),
+            ("fzline", "lc5", KLinePeriod::Minute5),
+        ] {
+            files.extend(
+                scan_vipdoc(root, folder, extension)?
+                    .into_iter()
+                    .map(|(market, code, path)| MinuteFile { market, code, period, path }),
+            );
+        }
+
+        if files.is_empty() {
+            return Err(AppError::NotFound(format!("未找到分钟线文件: {}", root.display())));
+        }
+
+        Ok(files)
+    }
+
+    /// 解析 gbbq 文件内容
+    ///
+    /// 市场编号无法识别的记录会被跳过。
+    pub fn parse_gbbq(data: &[u8], key: &GbbqKey) -> Result<Vec<GbbqRecord>> {
+        if data.len() < 4 {
+            return Err(AppError::Parse("gbbq 文件缺少记录数".to_string()));
+        }
+
+        let count = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let body = &data[4..];
+        if body.len() < count * GBBQ_RECORD_LEN {
+            return Err(AppError::Parse(format!(
+                "gbbq 文件不完整: 声明 {} 条，实际 {} 字节",
+                count,
+                body.len()
+            )));
+        }
+
+        let mut records = Vec::with_capacity(count);
+        for raw in body.chunks_exact(GBBQ_RECORD_LEN).take(count) {
+            let mut clear = [0u8; GBBQ_RECORD_LEN];
+            for (i, block) in raw[..24].chunks_exact(8).enumerate() {
+                clear[i * 8..i * 8 + 8].copy_from_slice(&key.decrypt_block(block));
+            }
+            clear[24..].copy_from_slice(&raw[24..]);
+
+            let Some(market) = protocol::market_from_id(clear[0]) else {
+                continue;
+            };
+
+            let code = std::str::from_utf8(&clear[1..7])
+                .map_err(|_| AppError::Parse("gbbq 代码不是 ASCII，密钥可能不正确".to_string()))?
+                .to_string();
+
+            let raw_date = u32::from_le_bytes([clear[8], clear[9], clear[10], clear[11]]);
+            let date = NaiveDate::from_ymd_opt(
+                (raw_date / 10000) as i32,
+                (raw_date % 10000) / 100,
+                raw_date % 100,
+            )
+            .ok_or_else(|| AppError::Parse(format!("{} gbbq 日期无效: {}", code, raw_date)))?;
+
+            let category = clear[12];
+            let f = |i: usize| {
+                let at = 13 + i * 4;
+                f32::from_le_bytes([clear[at], clear[at + 1], clear[at + 2], clear[at + 3]]) as f64
+            };
+
+            let event = if category == 1 {
+                GbbqEvent::Dividend {
+                    cash: f(0),
+                    rights_price: f(1),
+                    bonus: f(2),
+                    rights: f(3),
+                }
+            } else {
+                GbbqEvent::ShareChange {
+                    float_before: f(0),
+                    total_before: f(1),
+                    float_after: f(2),
+                    total_after: f(3),
+                }
+            };
+
+            records.push(GbbqRecord { market, code, date, category, event });
+        }
 
-        Ok(vec![])
+        tracing::debug!("解析股本变迁记录 {} 条", records.len());
+        Ok(records)
     }
+
+    /// 解析 gbbq 文件
+    pub fn parse_gbbq_file(path: &Path, key: &GbbqKey) -> Result<Vec<GbbqRecord>> {
+        let data = std::fs::read(path)?;
+        Self::parse_gbbq(&data, key)
+    }
+}
+
+/// 遍历 `vipdoc/{sh,sz,bj}/<folder>/*.<extension>`，返回按路径排序的 (市场, 代码, 路径)
+///
+/// `root` 可以是通达信安装目录或 `vipdoc` 目录本身，无法识别证券类型的文件会被跳过。
+fn scan_vipdoc(root: &Path, folder: &str, extension: &str) -> Result<Vec<(Market, String, PathBuf)>> {
+    let vipdoc = if root.join("vipdoc").is_dir() {
+        root.join("vipdoc")
+    } else {
+        root.to_path_buf()
+    };
+
+    let mut files = Vec::new();
+    for (name, market) in [("sh", Market::SH), ("sz", Market::SZ), ("bj", Market::BJ)] {
+        let dir = vipdoc.join(name).join(folder);
+        if !dir.is_dir() {
+            continue;
+        }
+
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(extension) {
+                continue;
+            }
+
+            let Some(code) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|stem| stem.get(stem.len().saturating_sub(6)..))
+                .map(str::to_string)
+            else {
+                continue;
+            };
+
+            if SecurityType::classify(market, &code).is_none() {
+                tracing::debug!("跳过无法识别的文件: {}", path.display());
+                continue;
+            }
+
+            files.push((market, code, path));
+        }
+    }
+
+    files.sort_by(|a, b| a.2.cmp(&b.2));
+    Ok(files)
+}
+
+/// 分钟线文件扩展名对应的周期
+fn minute_period(extension: &str) -> Option<KLinePeriod> {
+    match extension {
+        "lc1" => Some(KLinePeriod::Minute1),
+        "lc5" => Some(KLinePeriod::Minute5),
+        _ => None,
+    }
+}
+
+/// 价格缩放系数
+fn price_divisor(kind: SecurityType) -> f64 {
+    10f64.powi(kind.price_decimals() as i32)
+}
+
+/// `sh600000` -> (SH, "600000")
+fn split_file_stem(stem: &str) -> Option<(Market, String)> {
+    if stem.len() != 8 {
+        return None;
+    }
+
+    let market = match &stem[0..2] {
+        "sh" => Market::SH,
+        "sz" => Market::SZ,
+        "bj" => Market::BJ,
+        _ => return None,
+    };
+
+    Some((market, stem[2..].to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// 构造一条 .day 记录
+    fn record(date: u32, prices: [u32; 4], amount: f32, volume: u32) -> Vec<u8> {
+        let mut buf = date.to_le_bytes().to_vec();
+        for price in prices {
+            buf.extend_from_slice(&price.to_le_bytes());
+        }
+        buf.extend_from_slice(&amount.to_le_bytes());
+        buf.extend_from_slice(&volume.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf
+    }
+
+    /// 构造一条 .lc1 / .lc5 记录
+    fn minute_record(date: (u32, u32, u32), minutes: u16, prices: [f32; 4], amount: f32, volume: u32) -> Vec<u8> {
+        let zipday = ((date.0 - 2004) * 2048 + date.1 * 100 + date.2) as u16;
+        let mut buf = zipday.to_le_bytes().to_vec();
+        buf.extend_from_slice(&minutes.to_le_bytes());
+        for price in prices {
+            buf.extend_from_slice(&price.to_le_bytes());
+        }
+        buf.extend_from_slice(&amount.to_le_bytes());
+        buf.extend_from_slice(&volume.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kaipanla_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 伪随机密钥表
+    fn test_key() -> GbbqKey {
+        let mut seed = 0x1234_5678u32;
+        let bytes: Vec<u8> = (0..GBBQ_KEY_LEN / 4)
+            .flat_map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                seed.to_le_bytes()
+            })
+            .collect();
+        GbbqKey::from_bytes(&bytes).unwrap()
+    }
+
+    /// 构造一条加密的 gbbq 记录
+    fn gbbq_record(key: &GbbqKey, market: u8, code: &str, date: u32, category: u8, values: [f32; 4]) -> Vec<u8> {
+        let mut clear = vec![market];
+        clear.extend_from_slice(code.as_bytes());
+        clear.push(0);
+        clear.extend_from_slice(&date.to_le_bytes());
+        clear.push(category);
+        for v in values {
+            clear.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let mut buf = Vec::new();
+        for block in clear[..24].chunks_exact(8) {
+            buf.extend_from_slice(&key.encrypt_block(block));
+        }
+        buf.extend_from_slice(&clear[24..]);
+        buf
+    }
+
     #[test]
     fn test_parse_day_data_empty() {
-        let result = Parser::parse_day_data("").unwrap();
+        let result = Parser::parse_day_data(Market::SZ, "000001", &[]).unwrap();
         assert_eq!(result.len(), 0);
     }
+
+    #[test]
+    fn test_parse_day_data_stock() {
+        let mut data = record(20240102, [1050, 1080, 1040, 1066], 1.5e8, 1_400_000);
+        data.extend(record(20240103, [1066, 1070, 1050, 1055], 1.2e8, 1_100_000));
+
+        let days = Parser::parse_day_data(Market::SH, "600000", &data).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, NaiveDate::from_ymd_opt(2024, 1, 2).unwrap());
+        assert_eq!(days[0].open, 10.5);
+        assert_eq!(days[0].close, 10.66);
+        assert_eq!(days[0].volume, 1_400_000.0);
+        assert_eq!(days[0].amount, 1.5e8);
+        assert_eq!(days[1].low, 10.5);
+    }
+
+    #[test]
+    fn test_parse_day_data_fund_scaling() {
+        let data = record(20240102, [3512, 3530, 3500, 3521], 1.0e9, 300_000_000);
+        let days = Parser::parse_day_data(Market::SH, "510300", &data).unwrap();
+        assert_eq!(days[0].close, 3.521);
+    }
+
+    #[test]
+    fn test_parse_day_data_invalid() {
+        // 不完整记录
+        assert!(Parser::parse_day_data(Market::SZ, "000001", &[0u8; 31]).is_err());
+        // 非法日期
+        let data = record(20241345, [1, 1, 1, 1], 0.0, 0);
+        assert!(Parser::parse_day_data(Market::SZ, "000001", &data).is_err());
+    }
+
+    #[test]
+    fn test_scan_day_files() {
+        let root = temp_dir("vipdoc");
+        let sh = root.join("vipdoc").join("sh").join("lday");
+        let sz = root.join("vipdoc").join("sz").join("lday");
+        std::fs::create_dir_all(&sh).unwrap();
+        std::fs::create_dir_all(&sz).unwrap();
+
+        let day = record(20240102, [1000, 1010, 990, 1005], 1.0e6, 1000);
+        std::fs::write(sh.join("sh000001.day"), &day).unwrap();
+        std::fs::write(sz.join("sz000001.day"), &day).unwrap();
+        std::fs::write(sz.join("sz990001.day"), &day).unwrap(); // 无法识别，跳过
+        std::fs::write(sz.join("readme.txt"), b"").unwrap();
+
+        let files = Parser::scan_day_files(&root).unwrap();
+        assert_eq!(files.len(), 2);
+        // 同一代码由目录决定市场
+        assert_eq!(files[0].market, Market::SH);
+        assert_eq!(files[1].market, Market::SZ);
+        assert_eq!(files[1].code, "000001");
+
+        let days = files[0].load().unwrap();
+        assert_eq!(days[0].close, 10.05);
+
+        let days = Parser::parse_day_file(&sz.join("sz000001.day")).unwrap();
+        assert_eq!(days.len(), 1);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_day_files_missing() {
+        let root = temp_dir("vipdoc_empty");
+        assert!(matches!(Parser::scan_day_files(&root), Err(AppError::NotFound(_))));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_gbbq_block_roundtrip() {
+        let key = test_key();
+        let clear = *b"\x01600000\0";
+        let encrypted = key.encrypt_block(&clear);
+        assert_ne!(encrypted, clear);
+        assert_eq!(key.decrypt_block(&encrypted), clear);
+    }
+
+    #[test]
+    fn test_parse_gbbq() {
+        let key = test_key();
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend(gbbq_record(&key, 1, "600000", 20230714, 1, [3.2, 0.0, 3.0, 0.0]));
+        data.extend(gbbq_record(&key, 0, "000001", 20230614, 5, [1940.5, 1940.6, 1940.5, 1940.6]));
+        data.extend(gbbq_record(&key, 9, "999999", 20230101, 1, [0.0; 4])); // 未知市场，跳过
+
+        let records = Parser::parse_gbbq(&data, &key).unwrap();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].market, Market::SH);
+        assert_eq!(records[0].code, "600000");
+        assert_eq!(records[0].date, NaiveDate::from_ymd_opt(2023, 7, 14).unwrap());
+        match records[0].event {
+            GbbqEvent::Dividend { cash, bonus, .. } => {
+                assert!((cash - 3.2).abs() < 1e-6);
+                assert_eq!(bonus, 3.0);
+            }
+            _ => panic!("应为除权除息"),
+        }
+
+        assert_eq!(records[1].category, 5);
+        assert!(matches!(records[1].event, GbbqEvent::ShareChange { .. }));
+    }
+
+    #[test]
+    fn test_parse_gbbq_invalid() {
+        let key = test_key();
+        assert!(GbbqKey::from_bytes(&[0u8; 16]).is_err());
+        assert!(Parser::parse_gbbq(&[1, 0], &key).is_err());
+        // 声明 2 条，实际只有 1 条
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend(gbbq_record(&key, 1, "600000", 20230714, 1, [0.0; 4]));
+        assert!(Parser::parse_gbbq(&data, &key).is_err());
+    }
+
+    #[test]
+    fn test_parse_minute_data() {
+        use chrono::{TimeZone, Utc};
+
+        let mut data = minute_record((2024, 1, 2), 9 * 60 + 31, [10.5, 10.6, 10.4, 10.55], 1.0e6, 95_000);
+        data.extend(minute_record((2024, 1, 2), 15 * 60, [10.6, 10.6, 10.6, 10.6], 2.0e6, 190_000));
+
+        let bars = Parser::parse_minute_data("000001", KLinePeriod::Minute1, &data).unwrap();
+        assert_eq!(bars.len(), 2);
+        // 北京时间 09:31 即 UTC 01:31
+        assert_eq!(bars[0].datetime, Utc.with_ymd_and_hms(2024, 1, 2, 1, 31, 0).unwrap());
+        assert_eq!(bars[1].datetime, Utc.with_ymd_and_hms(2024, 1, 2, 7, 0, 0).unwrap());
+        assert_eq!(bars[0].open, 10.5);
+        assert!((bars[0].close - 10.55).abs() < 1e-6);
+        assert_eq!(bars[0].volume, 95_000.0);
+        assert_eq!(bars[0].amount, 1.0e6);
+    }
+
+    #[test]
+    fn test_parse_minute_data_invalid() {
+        let data = minute_record((2024, 1, 2), 24 * 60, [1.0; 4], 0.0, 0);
+        assert!(Parser::parse_minute_data("000001", KLinePeriod::Minute1, &data).is_err());
+        assert!(Parser::parse_minute_data("000001", KLinePeriod::Minute1, &[0u8; 16]).is_err());
+        assert!(Parser::parse_minute_data("000001", KLinePeriod::Day, &[]).is_err());
+    }
+
+    #[test]
+    fn test_scan_minute_files() {
+        let root = temp_dir("minline");
+        let minline = root.join("sh").join("minline");
+        let fzline = root.join("sz").join("fzline");
+        std::fs::create_dir_all(&minline).unwrap();
+        std::fs::create_dir_all(&fzline).unwrap();
+
+        let bar = minute_record((2024, 1, 2), 9 * 60 + 35, [10.0; 4], 1.0e5, 100);
+        std::fs::write(minline.join("sh600000.lc1"), &bar).unwrap();
+        std::fs::write(fzline.join("sz000001.lc5"), &bar).unwrap();
+        std::fs::write(fzline.join("sz000001.lc1"), &bar).unwrap(); // 目录与扩展名不符，跳过
+
+        // 直接传入 vipdoc 目录
+        let files = Parser::scan_minute_files(&root).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].market, Market::SH);
+        assert_eq!(files[0].period, KLinePeriod::Minute1);
+        assert_eq!(files[1].code, "000001");
+        assert_eq!(files[1].period, KLinePeriod::Minute5);
+        assert_eq!(files[1].load().unwrap().len(), 1);
+
+        let bars = Parser::parse_minute_file(&fzline.join("sz000001.lc5")).unwrap();
+        assert_eq!(bars[0].code, "000001");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
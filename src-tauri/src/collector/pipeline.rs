@@ -0,0 +1,272 @@
+//! 实时采集流水线 - 获取行情 → 验证 → 缓冲 → 批量写入
+//!
+//! 调度器每次采集调用 [`CollectionPipeline::tick`]：从数据源获取采集范围内的全部行情，
+//! 经 [`DataValidator`] 按当前验证规则过滤后推入 [`DataBuffer`]，由后台 [`BatchWriter`] 批量写入。
+//! 采集证券主数据时按证券的板块、风险警示与上市日期确定涨跌幅，否则按代码推断板块。
+//! 关闭时先关闭缓冲区，等待写入器写完剩余数据。
+
+use crate::collector::buffer::DataBuffer;
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::collector::feed::{MarketEvent, MarketFeed};
+use crate::collector::rules::RuleEngine;
+use crate::collector::source::DataSource;
+use crate::collector::validator::{DataValidator, PriceLimit, QualityScore};
+use crate::collector::writer::{BatchSink, BatchWriter};
+use crate::error::{AppError, Result};
+use crate::models::quote::Quote;
+use crate::monitor::CollectorMonitor;
+use crate::service::SecurityService;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+/// 默认缓冲区容量
+const DEFAULT_BUFFER_CAPACITY: usize = 10_000;
+
+/// 采集范围
+enum Universe {
+    Codes(Vec<String>),              // 固定代码列表
+    Master(Arc<SecurityService>),    // 证券主数据中仍在交易的股票
+}
+
+/// 单次采集结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TickSummary {
+    pub fetched: usize,   // 数据源返回的行情数
+    pub accepted: usize,  // 通过验证写入缓冲区的行情数（含可疑）
+    pub suspect: usize,   // 标记为可疑的行情数
+    pub rejected: usize,  // 验证失败丢弃的行情数
+}
+
+/// 运行中的缓冲区与写入任务
+struct Running {
+    buffer: DataBuffer<Quote>,
+    writer: JoinHandle<Result<()>>,
+}
+
+/// 实时采集流水线
+pub struct CollectionPipeline {
+    source: Arc<dyn DataSource>,
+    sink: Arc<dyn BatchSink<Quote>>,
+    monitor: Arc<CollectorMonitor>,
+    universe: Universe,
+    feed: Option<MarketFeed>,
+    calendar: Arc<TradingCalendar>,
+    rules: Arc<RuleEngine>,
+    buffer_capacity: usize,
+    running: Mutex<Option<Running>>,
+}
+
+impl CollectionPipeline {
+    /// 创建流水线，采集范围默认为空
+    pub fn new(source: Arc<dyn DataSource>, sink: Arc<dyn BatchSink<Quote>>, monitor: Arc<CollectorMonitor>) -> Self {
+        Self {
+            source,
+            sink,
+            monitor,
+            universe: Universe::Codes(Vec::new()),
+            feed: None,
+            calendar: Arc::new(TradingCalendar::bundled()),
+            rules: Arc::new(RuleEngine::default()),
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            running: Mutex::new(None),
+        }
+    }
+
+    /// 采集固定的代码列表
+    pub fn with_codes(mut self, codes: Vec<String>) -> Self {
+        self.universe = Universe::Codes(codes);
+        self
+    }
+
+    /// 采集证券主数据中仍在交易的全部股票（每次采集时读取）
+    pub fn with_security_master(mut self, securities: Arc<SecurityService>) -> Self {
+        self.universe = Universe::Master(securities);
+        self
+    }
+
+    /// 通过验证的行情同时发布到事件流
+    pub fn with_feed(mut self, feed: MarketFeed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    /// 设置判断新股上市天数使用的交易日历
+    pub fn with_calendar(mut self, calendar: Arc<TradingCalendar>) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    /// 使用可重新加载的验证规则（缺省为内置规则）
+    pub fn with_rules(mut self, rules: Arc<RuleEngine>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// 设置缓冲区容量，写入跟不上时采集等待
+    pub fn with_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.buffer_capacity = capacity.max(1);
+        self
+    }
+
+    /// 监控器
+    pub fn monitor(&self) -> &Arc<CollectorMonitor> {
+        &self.monitor
+    }
+
+    /// 创建缓冲区并启动写入任务
+    pub async fn open(&self) -> Result<()> {
+        let mut running = self.running.lock().await;
+        if running.is_some() {
+            return Err(AppError::Internal("采集流水线已打开".to_string()));
+        }
+
+        let (buffer, receiver) = DataBuffer::new(self.buffer_capacity);
+        let writer = BatchWriter::new().with_sink(self.sink.clone());
+        let writer = tokio::spawn(async move { writer.start(receiver).await });
+        *running = Some(Running { buffer, writer });
+        Ok(())
+    }
+
+    /// 执行一次采集
+    pub async fn tick(&self) -> Result<TickSummary> {
+        let running = self.running.lock().await;
+        let Some(running) = running.as_ref() else {
+            return Err(AppError::Internal("采集流水线未打开".to_string()));
+        };
+
+        let codes = match &self.universe {
+            Universe::Codes(codes) => codes.clone(),
+            Universe::Master(securities) => securities.stocks().await.into_iter().map(|s| s.code).collect(),
+        };
+        self.monitor.set_total_stocks(codes.len());
+        if codes.is_empty() {
+            return Ok(TickSummary::default());
+        }
+
+        let started = Instant::now();
+        let quotes = match self.source.get_quotes(&codes).await {
+            Ok(quotes) => {
+                self.monitor.record_success();
+                quotes
+            }
+            Err(e) => {
+                self.monitor.record_failure();
+                tracing::warn!("从 {} 获取 {} 只股票行情失败: {}", self.source.name(), codes.len(), e);
+                return Err(e);
+            }
+        };
+        self.monitor
+            .record_latency(started.elapsed().as_secs_f64() * 1000.0)
+            .await;
+
+        let mut summary = TickSummary {
+            fetched: quotes.len(),
+            ..Default::default()
+        };
+        let rules = self.rules.current();
+        for quote in quotes {
+            let limit = self.price_limit(&quote).await;
+            match DataValidator::validate_quote_with_rules(&quote, limit, &rules) {
+                Ok(score) => {
+                    if score == QualityScore::Suspect {
+                        summary.suspect += 1;
+                    }
+                    summary.accepted += 1;
+                    if let Some(feed) = &self.feed {
+                        feed.publish(MarketEvent::Quote(quote.clone()));
+                    }
+                    running.buffer.send(quote).await?;
+                }
+                Err(e) => {
+                    summary.rejected += 1;
+                    tracing::warn!("丢弃 {} 行情: {}", quote.code, e);
+                }
+            }
+        }
+
+        tracing::debug!(
+            "采集完成: {} 条，接受 {}，可疑 {}，丢弃 {}",
+            summary.fetched,
+            summary.accepted,
+            summary.suspect,
+            summary.rejected
+        );
+        Ok(summary)
+    }
+
+    /// 行情对应的涨跌幅
+    async fn price_limit(&self, quote: &Quote) -> PriceLimit {
+        if let Universe::Master(securities) = &self.universe {
+            if let Some(security) = securities.get_by_code(&quote.code).await {
+                let date = clock::local_date(quote.timestamp);
+                return PriceLimit::for_security(&security, date, &self.calendar);
+            }
+        }
+        PriceLimit::for_code(&quote.code)
+    }
+
+    /// 关闭缓冲区并等待写入器写完剩余数据（未打开时直接返回）
+    pub async fn close(&self) -> Result<()> {
+        let Some(Running { buffer, writer }) = self.running.lock().await.take() else {
+            return Ok(());
+        };
+
+        drop(buffer);
+        writer
+            .await
+            .map_err(|e| AppError::Internal(format!("写入任务异常退出: {}", e)))??;
+        tracing::info!("采集流水线已关闭，缓冲区数据已写入");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::collector::csv_source::fixture_source;
+    use async_trait::async_trait;
+
+    /// 保存写入数据的内存目标
+    #[derive(Default)]
+    pub(crate) struct MemorySink {
+        pub rows: std::sync::Mutex<Vec<Quote>>,
+    }
+
+    #[async_trait]
+    impl BatchSink<Quote> for MemorySink {
+        async fn write_batch(&self, batch: &[Quote]) -> Result<()> {
+            self.rows.lock().unwrap().extend_from_slice(batch);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tick_validates_and_drains_on_close() {
+        let sink = Arc::new(MemorySink::default());
+        let monitor = Arc::new(CollectorMonitor::new(Vec::new()));
+        let feed = MarketFeed::default();
+        let mut events = feed.subscribe();
+        let pipeline = CollectionPipeline::new(Arc::new(fixture_source()), sink.clone(), monitor.clone())
+            .with_codes(vec!["000001".to_string(), "600036".to_string(), "999999".to_string()])
+            .with_feed(feed);
+
+        assert!(pipeline.tick().await.is_err());
+        pipeline.open().await.unwrap();
+
+        let summary = pipeline.tick().await.unwrap();
+        assert_eq!(summary.fetched, 2);
+        assert_eq!(summary.accepted, 2);
+        assert_eq!(events.recv().await.unwrap().code(), "000001");
+
+        // 写入器按 5 秒超时成批，关闭时立即写完
+        pipeline.close().await.unwrap();
+        assert_eq!(sink.rows.lock().unwrap().len(), 2);
+
+        let metrics = monitor.get_metrics().await;
+        assert_eq!(metrics.success_count, 1);
+        assert_eq!(metrics.total_stocks, 3);
+    }
+}
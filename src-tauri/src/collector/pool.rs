@@ -0,0 +1,649 @@
+//! 通达信服务器连接池
+//!
+//! 启动时探测全部配置的服务器，按握手延迟排序并保留最多 `size` 条空闲连接。
+//! 每台服务器配有熔断器：连续失败达到阈值（探测失败则立即）后熔断剔除，
+//! 冷却期满后放行一次试探请求，成功即重新加入排序。每次成功或失败都会同步到
+//! `CollectorMonitor`，界面展示的服务器状态即来源于此。
+//!
+//! 设置了每台服务器的请求预算时，每台服务器按固定间隔放行请求；选择服务器时优先
+//! 最早有名额的，名额相同再按排名，因此单台服务器（包括半开试探中的）不会占用其他服务器的预算。
+
+use crate::collector::breaker::{BreakerState, CircuitBreaker};
+use crate::collector::protocol::Request;
+use crate::collector::tdx::TdxConnection;
+use crate::error::{AppError, Result};
+use crate::monitor::CollectorMonitor;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// 连接池配置
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub size: usize,          // 最多保持的连接数（同时也是并发请求上限）
+    pub timeout: Duration,    // 连接/读写超时
+    pub cooldown: Duration,   // 熔断后的冷却时间
+    pub failure_threshold: u32, // 连续失败多少次后熔断
+    pub server_budget: Option<f64>, // 每台服务器每秒请求数上限
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 3,
+            timeout: Duration::from_secs(5),
+            cooldown: Duration::from_secs(60),
+            failure_threshold: 3,
+            server_budget: None,
+        }
+    }
+}
+
+/// 服务器状态快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerSnapshot {
+    pub addr: String,
+    pub healthy: bool,
+    pub latency_ms: Option<f64>,
+    pub fail_count: u32,
+    pub breaker: BreakerState,
+}
+
+/// 单台服务器的运行状态
+#[derive(Debug)]
+struct ServerState {
+    addr: String,
+    latency: Option<Duration>,
+    breaker: CircuitBreaker,
+    next_slot: Instant,  // 下一个请求名额（未设置预算时不使用）
+}
+
+impl ServerState {
+    fn is_available(&self, now: Instant) -> bool {
+        self.breaker.is_callable(now)
+    }
+}
+
+/// 通达信服务器连接池
+pub struct ServerPool {
+    config: PoolConfig,
+    servers: Mutex<Vec<ServerState>>,
+    idle: Mutex<Vec<TdxConnection>>,
+    permits: Semaphore,
+    monitor: Option<Arc<CollectorMonitor>>,
+}
+
+impl ServerPool {
+    /// 创建连接池（不立即连接）
+    pub fn new(servers: Vec<String>, config: PoolConfig) -> Self {
+        let servers = servers
+            .into_iter()
+            .map(|addr| ServerState {
+                addr,
+                latency: None,
+                breaker: CircuitBreaker::new(config.failure_threshold, config.cooldown),
+                next_slot: Instant::now(),
+            })
+            .collect();
+
+        Self {
+            permits: Semaphore::new(config.size.max(1)),
+            config,
+            servers: Mutex::new(servers),
+            idle: Mutex::new(Vec::new()),
+            monitor: None,
+        }
+    }
+
+    /// 设置连接数
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.config.size = size.max(1);
+        self.permits = Semaphore::new(self.config.size);
+        self
+    }
+
+    /// 设置连接/读写超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    /// 设置熔断后的冷却时间
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.config.cooldown = cooldown;
+        self.reset_breakers();
+        self
+    }
+
+    /// 设置熔断阈值（连续失败次数）
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.config.failure_threshold = threshold;
+        self.reset_breakers();
+        self
+    }
+
+    /// 限制每台服务器每秒的请求数（含重试）
+    pub fn with_server_budget(mut self, requests_per_sec: f64) -> Self {
+        self.config.server_budget = Some(requests_per_sec).filter(|rate| *rate > 0.0);
+        self
+    }
+
+    fn reset_breakers(&mut self) {
+        let servers = self.servers.get_mut().unwrap_or_else(|e| e.into_inner());
+        for server in servers.iter_mut() {
+            server.breaker = CircuitBreaker::new(self.config.failure_threshold, self.config.cooldown);
+        }
+    }
+
+    /// 上报服务器健康状态到监控器
+    pub fn with_monitor(mut self, monitor: Arc<CollectorMonitor>) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+
+    /// 连接池配置
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// 并发探测全部服务器，返回可用服务器数量
+    ///
+    /// 按握手耗时排序，延迟最低的 `size` 条连接保留为空闲连接，失败的服务器直接熔断。
+    pub async fn probe_all(&self) -> usize {
+        let addrs: Vec<String> = self.lock_servers().iter().map(|s| s.addr.clone()).collect();
+        let timeout = self.config.timeout;
+
+        let mut probes = JoinSet::new();
+        for addr in addrs {
+            probes.spawn(async move {
+                let started = Instant::now();
+                let result = connect_server(&addr, timeout).await;
+                (addr, started.elapsed(), result)
+            });
+        }
+
+        let mut connected = Vec::new();
+        while let Some(joined) = probes.join_next().await {
+            let Ok((addr, latency, result)) = joined else {
+                continue;
+            };
+
+            match result {
+                Ok(conn) => {
+                    tracing::info!("服务器 {} 探测成功，延迟 {:?}", addr, latency);
+                    self.mark_success(&addr, latency).await;
+                    connected.push((latency, conn));
+                }
+                Err(e) => {
+                    tracing::warn!("服务器 {} 探测失败: {}", addr, e);
+                    self.mark_failure(&addr, true).await;
+                }
+            }
+        }
+
+        let healthy = connected.len();
+        connected.sort_by_key(|(latency, _)| *latency);
+
+        let mut idle = self.lock_idle();
+        idle.clear();
+        idle.extend(connected.into_iter().take(self.config.size).map(|(_, conn)| conn));
+
+        healthy
+    }
+
+    /// 执行请求：优先复用空闲连接，否则连接排名最高的可用服务器
+    ///
+    /// 设置了请求预算时先等待所选服务器的名额。请求失败时丢弃连接并计入该服务器的熔断器，
+    /// 错误原样返回（重试由调用方决定）。
+    pub async fn execute(&self, request: &Request) -> Result<Vec<u8>> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|_| AppError::Internal("连接池已关闭".to_string()))?;
+
+        let order = self.schedule();
+        let mut conn = match self.take_idle(&order) {
+            Some(conn) => conn,
+            None => self.connect_best(&order).await?,
+        };
+        if let Some(at) = self.reserve_slot(conn.addr()) {
+            tokio::time::sleep_until(at.into()).await;
+        }
+
+        let started = Instant::now();
+        match conn.request(request).await {
+            Ok(body) => {
+                self.mark_success(conn.addr(), started.elapsed()).await;
+                self.put_idle(conn);
+                Ok(body)
+            }
+            Err(e) => {
+                tracing::warn!("服务器 {} 请求失败: {}", conn.addr(), e);
+                self.mark_failure(conn.addr(), false).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// 当前排名最高的可用服务器
+    pub fn current_server(&self) -> String {
+        self.ranked()
+            .into_iter()
+            .next()
+            .or_else(|| self.lock_servers().first().map(|s| s.addr.clone()))
+            .unwrap_or_default()
+    }
+
+    /// 配置的服务器地址
+    pub fn servers(&self) -> Vec<String> {
+        self.lock_servers().iter().map(|s| s.addr.clone()).collect()
+    }
+
+    /// 按排名返回全部服务器状态（可用在前）
+    pub fn snapshot(&self) -> Vec<ServerSnapshot> {
+        let now = Instant::now();
+        let servers = self.lock_servers();
+        let mut order: Vec<usize> = (0..servers.len()).collect();
+        order.sort_by_key(|&i| (!servers[i].is_available(now), rank_key(&servers[i]), i));
+
+        order
+            .into_iter()
+            .map(|i| {
+                let s = &servers[i];
+                let breaker = s.breaker.state(now);
+                ServerSnapshot {
+                    addr: s.addr.clone(),
+                    healthy: breaker != BreakerState::Open,
+                    latency_ms: s.latency.map(|l| l.as_secs_f64() * 1000.0),
+                    fail_count: s.breaker.consecutive_failures(),
+                    breaker,
+                }
+            })
+            .collect()
+    }
+
+    /// 未熔断的服务器按延迟排序
+    ///
+    /// 近期失败过的排在后面，未测量的排在已测量之后并保持配置顺序。
+    fn ranked(&self) -> Vec<String> {
+        let now = Instant::now();
+        let servers = self.lock_servers();
+
+        let mut order: Vec<usize> = (0..servers.len())
+            .filter(|&i| servers[i].is_available(now))
+            .collect();
+        order.sort_by_key(|&i| (rank_key(&servers[i]), i));
+        order.into_iter().map(|i| servers[i].addr.clone()).collect()
+    }
+
+    /// 本次请求选择服务器的顺序：设置了预算时最早有名额的在前，其余按排名
+    fn schedule(&self) -> Vec<String> {
+        let ranked = self.ranked();
+        if self.config.server_budget.is_none() {
+            return ranked;
+        }
+
+        let now = Instant::now();
+        let slots: Vec<Instant> = {
+            let servers = self.lock_servers();
+            ranked
+                .iter()
+                .map(|addr| servers.iter().find(|s| &s.addr == addr).map_or(now, |s| s.next_slot.max(now)))
+                .collect()
+        };
+        let mut order: Vec<usize> = (0..ranked.len()).collect();
+        order.sort_by_key(|&i| (slots[i], i));
+        order.into_iter().map(|i| ranked[i].clone()).collect()
+    }
+
+    /// 占用服务器的下一个请求名额，返回可发出请求的时刻（未设置预算时为 `None`）
+    fn reserve_slot(&self, addr: &str) -> Option<Instant> {
+        let interval = Duration::from_secs_f64(1.0 / self.config.server_budget?);
+        let now = Instant::now();
+        let mut servers = self.lock_servers();
+        let server = servers.iter_mut().find(|s| s.addr == addr)?;
+        let at = server.next_slot.max(now);
+        server.next_slot = at + interval;
+        Some(at)
+    }
+
+    /// 依顺序尝试连接，返回第一个握手成功的连接
+    async fn connect_best(&self, order: &[String]) -> Result<TdxConnection> {
+        let total = order.len();
+
+        for (i, addr) in order.iter().cloned().enumerate() {
+            let Some(admission) = self.try_acquire(&addr) else {
+                continue;
+            };
+
+            tracing::info!("尝试连接到服务器 [{}/{}]: {}", i + 1, total, addr);
+
+            let started = Instant::now();
+            match connect_server(&addr, self.config.timeout).await {
+                Ok(conn) => {
+                    tracing::info!("成功连接到服务器: {}", addr);
+                    self.mark_success(&addr, started.elapsed()).await;
+                    admission.finish();
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    tracing::warn!("连接服务器 {} 失败: {}", addr, e);
+                    self.mark_failure(&addr, false).await;
+                    admission.finish();
+                }
+            }
+        }
+
+        Err(AppError::Network("无法连接到任何通达信服务器".to_string()))
+    }
+
+    /// 按顺序取出最靠前服务器的空闲连接
+    fn take_idle(&self, order: &[String]) -> Option<TdxConnection> {
+        let mut idle = self.lock_idle();
+
+        let index = idle
+            .iter()
+            .enumerate()
+            .filter_map(|(i, conn)| order.iter().position(|a| a == conn.addr()).map(|rank| (rank, i)))
+            .min()
+            .map(|(_, i)| i)?;
+
+        Some(idle.swap_remove(index))
+    }
+
+    /// 向熔断器申请连接名额（半开状态只放行一次试探）
+    fn try_acquire(&self, addr: &str) -> Option<Admission<'_>> {
+        let now = Instant::now();
+        let mut servers = self.lock_servers();
+        let server = servers.iter_mut().find(|s| s.addr == addr)?;
+        let trial = server.breaker.state(now) == BreakerState::HalfOpen;
+        server.breaker.try_acquire(now).then(|| Admission {
+            pool: self,
+            addr: addr.to_string(),
+            trial,
+        })
+    }
+
+    fn put_idle(&self, conn: TdxConnection) {
+        let mut idle = self.lock_idle();
+        if idle.len() < self.config.size {
+            idle.push(conn);
+        }
+    }
+
+    async fn mark_success(&self, addr: &str, latency: Duration) {
+        {
+            let mut servers = self.lock_servers();
+            if let Some(server) = servers.iter_mut().find(|s| s.addr == addr) {
+                // 指数平滑，避免单次抖动打乱排名
+                server.latency = Some(match server.latency {
+                    Some(prev) => prev.mul_f64(0.7) + latency.mul_f64(0.3),
+                    None => latency,
+                });
+                if server.breaker.state(Instant::now()) != BreakerState::Closed {
+                    tracing::info!("服务器 {} 恢复，熔断器关闭", addr);
+                }
+                server.breaker.on_success();
+            }
+        }
+
+        if let Some(monitor) = &self.monitor {
+            monitor
+                .update_server_health(addr.to_string(), true, latency.as_secs_f64() * 1000.0)
+                .await;
+        }
+    }
+
+    /// 记录失败；`trip` 为真时立即熔断
+    async fn mark_failure(&self, addr: &str, trip: bool) {
+        {
+            let now = Instant::now();
+            let mut servers = self.lock_servers();
+            if let Some(server) = servers.iter_mut().find(|s| s.addr == addr) {
+                let mut opened = server.breaker.on_failure(now);
+                if trip && !opened {
+                    server.breaker.trip(now);
+                    opened = true;
+                }
+                if opened {
+                    tracing::warn!("服务器 {} 熔断，{:?} 后试探恢复", addr, self.config.cooldown);
+                }
+            }
+        }
+        self.lock_idle().retain(|conn| conn.addr() != addr);
+
+        if let Some(monitor) = &self.monitor {
+            monitor.update_server_health(addr.to_string(), false, 0.0).await;
+        }
+    }
+
+    fn lock_servers(&self) -> std::sync::MutexGuard<'_, Vec<ServerState>> {
+        self.servers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_idle(&self) -> std::sync::MutexGuard<'_, Vec<TdxConnection>> {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 熔断器放行的连接名额
+///
+/// 半开试探在记录结果前被丢弃（如导入取消时丢弃进行中的请求）会重新熔断，
+/// 否则该服务器会一直处于“试探中”而不再被选择。
+struct Admission<'a> {
+    pool: &'a ServerPool,
+    addr: String,
+    trial: bool,
+}
+
+impl Admission<'_> {
+    /// 结果已记录到熔断器
+    fn finish(mut self) {
+        self.trial = false;
+    }
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        if !self.trial {
+            return;
+        }
+        let mut servers = self.pool.lock_servers();
+        if let Some(server) = servers.iter_mut().find(|s| s.addr == self.addr) {
+            tracing::warn!("服务器 {} 的试探请求被取消，重新熔断", self.addr);
+            server.breaker.abandon_trial(Instant::now());
+        }
+    }
+}
+
+/// 排序键：近期无失败优先，其次已测量的按延迟升序，未测量的排在最后
+fn rank_key(server: &ServerState) -> (bool, bool, Duration) {
+    (
+        server.breaker.consecutive_failures() > 0,
+        server.latency.is_none(),
+        server.latency.unwrap_or_default(),
+    )
+}
+
+/// 校验地址格式后连接并握手
+pub(crate) async fn connect_server(addr: &str, timeout: Duration) -> Result<TdxConnection> {
+    let parts: Vec<&str> = addr.split(':').collect();
+    if parts.len() != 2 {
+        return Err(AppError::Config(format!("无效的地址格式: {}", addr)));
+    }
+
+    let _port: u16 = parts[1]
+        .parse()
+        .map_err(|_| AppError::Config(format!("无效的端口号: {}", parts[1])))?;
+
+    TdxConnection::connect(addr, timeout).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::mock_server::{closed_addr, MockData, MockTdxServer};
+    use crate::collector::protocol;
+    use crate::models::stock::Market;
+
+    fn count_request() -> Request {
+        protocol::security_count_request(Market::SZ)
+    }
+
+    #[tokio::test]
+    async fn test_probe_ranks_by_latency() {
+        let slow = MockTdxServer::start(MockData::default().with_delay(Duration::from_millis(40))).await;
+        let fast = MockTdxServer::start(MockData::default()).await;
+        let dead = closed_addr().await;
+
+        let pool = ServerPool::new(vec![slow.addr(), dead.clone(), fast.addr()], PoolConfig::default());
+        assert_eq!(pool.probe_all().await, 2);
+
+        assert_eq!(pool.current_server(), fast.addr());
+        let snapshot = pool.snapshot();
+        assert_eq!(snapshot[0].addr, fast.addr());
+        assert_eq!(snapshot[1].addr, slow.addr());
+        assert_eq!(snapshot[2].addr, dead);
+        assert!(!snapshot[2].healthy);
+        assert_eq!(snapshot[2].fail_count, 1);
+        assert_eq!(snapshot[2].breaker, BreakerState::Open);
+
+        // 复用探测时保留的连接，不再握手
+        let before = fast.request_count();
+        pool.execute(&count_request()).await.unwrap();
+        assert_eq!(fast.request_count(), before + 1);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_at_most_size_connections() {
+        let servers: Vec<MockTdxServer> = start_servers(3).await;
+        let addrs = servers.iter().map(|s| s.addr()).collect();
+
+        let pool = ServerPool::new(addrs, PoolConfig::default()).with_size(2);
+        assert_eq!(pool.probe_all().await, 3);
+        assert_eq!(pool.lock_idle().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_evict_and_readmit_after_cooldown() {
+        let server = MockTdxServer::start(MockData::default()).await;
+        let pool = ServerPool::new(vec![server.addr()], PoolConfig::default())
+            .with_cooldown(Duration::from_millis(100))
+            .with_failure_threshold(2);
+
+        pool.execute(&count_request()).await.unwrap();
+        pool.mark_failure(&server.addr(), false).await;
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::Closed);
+        pool.mark_failure(&server.addr(), false).await;
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::Open);
+
+        // 冷却期内无可用服务器
+        assert!(matches!(pool.execute(&count_request()).await, Err(AppError::Network(_))));
+        assert!(pool.lock_idle().is_empty());
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::HalfOpen);
+        assert!(pool.execute(&count_request()).await.is_ok());
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::Closed);
+        assert_eq!(pool.snapshot()[0].fail_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_trial_failure_reopens() {
+        let dead = closed_addr().await;
+        let pool = ServerPool::new(vec![dead], PoolConfig::default())
+            .with_cooldown(Duration::from_millis(50));
+
+        assert_eq!(pool.probe_all().await, 0);
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::HalfOpen);
+
+        // 试探失败，重新熔断
+        assert!(pool.execute(&count_request()).await.is_err());
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_half_open_trial_reopens() {
+        // 接受连接但不响应握手
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                held.push(stream);
+            }
+        });
+        let pool = ServerPool::new(vec![addr.clone()], PoolConfig::default())
+            .with_cooldown(Duration::from_millis(50))
+            .with_timeout(Duration::from_secs(5));
+
+        pool.mark_failure(&addr, true).await;
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::HalfOpen);
+
+        // 试探在握手中被丢弃
+        let dropped = tokio::time::timeout(Duration::from_millis(50), pool.execute(&count_request())).await;
+        assert!(dropped.is_err());
+        assert_eq!(pool.snapshot()[0].breaker, BreakerState::Open);
+
+        // 冷却后可以再次试探
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(pool.ranked(), vec![addr]);
+    }
+
+    #[tokio::test]
+    async fn test_reports_to_monitor() {
+        let server = MockTdxServer::start(MockData::default()).await;
+        let dead = closed_addr().await;
+        let monitor = Arc::new(CollectorMonitor::new(vec![server.addr(), dead.clone()]));
+
+        let pool = ServerPool::new(vec![server.addr(), dead.clone()], PoolConfig::default())
+            .with_monitor(monitor.clone());
+        pool.probe_all().await;
+
+        let metrics = monitor.get_metrics().await;
+        let health = |addr: &str| metrics.servers.iter().find(|s| s.addr == addr).unwrap().clone();
+        assert!(health(&server.addr()).is_healthy);
+        assert!(!health(&dead).is_healthy);
+        assert_eq!(health(&dead).fail_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_server_budget_spreads_requests() {
+        let slow = MockTdxServer::start(MockData::default().with_delay(Duration::from_millis(5))).await;
+        let fast = MockTdxServer::start(MockData::default()).await;
+        let pool = ServerPool::new(vec![slow.addr(), fast.addr()], PoolConfig::default())
+            .with_size(2)
+            .with_server_budget(20.0);
+        assert_eq!(pool.probe_all().await, 2);
+        let before = (slow.request_count(), fast.request_count());
+
+        // 每台每秒 20 次：8 个请求两台各 4 个，每台间隔 50ms
+        let started = Instant::now();
+        for _ in 0..8 {
+            pool.execute(&count_request()).await.unwrap();
+        }
+        assert_eq!(slow.request_count() - before.0, 4);
+        assert_eq!(fast.request_count() - before.1, 4);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_address() {
+        let timeout = Duration::from_millis(100);
+        assert!(matches!(connect_server("127.0.0.1", timeout).await, Err(AppError::Config(_))));
+        assert!(matches!(connect_server("127.0.0.1:port", timeout).await, Err(AppError::Config(_))));
+    }
+
+    async fn start_servers(n: usize) -> Vec<MockTdxServer> {
+        let mut servers = Vec::with_capacity(n);
+        for _ in 0..n {
+            servers.push(MockTdxServer::start(MockData::default()).await);
+        }
+        servers
+    }
+}
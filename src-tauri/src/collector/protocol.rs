@@ -0,0 +1,713 @@
+//! 通达信行情协议（7709 端口）编解码
+//!
+//! 请求包格式：`0x0c | seq(u32) | type(u8) | len(u16) | len(u16) | cmd(u16) | body`，
+//! 其中 `len = body 长度 + 2`。
+//!
+//! 响应包为 16 字节头 + body，头部最后两个 u16 分别是 body 的压缩后长度与原始长度，
+//! 二者不相等时 body 为 zlib 压缩数据。
+
+use crate::collector::clock;
+use crate::error::{AppError, Result};
+use crate::models::money_flow::{TradeDirection, Transaction};
+use crate::models::quote::{KLine, KLinePeriod, Quote};
+use crate::models::stock::Market;
+use chrono::{Datelike, NaiveDate, Utc};
+use flate2::read::ZlibDecoder;
+use std::io::Read;
+
+/// 响应头长度
+pub const RESPONSE_HEADER_LEN: usize = 16;
+
+/// 握手命令
+pub const CMD_SETUP: u16 = 0x000d;
+pub const CMD_SETUP_EXT: u16 = 0x0fdb;
+/// 证券数量
+pub const CMD_SECURITY_COUNT: u16 = 0x044e;
+/// 证券列表
+pub const CMD_SECURITY_LIST: u16 = 0x0450;
+/// 实时行情（五档）
+pub const CMD_QUOTES: u16 = 0x053e;
+/// K 线
+pub const CMD_BARS: u16 = 0x052d;
+/// 当日逐笔成交
+pub const CMD_TRANSACTIONS: u16 = 0x0fc5;
+/// 历史逐笔成交
+pub const CMD_HISTORY_TRANSACTIONS: u16 = 0x0fb5;
+/// 基本财务信息（股本、上市日期）
+pub const CMD_FINANCE_INFO: u16 = 0x0010;
+
+/// 单次证券列表请求返回的最大条数
+pub const SECURITY_LIST_PAGE_SIZE: usize = 1000;
+/// 单次实时行情请求的最大股票数
+pub const MAX_QUOTES_PER_REQUEST: usize = 80;
+/// 单次 K 线请求的最大条数
+pub const MAX_BARS_PER_REQUEST: u16 = 800;
+/// 单次逐笔成交请求的最大条数
+pub const MAX_TRANSACTIONS_PER_REQUEST: u16 = 2000;
+
+/// 证券列表单条记录长度
+const SECURITY_RECORD_LEN: usize = 29;
+
+/// 协议请求
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub kind: u8,
+    pub cmd: u16,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    fn new(kind: u8, cmd: u16, body: Vec<u8>) -> Self {
+        Self { kind, cmd, body }
+    }
+
+    /// 编码为网络字节流
+    pub fn encode(&self, seq: u32) -> Vec<u8> {
+        let len = (self.body.len() + 2) as u16;
+        let mut buf = Vec::with_capacity(12 + self.body.len());
+        buf.push(0x0c);
+        buf.extend_from_slice(&seq.to_le_bytes());
+        buf.push(self.kind);
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(&self.cmd.to_le_bytes());
+        buf.extend_from_slice(&self.body);
+        buf
+    }
+}
+
+/// 响应头
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseHeader {
+    pub zip_size: usize,
+    pub unzip_size: usize,
+}
+
+impl ResponseHeader {
+    /// 解析 16 字节响应头
+    pub fn parse(buf: &[u8; RESPONSE_HEADER_LEN]) -> Self {
+        Self {
+            zip_size: u16::from_le_bytes([buf[12], buf[13]]) as usize,
+            unzip_size: u16::from_le_bytes([buf[14], buf[15]]) as usize,
+        }
+    }
+
+    /// 解压响应 body（未压缩时原样返回）
+    pub fn decode_body(&self, body: Vec<u8>) -> Result<Vec<u8>> {
+        if self.zip_size == self.unzip_size {
+            return Ok(body);
+        }
+
+        let mut out = Vec::with_capacity(self.unzip_size);
+        ZlibDecoder::new(body.as_slice())
+            .read_to_end(&mut out)
+            .map_err(|e| AppError::Parse(format!("响应解压失败: {}", e)))?;
+
+        if out.len() != self.unzip_size {
+            return Err(AppError::Parse(format!(
+                "解压后长度不符: 期望 {}, 实际 {}",
+                self.unzip_size,
+                out.len()
+            )));
+        }
+
+        Ok(out)
+    }
+}
+
+/// 证券列表条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityInfo {
+    pub market: Market,
+    pub code: String,
+    pub name: String,
+    pub volunit: u16,       // 每手股数
+    pub decimal_point: u8,  // 价格小数位
+    pub pre_close: f64,     // 昨收价
+}
+
+/// 基本财务信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinanceInfo {
+    pub market: Market,
+    pub code: String,
+    pub list_date: Option<NaiveDate>,     // 上市日期
+    pub updated_date: Option<NaiveDate>,  // 财务数据更新日期
+    pub total_shares: f64,                // 总股本（股）
+    pub float_shares: f64,                // 流通股本（股）
+}
+
+/// 通达信市场编号
+pub fn market_id(market: Market) -> u8 {
+    match market {
+        Market::SZ => 0,
+        Market::SH => 1,
+        Market::BJ => 2,
+    }
+}
+
+/// 由通达信市场编号得到市场
+pub fn market_from_id(id: u8) -> Option<Market> {
+    match id {
+        0 => Some(Market::SZ),
+        1 => Some(Market::SH),
+        2 => Some(Market::BJ),
+        _ => None,
+    }
+}
+
+/// 握手请求（连接建立后依次发送）
+pub fn setup_requests() -> Vec<Request> {
+    vec![
+        Request::new(0x01, CMD_SETUP, vec![0x01]),
+        Request::new(0x01, CMD_SETUP, vec![0x02]),
+        Request::new(
+            0x01,
+            CMD_SETUP_EXT,
+            vec![
+                0xd5, 0xd0, 0xc9, 0xcc, 0xd6, 0xa4, 0xa8, 0xaf, 0x00, 0x00, 0x00, 0x8f, 0xc2, 0x25,
+                0x40, 0x13, 0x00, 0x00, 0xd5, 0x00, 0xc9, 0xcc, 0xbd, 0xf0, 0xd7, 0xea, 0x00, 0x00,
+                0x00, 0x02,
+            ],
+        ),
+    ]
+}
+
+/// 证券数量请求
+pub fn security_count_request(market: Market) -> Request {
+    let mut body = (market_id(market) as u16).to_le_bytes().to_vec();
+    body.extend_from_slice(&[0x75, 0xc7, 0x33, 0x01]);
+    Request::new(0x01, CMD_SECURITY_COUNT, body)
+}
+
+/// 证券列表请求（从 start 开始，每页最多 1000 条）
+pub fn security_list_request(market: Market, start: u16) -> Request {
+    let mut body = (market_id(market) as u16).to_le_bytes().to_vec();
+    body.extend_from_slice(&start.to_le_bytes());
+    Request::new(0x01, CMD_SECURITY_LIST, body)
+}
+
+/// 实时行情请求
+pub fn quotes_request(codes: &[(Market, String)]) -> Result<Request> {
+    if codes.len() > MAX_QUOTES_PER_REQUEST {
+        return Err(AppError::Internal(format!(
+            "单次行情请求最多 {} 只股票",
+            MAX_QUOTES_PER_REQUEST
+        )));
+    }
+
+    let mut body = vec![0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    body.extend_from_slice(&(codes.len() as u16).to_le_bytes());
+    for (market, code) in codes {
+        body.push(market_id(*market));
+        body.extend_from_slice(&code_bytes(code)?);
+    }
+    Ok(Request::new(0x02, CMD_QUOTES, body))
+}
+
+/// K 线周期对应的通达信类别编号
+pub fn bar_category(period: KLinePeriod) -> u16 {
+    match period {
+        KLinePeriod::Minute5 => 0,
+        KLinePeriod::Week => 5,
+        KLinePeriod::Month => 6,
+        KLinePeriod::Minute1 => 8,
+        KLinePeriod::Day => 9,
+    }
+}
+
+/// 分钟类别的时间字段为 `zipday(u16) + 分钟数(u16)`，其余为 `yyyymmdd(u32)`
+pub fn is_intraday_category(category: u16) -> bool {
+    category < 4 || category == 7 || category == 8
+}
+
+/// K 线请求（start 为距最新一根的偏移，0 表示从最新开始向前取）
+pub fn bars_request(market: Market, code: &str, period: KLinePeriod, start: u16, count: u16) -> Result<Request> {
+    let mut body = (market_id(market) as u16).to_le_bytes().to_vec();
+    body.extend_from_slice(&code_bytes(code)?);
+    body.extend_from_slice(&bar_category(period).to_le_bytes());
+    body.extend_from_slice(&1u16.to_le_bytes());
+    body.extend_from_slice(&start.to_le_bytes());
+    body.extend_from_slice(&count.to_le_bytes());
+    body.extend_from_slice(&[0u8; 10]);
+    Ok(Request::new(0x01, CMD_BARS, body))
+}
+
+/// 逐笔成交请求（start 为距最新一笔的偏移）
+///
+/// `date` 为空时查询当日，否则查询指定历史日期。
+pub fn transactions_request(
+    market: Market,
+    code: &str,
+    date: Option<NaiveDate>,
+    start: u16,
+    count: u16,
+) -> Result<Request> {
+    let mut body = Vec::with_capacity(16);
+    let cmd = match date {
+        Some(date) => {
+            body.extend_from_slice(&yyyymmdd(date).to_le_bytes());
+            CMD_HISTORY_TRANSACTIONS
+        }
+        None => CMD_TRANSACTIONS,
+    };
+    body.extend_from_slice(&(market_id(market) as u16).to_le_bytes());
+    body.extend_from_slice(&code_bytes(code)?);
+    body.extend_from_slice(&start.to_le_bytes());
+    body.extend_from_slice(&count.to_le_bytes());
+    Ok(Request::new(0x01, cmd, body))
+}
+
+/// 基本财务信息请求
+pub fn finance_info_request(market: Market, code: &str) -> Result<Request> {
+    let mut body = 1u16.to_le_bytes().to_vec();
+    body.push(market_id(market));
+    body.extend_from_slice(&code_bytes(code)?);
+    Ok(Request::new(0x01, CMD_FINANCE_INFO, body))
+}
+
+/// 解析证券数量响应
+pub fn parse_security_count(body: &[u8]) -> Result<u16> {
+    BodyReader::new(body).u16()
+}
+
+/// 解析证券列表响应
+pub fn parse_security_list(market: Market, body: &[u8]) -> Result<Vec<SecurityInfo>> {
+    let mut reader = BodyReader::new(body);
+    let count = reader.u16()? as usize;
+    let mut list = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let record = reader.bytes(SECURITY_RECORD_LEN)?;
+        let mut r = BodyReader::new(record);
+
+        let code = ascii_field(r.bytes(6)?);
+        let volunit = r.u16()?;
+        let name = gbk_field(r.bytes(8)?);
+        r.skip(4)?;
+        let decimal_point = r.u8()?;
+        let pre_close = decode_volume(r.u32()?);
+
+        list.push(SecurityInfo {
+            market,
+            code,
+            name,
+            volunit,
+            decimal_point,
+            pre_close,
+        });
+    }
+
+    Ok(list)
+}
+
+/// 解析实时行情响应
+pub fn parse_quotes(body: &[u8]) -> Result<Vec<Quote>> {
+    let mut reader = BodyReader::new(body);
+    reader.skip(2)?;
+    let count = reader.u16()? as usize;
+    let mut quotes = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let _market = reader.u8()?;
+        let code = ascii_field(reader.bytes(6)?);
+        let _active1 = reader.u16()?;
+
+        let price = reader.price()?;
+        let preclose = price + reader.price()?;
+        let open = price + reader.price()?;
+        let high = price + reader.price()?;
+        let low = price + reader.price()?;
+        let _server_time = reader.price()?;
+        let _reversed1 = reader.price()?;
+        let volume = reader.price()?;
+        let _cur_volume = reader.price()?;
+        let amount = decode_volume(reader.u32()?);
+        let _sell_volume = reader.price()?;
+        let _buy_volume = reader.price()?;
+        let _reversed2 = reader.price()?;
+        let _reversed3 = reader.price()?;
+
+        let mut bid = [0.0; 5];
+        let mut bid_vol = [0.0; 5];
+        let mut ask = [0.0; 5];
+        let mut ask_vol = [0.0; 5];
+        for level in 0..5 {
+            bid[level] = scale_price(price + reader.price()?);
+            ask[level] = scale_price(price + reader.price()?);
+            bid_vol[level] = reader.price()? as f64;
+            ask_vol[level] = reader.price()? as f64;
+        }
+
+        let _reversed4 = reader.u16()?;
+        for _ in 0..4 {
+            reader.price()?;
+        }
+        reader.skip(4)?;
+
+        quotes.push(Quote {
+            code,
+            name: String::new(),
+            price: scale_price(price),
+            preclose: scale_price(preclose),
+            open: scale_price(open),
+            high: scale_price(high),
+            low: scale_price(low),
+            volume: volume as f64,
+            amount,
+            bid,
+            bid_vol,
+            ask,
+            ask_vol,
+            timestamp: Utc::now(),
+        });
+    }
+
+    Ok(quotes)
+}
+
+/// 解析 K 线响应（按时间升序）
+///
+/// 价格单位为 0.001 元，开盘价相对上一根收盘价差分编码，收/高/低相对本根开盘价差分编码。
+pub fn parse_bars(code: &str, period: KLinePeriod, body: &[u8]) -> Result<Vec<KLine>> {
+    let category = bar_category(period);
+    let mut reader = BodyReader::new(body);
+    let count = reader.u16()? as usize;
+    let mut bars = Vec::with_capacity(count);
+    let mut pre_close = 0i64;
+
+    for _ in 0..count {
+        let (date, hour, minute) = if is_intraday_category(category) {
+            let zipday = reader.u16()? as i32;
+            let minutes = reader.u16()? as u32;
+            let date = NaiveDate::from_ymd_opt((zipday >> 11) + 2004, ((zipday % 2048) / 100) as u32, ((zipday % 2048) % 100) as u32);
+            (date, minutes / 60, minutes % 60)
+        } else {
+            let yyyymmdd = reader.u32()?;
+            let date = NaiveDate::from_ymd_opt((yyyymmdd / 10000) as i32, (yyyymmdd % 10000) / 100, yyyymmdd % 100);
+            (date, 15, 0)
+        };
+
+        let datetime = date
+            .and_then(|d| d.and_hms_opt(hour, minute, 0))
+            .ok_or_else(|| AppError::Parse(format!("K 线时间无效: {} {:?} {}:{}", code, date, hour, minute)))?;
+
+        let open = pre_close + reader.price()?;
+        let close = open + reader.price()?;
+        let high = open + reader.price()?;
+        let low = open + reader.price()?;
+        let volume = decode_volume(reader.u32()?);
+        let amount = decode_volume(reader.u32()?);
+        pre_close = close;
+
+        bars.push(KLine {
+            datetime: clock::to_utc(datetime),
+            code: code.to_string(),
+            open: open as f64 / 1000.0,
+            high: high as f64 / 1000.0,
+            low: low as f64 / 1000.0,
+            close: close as f64 / 1000.0,
+            volume,
+            amount,
+        });
+    }
+
+    Ok(bars)
+}
+
+/// 解析逐笔成交响应
+///
+/// 当日与历史响应格式略有不同：历史响应在条数后多 4 字节，且每笔没有成交笔数字段。
+/// 协议只给到分钟，时间按 `date` 当日的北京时间换算。
+pub fn parse_transactions(code: &str, date: NaiveDate, history: bool, body: &[u8]) -> Result<Vec<Transaction>> {
+    let mut reader = BodyReader::new(body);
+    let count = reader.u16()? as usize;
+    if history {
+        reader.skip(4)?;
+    }
+
+    let mut list = Vec::with_capacity(count);
+    let mut price = 0i64;
+
+    for _ in 0..count {
+        let minutes = reader.u16()? as u32;
+        price += reader.price()?;
+        let volume = reader.price()? as f64;
+        let num = if history { 0 } else { reader.price()? as u32 };
+        let direction = match reader.price()? {
+            0 => TradeDirection::Buy,
+            1 => TradeDirection::Sell,
+            _ => TradeDirection::Neutral,
+        };
+        reader.price()?; // 保留字段
+
+        let datetime = date
+            .and_hms_opt(minutes / 60, minutes % 60, 0)
+            .ok_or_else(|| AppError::Parse(format!("逐笔成交时间无效: {} {}", code, minutes)))?;
+        let price_yuan = scale_price(price);
+
+        list.push(Transaction {
+            code: code.to_string(),
+            datetime: clock::to_utc(datetime),
+            price: price_yuan,
+            volume,
+            amount: price_yuan * volume * 100.0,
+            num,
+            direction,
+        });
+    }
+
+    Ok(list)
+}
+
+/// 解析基本财务信息响应
+///
+/// 记录为 `market(u8) code(6) 流通股本(f32) 省份(u16) 行业(u16) 更新日期(u32) 上市日期(u32) 总股本(f32) ...`，
+/// 股本单位为万股，其后的资产负债等字段不解析。未上市或无数据时日期为 0。
+pub fn parse_finance_info(body: &[u8]) -> Result<FinanceInfo> {
+    let mut reader = BodyReader::new(body);
+    reader.skip(2)?; // 记录数，固定为 1
+    let market_byte = reader.u8()?;
+    let market = market_from_id(market_byte)
+        .ok_or_else(|| AppError::Parse(format!("未知市场编号: {}", market_byte)))?;
+    let code = ascii_field(reader.bytes(6)?);
+
+    let float_shares = reader.f32()? as f64 * 10000.0;
+    reader.skip(4)?; // 省份、行业
+    let updated_date = date_from_yyyymmdd(reader.u32()?);
+    let list_date = date_from_yyyymmdd(reader.u32()?);
+    let total_shares = reader.f32()? as f64 * 10000.0;
+
+    Ok(FinanceInfo {
+        market,
+        code,
+        list_date,
+        updated_date,
+        total_shares,
+        float_shares,
+    })
+}
+
+/// 日期编码为 yyyymmdd
+fn yyyymmdd(date: NaiveDate) -> u32 {
+    date.year() as u32 * 10000 + date.month() * 100 + date.day()
+}
+
+/// yyyymmdd 解码为日期，0 或非法日期返回 None
+fn date_from_yyyymmdd(value: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt((value / 10000) as i32, value / 100 % 100, value % 100)
+}
+
+/// 行情价格单位为 0.01 元
+fn scale_price(raw: i64) -> f64 {
+    raw as f64 / 100.0
+}
+
+/// 解码通达信自定义浮点格式（成交额、昨收等字段）
+pub fn decode_volume(raw: u32) -> f64 {
+    let logpoint = (raw >> 24) as i32;
+    let hleax = ((raw >> 16) & 0xff) as i32;
+    let lheax = ((raw >> 8) & 0xff) as f64;
+    let lleax = (raw & 0xff) as f64;
+
+    let exp_ecx = logpoint * 2 - 0x7f;
+    let exp_edx = logpoint * 2 - 0x86;
+    let exp_esi = logpoint * 2 - 0x8e;
+    let exp_eax = logpoint * 2 - 0x96;
+
+    let base = 2f64.powi(exp_ecx);
+    let high = if hleax > 0x80 {
+        2f64.powi(exp_edx) * 128.0 + (hleax & 0x7f) as f64 * 2f64.powi(exp_edx + 1)
+    } else {
+        2f64.powi(exp_edx) * hleax as f64
+    };
+
+    let mut mid = 2f64.powi(exp_esi) * lheax;
+    let mut low = 2f64.powi(exp_eax) * lleax;
+    if hleax & 0x80 != 0 {
+        mid *= 2.0;
+        low *= 2.0;
+    }
+
+    base + high + mid + low
+}
+
+/// 6 位股票代码转为定长字节
+fn code_bytes(code: &str) -> Result<[u8; 6]> {
+    code.as_bytes()
+        .try_into()
+        .map_err(|_| AppError::Parse(format!("股票代码长度错误: {}", code)))
+}
+
+fn ascii_field(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn gbk_field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let (name, _, _) = encoding_rs::GBK.decode(&bytes[..end]);
+    name.trim().to_string()
+}
+
+/// 响应 body 读取器
+pub struct BodyReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BodyReader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos + len;
+        if end > self.buf.len() {
+            return Err(AppError::Parse(format!(
+                "响应数据不完整: 需要 {} 字节, 剩余 {} 字节",
+                len,
+                self.buf.len() - self.pos
+            )));
+        }
+        let slice = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    /// 变长有符号整数：首字节低 6 位为数值，0x40 为符号位，0x80 为续位；
+    /// 后续字节低 7 位依次左移拼接
+    pub fn price(&mut self) -> Result<i64> {
+        let first = self.u8()?;
+        let negative = first & 0x40 != 0;
+        let mut value = (first & 0x3f) as i64;
+        let mut shift = 6;
+        let mut more = first & 0x80 != 0;
+
+        while more {
+            let b = self.u8()?;
+            value += ((b & 0x7f) as i64) << shift;
+            shift += 7;
+            more = b & 0x80 != 0;
+        }
+
+        Ok(if negative { -value } else { value })
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_encode() {
+        let packet = security_count_request(Market::SH).encode(0x006c180c);
+        assert_eq!(
+            packet,
+            vec![0x0c, 0x0c, 0x18, 0x6c, 0x00, 0x01, 0x08, 0x00, 0x08, 0x00, 0x4e, 0x04, 0x01, 0x00, 0x75, 0xc7, 0x33, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_price_varint() {
+        // 0x85 0x01: 低 6 位 5，续位 1 << 6 = 64，合计 69
+        let mut reader = BodyReader::new(&[0x85, 0x01, 0x45, 0x00]);
+        assert_eq!(reader.price().unwrap(), 69);
+        assert_eq!(reader.price().unwrap(), -5);
+        assert_eq!(reader.price().unwrap(), 0);
+        assert!(reader.price().is_err());
+    }
+
+    #[test]
+    fn test_decode_volume() {
+        // 指数 0x40 对应 2^1，尾数全 0
+        assert_eq!(decode_volume(0x4000_0000), 2.0);
+        // 0x41 对应 2^3，hleax=0x40 再加 2^3 * 0x40 / 128
+        assert_eq!(decode_volume(0x4140_0000), 12.0);
+    }
+
+    #[test]
+    fn test_body_reader_truncated() {
+        let mut reader = BodyReader::new(&[0x01]);
+        assert!(reader.u16().is_err());
+    }
+
+    #[test]
+    fn test_bars_request_encode() {
+        let packet = bars_request(Market::SZ, "000001", KLinePeriod::Day, 0, 800)
+            .unwrap()
+            .encode(0x01016408);
+        // 头部 12 字节 + body 26 字节，长度字段 0x1c
+        assert_eq!(packet.len(), 38);
+        assert_eq!(&packet[6..12], &[0x1c, 0x00, 0x1c, 0x00, 0x2d, 0x05]);
+        assert_eq!(&packet[14..20], b"000001");
+        assert_eq!(&packet[20..22], &9u16.to_le_bytes());
+    }
+
+    #[test]
+    fn test_parse_bars_intraday_time() {
+        // 2024-01-02 09:31：zipday = (2024-2004)<<11 | 102，分钟数 571
+        let mut body = 1u16.to_le_bytes().to_vec();
+        body.extend_from_slice(&((((2024 - 2004) << 11) | 102) as u16).to_le_bytes());
+        body.extend_from_slice(&571u16.to_le_bytes());
+        body.extend_from_slice(&[0x90, 0x9c, 0x01, 0x0a, 0x0a, 0x00]); // 开 10.0 收 10.01 高 10.01 低 10.0
+        body.extend_from_slice(&0x4000_0000u32.to_le_bytes());
+        body.extend_from_slice(&0x4000_0000u32.to_le_bytes());
+
+        let bars = parse_bars("000001", KLinePeriod::Minute1, &body).unwrap();
+        assert_eq!(bars.len(), 1);
+        assert_eq!(clock::to_local(bars[0].datetime).to_string(), "2024-01-02 09:31:00");
+        assert_eq!(bars[0].open, 10.0);
+        assert_eq!(bars[0].close, 10.01);
+        assert_eq!(bars[0].volume, 2.0);
+    }
+
+    #[test]
+    fn test_quotes_request_limit() {
+        let codes: Vec<(Market, String)> = (0..81)
+            .map(|i| (Market::SZ, format!("{:06}", i)))
+            .collect();
+        assert!(quotes_request(&codes).is_err());
+    }
+
+    #[test]
+    fn test_transactions_request_encode() {
+        let today = transactions_request(Market::SZ, "000001", None, 0, 2000).unwrap().encode(1);
+        assert_eq!(&today[10..12], &CMD_TRANSACTIONS.to_le_bytes());
+        assert_eq!(u16::from_le_bytes([today[6], today[7]]), 14);
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let history = transactions_request(Market::SH, "600000", Some(date), 10, 20).unwrap().encode(1);
+        assert_eq!(&history[10..12], &CMD_HISTORY_TRANSACTIONS.to_le_bytes());
+        assert_eq!(&history[12..16], &20240102u32.to_le_bytes());
+        assert_eq!(&history[16..18], &1u16.to_le_bytes());
+        assert_eq!(&history[18..24], b"600000");
+    }
+}
@@ -0,0 +1,953 @@
+//! 多数据源对账 - 按代码和交易日比较各数据源的日线，选出采用值写入 `factor` 表
+//!
+//! 数据源可以是多台通达信服务器，也可以是服务器加本地 .day 文件。各数据源按交易日比较 OHLCV：
+//! 价格差异超过 `price_tolerance` 元或成交量、成交额相对差异超过 `volume_tolerance` 的记为异常，
+//! 部分数据源缺少的交易日记为遗漏，均写入质量日志。采用值取 `precedence` 中最靠前且有数据的
+//! 数据源，复权因子由采用值重新计算。采用值按验证规则（[`RuleEngine`]）检查，结果写入
+//! `factor.quality_score`，未通过的同时记入质量日志。
+//!
+//! 每个交易日的采用值保存在本地 `factor_versions` 表：首次写入版本号为 1，之后采用值、来源或
+//! 因子变化时版本号加 1 并重新写入 `factor`，未变化的交易日不重复写入。`factor` 表为
+//! `ReplacingMergeTree(data_version)`，旧版本在合并时被替换，查询加 `FINAL` 即只见最新版本。
+//! 区间前一交易日已有对账结果时以其收盘价与因子接续计算，否则区间首日因子为 1，因此日终任务
+//! 首次对账某代码时从上市日开始，使因子包含对账窗口之前的全部除权。
+
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::collector::factor::{FactorCalculator, FactorData, FactorSource};
+use crate::collector::jobs::DailyJob;
+use crate::collector::parser::{DayData, GbbqRecord, Parser};
+use crate::collector::rules::RuleEngine;
+use crate::collector::source::DataSource;
+use crate::collector::validator::{PriceLimit, QualityScore};
+use crate::collector::writer::{BatchSink, LogSink};
+use crate::config::ReconcileConfig;
+use crate::db::LocalDb;
+use crate::error::{AppError, Result};
+use crate::models::quality::{IssueType, QualityIssue, Severity};
+use crate::models::quote::{KLine, KLinePeriod};
+use crate::models::stock::{Market, Security, SecurityType};
+use crate::service::SecurityService;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 参与对账的日线数据源
+#[async_trait]
+pub trait DailyBars: Send + Sync {
+    /// 数据源名称（`precedence` 中使用）
+    fn name(&self) -> &str;
+
+    /// 写入 `factor.data_source` 的来源类别
+    fn kind(&self) -> FactorSource;
+
+    /// `[start, end]` 内的日线（不复权）
+    async fn daily_bars(&self, code: &str, start: NaiveDate, end: NaiveDate) -> Result<Vec<DayData>>;
+}
+
+/// 行情接口数据源（如单台通达信服务器）
+pub struct RemoteBars {
+    name: String,
+    source: Arc<dyn DataSource>,
+}
+
+impl RemoteBars {
+    pub fn new(name: impl Into<String>, source: Arc<dyn DataSource>) -> Self {
+        Self {
+            name: name.into(),
+            source,
+        }
+    }
+}
+
+#[async_trait]
+impl DailyBars for RemoteBars {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> FactorSource {
+        FactorSource::Api
+    }
+
+    async fn daily_bars(&self, code: &str, start: NaiveDate, end: NaiveDate) -> Result<Vec<DayData>> {
+        let klines = self.source.get_kline(code, KLinePeriod::Day, start, end).await?;
+        Ok(klines
+            .into_iter()
+            .map(|k| DayData {
+                date: clock::local_date(k.datetime),
+                code: k.code,
+                open: k.open,
+                high: k.high,
+                low: k.low,
+                close: k.close,
+                volume: k.volume,
+                amount: k.amount,
+            })
+            .collect())
+    }
+}
+
+/// 本地通达信 .day 文件（`root` 为安装目录或 `vipdoc` 目录）
+pub struct DayFileBars {
+    name: String,
+    root: PathBuf,
+}
+
+impl DayFileBars {
+    pub fn new(name: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            root: root.into(),
+        }
+    }
+
+    fn day_file(&self, code: &str) -> Option<PathBuf> {
+        let market = match Market::from_code(code)? {
+            Market::SH => "sh",
+            Market::SZ => "sz",
+            Market::BJ => "bj",
+        };
+        let vipdoc = if self.root.join("vipdoc").is_dir() {
+            self.root.join("vipdoc")
+        } else {
+            self.root.clone()
+        };
+        Some(vipdoc.join(market).join("lday").join(format!("{}{}.day", market, code)))
+    }
+}
+
+#[async_trait]
+impl DailyBars for DayFileBars {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> FactorSource {
+        FactorSource::File
+    }
+
+    async fn daily_bars(&self, code: &str, start: NaiveDate, end: NaiveDate) -> Result<Vec<DayData>> {
+        let Some(path) = self.day_file(code).filter(|p| p.is_file()) else {
+            return Ok(Vec::new());
+        };
+        let days = tokio::task::spawn_blocking(move || Parser::parse_day_file(&path))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+        Ok(days.into_iter().filter(|d| d.date >= start && d.date <= end).collect())
+    }
+}
+
+/// 单只股票的对账结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Reconciliation {
+    pub rows: Vec<FactorData>,       // 新增或变化的 `factor` 行
+    pub unchanged: usize,            // 与上次对账相同、未重复写入的交易日数
+    pub issues: Vec<QualityIssue>,   // 数据源之间的差异
+}
+
+/// 本地保存的上次对账结果
+struct StoredRow {
+    data_source: String,
+    source_name: String,
+    day: DayData,
+    factor: f64,
+    data_version: u32,
+    quality_score: String,
+}
+
+/// 多数据源对账
+pub struct Reconciler {
+    db: Arc<LocalDb>,
+    sources: Vec<Arc<dyn DailyBars>>,
+    config: ReconcileConfig,
+    ex_rights: Vec<GbbqRecord>,
+    rules: Arc<RuleEngine>,
+    sink: Arc<dyn BatchSink<FactorData>>,
+    log: Arc<dyn BatchSink<QualityIssue>>,
+}
+
+impl Reconciler {
+    pub fn new(db: Arc<LocalDb>) -> Self {
+        Self {
+            db,
+            sources: Vec::new(),
+            config: ReconcileConfig::default(),
+            ex_rights: Vec::new(),
+            rules: Arc::new(RuleEngine::default()),
+            sink: Arc::new(LogSink),
+            log: Arc::new(LogSink),
+        }
+    }
+
+    /// 添加数据源，未在 `precedence` 中列出的按添加顺序排在最后
+    pub fn with_source(mut self, source: Arc<dyn DailyBars>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// 设置容差与优先顺序
+    pub fn with_config(mut self, config: ReconcileConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 计算复权因子使用的股本变迁记录
+    pub fn with_ex_rights(mut self, records: Vec<GbbqRecord>) -> Self {
+        self.ex_rights = records;
+        self
+    }
+
+    /// 设置采用值的验证规则（与采集流程共用同一 [`RuleEngine`] 时重新加载同时生效）
+    pub fn with_rules(mut self, rules: Arc<RuleEngine>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// 设置 `factor` 表写入目标
+    pub fn with_sink(mut self, sink: Arc<dyn BatchSink<FactorData>>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// 设置质量日志写入目标（`data_quality_log`）
+    pub fn with_log(mut self, log: Arc<dyn BatchSink<QualityIssue>>) -> Self {
+        self.log = log;
+        self
+    }
+
+    pub fn config(&self) -> &ReconcileConfig {
+        &self.config
+    }
+
+    /// `date` 之前是否已有对账结果（可接续计算因子）
+    pub fn has_history_before(&self, code: &str, date: NaiveDate) -> Result<bool> {
+        Ok(self.stored_before(code, date)?.is_some())
+    }
+
+    /// 对账 `[start, end]`，写入变化的 `factor` 行与差异
+    ///
+    /// 单个数据源失败时只用其余数据源对账（不记遗漏），全部失败时返回错误。
+    pub async fn reconcile(&self, code: &str, start: NaiveDate, end: NaiveDate) -> Result<Reconciliation> {
+        let mut fetched = Vec::with_capacity(self.sources.len());
+        let mut last_error = None;
+        for source in self.ranked_sources() {
+            match source.daily_bars(code, start, end).await {
+                Ok(days) => fetched.push((source, days)),
+                Err(e) => {
+                    tracing::warn!("对账 {} 获取 {} 日线失败: {}", source.name(), code, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        if fetched.is_empty() {
+            return Err(last_error.unwrap_or_else(|| AppError::Config("未配置对账数据源".to_string())));
+        }
+
+        let result = self.merge(code, start, end, &fetched)?;
+        if !result.issues.is_empty() {
+            self.log.write_batch(&result.issues).await?;
+        }
+        if !result.rows.is_empty() {
+            self.sink.write_batch(&result.rows).await?;
+            let names: HashMap<NaiveDate, &str> = result
+                .rows
+                .iter()
+                .filter_map(|row| Some((row.date, self.winner_name(&fetched, row)?)))
+                .collect();
+            self.save(&result.rows, &names)?;
+        }
+
+        tracing::info!(
+            "{} 对账 {} 至 {}: 写入 {} 条，未变化 {} 条，差异 {} 条",
+            code,
+            start,
+            end,
+            result.rows.len(),
+            result.unchanged,
+            result.issues.len()
+        );
+        Ok(result)
+    }
+
+    /// 按优先顺序排列的数据源
+    fn ranked_sources(&self) -> Vec<&Arc<dyn DailyBars>> {
+        let mut sources: Vec<&Arc<dyn DailyBars>> = self.sources.iter().collect();
+        sources.sort_by_key(|s| {
+            self.config
+                .precedence
+                .iter()
+                .position(|name| name == s.name())
+                .unwrap_or(self.config.precedence.len())
+        });
+        sources
+    }
+
+    /// 比较各数据源（已按优先顺序排列）并计算需要写入的行
+    fn merge(
+        &self,
+        code: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        fetched: &[(&Arc<dyn DailyBars>, Vec<DayData>)],
+    ) -> Result<Reconciliation> {
+        let now = Utc::now();
+        let issue = |date, issue_type, severity, description: String| QualityIssue {
+            log_time: now,
+            date,
+            code: code.to_string(),
+            issue_type,
+            description,
+            severity,
+        };
+
+        // 交易日 → 各数据源的日线（保持优先顺序）
+        let mut by_date: BTreeMap<NaiveDate, Vec<(usize, &DayData)>> = BTreeMap::new();
+        for (i, (_, days)) in fetched.iter().enumerate() {
+            for day in days {
+                by_date.entry(day.date).or_default().push((i, day));
+            }
+        }
+
+        let mut issues = Vec::new();
+        let mut winners: Vec<(usize, DayData)> = Vec::with_capacity(by_date.len());
+        for (date, bars) in &by_date {
+            let (winner, chosen) = bars[0];
+            let winner_name = fetched[winner].0.name();
+
+            for &(i, other) in &bars[1..] {
+                let differences = self.differences(chosen, other);
+                if !differences.is_empty() {
+                    issues.push(issue(
+                        *date,
+                        IssueType::Abnormal,
+                        Severity::Warning,
+                        format!(
+                            "{} 与 {} 不一致: {}，采用 {}",
+                            winner_name,
+                            fetched[i].0.name(),
+                            differences.join("，"),
+                            winner_name
+                        ),
+                    ));
+                }
+            }
+
+            let missing: Vec<&str> = (0..fetched.len())
+                .filter(|i| bars.iter().all(|(j, _)| j != i))
+                .map(|i| fetched[i].0.name())
+                .collect();
+            if !missing.is_empty() {
+                issues.push(issue(
+                    *date,
+                    IssueType::Missing,
+                    Severity::Info,
+                    format!("{} 缺少该日数据，采用 {}", missing.join("、"), winner_name),
+                ));
+            }
+
+            winners.push((winner, chosen.clone()));
+        }
+
+        let Some(first) = winners.first().map(|(_, day)| day.date) else {
+            return Ok(Reconciliation {
+                issues,
+                ..Default::default()
+            });
+        };
+
+        // 以上次对账的前一交易日接续计算因子
+        let seed = self.stored_before(code, first)?;
+        let mut days: Vec<DayData> = seed.iter().map(|s| s.day.clone()).collect();
+        days.extend(winners.iter().map(|(_, day)| day.clone()));
+        let market = Market::from_code(code)
+            .ok_or_else(|| AppError::Parse(format!("无法识别股票代码的市场: {}", code)))?;
+        let mut factors = FactorCalculator::calculate(market, &days, &self.ex_rights);
+        if let Some(seed) = &seed {
+            factors.remove(0);
+            for row in &mut factors {
+                row.factor *= seed.factor;
+            }
+        }
+
+        let stored = self.stored_between(code, start, end)?;
+        let rules = self.rules.current();
+        let limit = PriceLimit::for_code(code);
+        let mut rows = Vec::new();
+        let mut unchanged = 0;
+        for (i, (mut row, (winner, _))) in factors.into_iter().zip(&winners).enumerate() {
+            let source = fetched[*winner].0;
+            row.data_source = source.kind();
+
+            // 没有前一交易日时首日昨收取的是开盘价，不检查涨跌停
+            let preclose = (seed.is_some() || i > 0).then_some(row.preclose);
+            row.quality_score = match rules.check_kline(&to_kline(&row), preclose, limit) {
+                Ok(score) => score,
+                Err(e) => {
+                    issues.push(issue(
+                        row.date,
+                        IssueType::Abnormal,
+                        Severity::Error,
+                        format!("{} 的采用值未通过验证: {}", source.name(), e),
+                    ));
+                    QualityScore::Error
+                }
+            };
+
+            match stored.get(&row.date) {
+                Some(previous) if same_row(previous, &row, source.name()) => unchanged += 1,
+                previous => {
+                    row.data_version = previous.map_or(1, |p| p.data_version + 1);
+                    rows.push(row);
+                }
+            }
+        }
+
+        Ok(Reconciliation { rows, unchanged, issues })
+    }
+
+    /// 超出容差的字段
+    fn differences(&self, chosen: &DayData, other: &DayData) -> Vec<String> {
+        let price = |name: &str, a: f64, b: f64| {
+            ((a - b).abs() > self.config.price_tolerance + 1e-9).then(|| format!("{} {} / {}", name, a, b))
+        };
+        let amount = |name: &str, a: f64, b: f64| {
+            let base = a.abs().max(b.abs());
+            (base > 0.0 && (a - b).abs() / base > self.config.volume_tolerance)
+                .then(|| format!("{} {} / {}", name, a, b))
+        };
+
+        [
+            price("开盘", chosen.open, other.open),
+            price("最高", chosen.high, other.high),
+            price("最低", chosen.low, other.low),
+            price("收盘", chosen.close, other.close),
+            amount("成交量", chosen.volume, other.volume),
+            amount("成交额", chosen.amount, other.amount),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn winner_name<'a>(&self, fetched: &'a [(&Arc<dyn DailyBars>, Vec<DayData>)], row: &FactorData) -> Option<&'a str> {
+        fetched
+            .iter()
+            .find(|(_, days)| days.iter().any(|d| d.date == row.date))
+            .map(|(source, _)| source.name())
+    }
+
+    fn stored_before(&self, code: &str, date: NaiveDate) -> Result<Option<StoredRow>> {
+        self.db.with_conn(|conn| {
+            conn.query_row(
+                &format!("{} WHERE code = ?1 AND trade_date < ?2 ORDER BY trade_date DESC LIMIT 1", STORED_COLUMNS),
+                params![code, date.to_string()],
+                stored_from_row,
+            )
+            .optional()
+        })?
+        .transpose()
+    }
+
+    fn stored_between(&self, code: &str, start: NaiveDate, end: NaiveDate) -> Result<HashMap<NaiveDate, StoredRow>> {
+        let rows = self.db.with_conn(|conn| {
+            let mut stmt =
+                conn.prepare(&format!("{} WHERE code = ?1 AND trade_date BETWEEN ?2 AND ?3", STORED_COLUMNS))?;
+            let rows = stmt.query_map(params![code, start.to_string(), end.to_string()], stored_from_row)?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+        })?;
+
+        rows.into_iter()
+            .map(|row| row.map(|row| (row.day.date, row)))
+            .collect()
+    }
+
+    /// 保存已写入的采用值
+    fn save(&self, rows: &[FactorData], names: &HashMap<NaiveDate, &str>) -> Result<()> {
+        let updated_at = Utc::now().to_rfc3339();
+        self.db.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR REPLACE INTO factor_versions
+                     (code, trade_date, data_source, source_name, open, high, low, close, volume, amount,
+                      factor, data_version, quality_score, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                )?;
+                for row in rows {
+                    stmt.execute(params![
+                        row.code,
+                        row.date.to_string(),
+                        row.data_source.as_str(),
+                        names.get(&row.date).copied().unwrap_or_default(),
+                        row.open,
+                        row.high,
+                        row.low,
+                        row.close,
+                        row.volume,
+                        row.amount,
+                        row.factor,
+                        row.data_version,
+                        row.quality_score.as_str(),
+                        updated_at,
+                    ])?;
+                }
+            }
+            tx.commit()
+        })
+    }
+}
+
+const STORED_COLUMNS: &str = "SELECT code, trade_date, data_source, source_name, open, high, low, close, volume, amount,
+                                     factor, data_version, quality_score
+                              FROM factor_versions";
+
+fn stored_from_row(row: &rusqlite::Row) -> rusqlite::Result<Result<StoredRow>> {
+    let date: String = row.get(1)?;
+    let day = |date: NaiveDate| -> rusqlite::Result<DayData> {
+        Ok(DayData {
+            date,
+            code: row.get(0)?,
+            open: row.get(4)?,
+            high: row.get(5)?,
+            low: row.get(6)?,
+            close: row.get(7)?,
+            volume: row.get(8)?,
+            amount: row.get(9)?,
+        })
+    };
+    let date = match date.parse() {
+        Ok(date) => date,
+        Err(e) => return Ok(Err(AppError::Parse(format!("对账记录日期无效 {}: {}", date, e)))),
+    };
+
+    Ok(Ok(StoredRow {
+        data_source: row.get(2)?,
+        source_name: row.get(3)?,
+        day: day(date)?,
+        factor: row.get(10)?,
+        data_version: row.get(11)?,
+        quality_score: row.get(12)?,
+    }))
+}
+
+/// 按收盘时刻构造日 K 线供验证规则检查
+fn to_kline(row: &FactorData) -> KLine {
+    KLine {
+        datetime: clock::to_utc(row.date.and_hms_opt(15, 0, 0).unwrap_or_default()),
+        code: row.code.clone(),
+        open: row.open,
+        high: row.high,
+        low: row.low,
+        close: row.close,
+        volume: row.volume,
+        amount: row.amount,
+    }
+}
+
+/// 采用值、来源、因子与验证结果是否与上次对账相同
+fn same_row(previous: &StoredRow, row: &FactorData, source_name: &str) -> bool {
+    let eq = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0);
+    previous.data_source == row.data_source.as_str()
+        && previous.source_name == source_name
+        && previous.quality_score == row.quality_score.as_str()
+        && eq(previous.day.open, row.open)
+        && eq(previous.day.high, row.high)
+        && eq(previous.day.low, row.low)
+        && eq(previous.day.close, row.close)
+        && eq(previous.day.volume, row.volume)
+        && eq(previous.day.amount, row.amount)
+        && eq(previous.factor, row.factor)
+}
+
+/// 日终对账任务：对证券主数据中仍在交易的股票对账最近 `lookback_days` 个交易日
+///
+/// 尚无对账结果的股票从上市日（主数据缺少时从沪市开市日）对账到当日，之后按窗口接续。
+pub struct ReconcileJob {
+    reconciler: Arc<Reconciler>,
+    securities: Arc<SecurityService>,
+    calendar: Arc<TradingCalendar>,
+}
+
+impl ReconcileJob {
+    pub fn new(reconciler: Arc<Reconciler>, securities: Arc<SecurityService>, calendar: Arc<TradingCalendar>) -> Self {
+        Self {
+            reconciler,
+            securities,
+            calendar,
+        }
+    }
+
+    /// 对账起始日：已有对账结果时为窗口首日，否则为上市日
+    fn range_start(&self, stock: &Security, start: NaiveDate) -> Result<NaiveDate> {
+        if self.reconciler.has_history_before(&stock.code, start)? {
+            return Ok(start);
+        }
+        let listed = stock
+            .list_date
+            .unwrap_or_else(|| NaiveDate::from_ymd_opt(1990, 12, 19).expect("有效日期"));
+        Ok(listed.min(start))
+    }
+}
+
+#[async_trait]
+impl DailyJob for ReconcileJob {
+    async fn run(&self, date: NaiveDate) -> Result<()> {
+        let lookback = self.reconciler.config().lookback_days.max(1);
+        let start = (1..lookback).fold(date, |day, _| self.calendar.prev_trading_day(day));
+
+        let stocks: Vec<Security> = self
+            .securities
+            .all()
+            .await
+            .into_iter()
+            .filter(|s| s.security_type == SecurityType::Stock && s.is_listed())
+            .collect();
+        let mut failed = 0;
+        for stock in &stocks {
+            let result = match self.range_start(stock, start) {
+                Ok(from) => self.reconciler.reconcile(&stock.code, from, date).await.map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                failed += 1;
+                tracing::warn!("{} 对账失败: {}", stock.code, e);
+            }
+        }
+
+        if failed > 0 && failed == stocks.len() {
+            return Err(AppError::Internal(format!("{} 只股票对账全部失败", failed)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::csv_source::fixture_source;
+    use crate::collector::parser::GbbqEvent;
+    use crate::config::{ExpressionRule, RuleSeverity, RuleTarget, ValidationConfig};
+    use crate::models::quality::DailyQuality;
+    use crate::service::{QualityService, QualityStore};
+    use std::collections::BTreeSet;
+
+    /// 记录写入内容的内存目标
+    struct Memory<T>(std::sync::Mutex<Vec<T>>);
+
+    impl<T> Default for Memory<T> {
+        fn default() -> Self {
+            Self(std::sync::Mutex::new(Vec::new()))
+        }
+    }
+
+    impl<T: Clone> Memory<T> {
+        fn take(&self) -> Vec<T> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    #[async_trait]
+    impl<T: Clone + Send + Sync> BatchSink<T> for Memory<T> {
+        async fn write_batch(&self, batch: &[T]) -> Result<()> {
+            self.0.lock().unwrap().extend_from_slice(batch);
+            Ok(())
+        }
+    }
+
+    /// 按 `ReplacingMergeTree(data_version)` 查询 `FINAL` 的语义保存 `factor` 行：
+    /// 同一 (code, date) 只保留版本号最大的一行，版本相同时保留后写入的
+    #[derive(Default)]
+    struct ReplacingFactor(std::sync::Mutex<BTreeMap<(String, NaiveDate), FactorData>>);
+
+    #[async_trait]
+    impl BatchSink<FactorData> for ReplacingFactor {
+        async fn write_batch(&self, batch: &[FactorData]) -> Result<()> {
+            let mut rows = self.0.lock().unwrap();
+            for row in batch {
+                let key = (row.code.clone(), row.date);
+                if rows.get(&key).is_none_or(|r| r.data_version <= row.data_version) {
+                    rows.insert(key, row.clone());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// 与 ClickHouse 实现相同的统计口径：对 `FINAL` 后的行按交易日与质量评分计数
+    #[async_trait]
+    impl QualityStore for ReplacingFactor {
+        async fn daily_quality(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<DailyQuality>> {
+            let rows = self.0.lock().unwrap();
+            let mut daily: BTreeMap<NaiveDate, DailyQuality> = BTreeMap::new();
+            for row in rows.values().filter(|r| r.date >= start && r.date <= end) {
+                let day = daily.entry(row.date).or_insert_with(|| DailyQuality { date: row.date, ..Default::default() });
+                match row.quality_score {
+                    QualityScore::Good => day.good += 1,
+                    QualityScore::Suspect => day.suspect += 1,
+                    QualityScore::Error => day.error += 1,
+                }
+            }
+            Ok(daily.into_values().collect())
+        }
+
+        async fn quality_issues(&self, _: NaiveDate, _: NaiveDate) -> Result<Vec<QualityIssue>> {
+            Ok(Vec::new())
+        }
+
+        async fn collected_codes(&self, _: NaiveDate, _: NaiveDate) -> Result<BTreeSet<String>> {
+            Ok(self.0.lock().unwrap().keys().map(|(code, _)| code.clone()).collect())
+        }
+    }
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, d).unwrap()
+    }
+
+    /// 构造一条 .day 记录（价格以分计）
+    fn record(d: u32, prices: [u32; 4], amount: f32, volume: u32) -> Vec<u8> {
+        let mut buf = (20240600 + d).to_le_bytes().to_vec();
+        for price in prices {
+            buf.extend_from_slice(&price.to_le_bytes());
+        }
+        buf.extend_from_slice(&amount.to_le_bytes());
+        buf.extend_from_slice(&volume.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf
+    }
+
+    /// 与样本 CSV 相同的 6-03 至 6-06，其中 6-04 收盘价不同，缺少 6-07
+    fn write_day_file(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("kaipanla_reconcile_{}_{}", name, std::process::id()));
+        let dir = root.join("vipdoc").join("sz").join("lday");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let data = [
+            record(3, [990, 1002, 985, 998], 975_000_000.0, 980_000),
+            record(4, [998, 1005, 992, 1005], 871_000_000.0, 870_000),
+            record(5, [1001, 1008, 995, 1000], 910_500_000.0, 910_000),
+            record(6, [1000, 1006, 996, 1000], 860_200_000.0, 860_000),
+        ]
+        .concat();
+        std::fs::write(dir.join("sz000001.day"), data).unwrap();
+        root
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_precedence_and_versions() {
+        let root = write_day_file("precedence");
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let sink = Arc::new(Memory::<FactorData>::default());
+        let log = Arc::new(Memory::<QualityIssue>::default());
+        let reconciler = |precedence: &[&str]| {
+            Reconciler::new(db.clone())
+                .with_source(Arc::new(RemoteBars::new("tdx", Arc::new(fixture_source()))))
+                .with_source(Arc::new(DayFileBars::new("local", &root)))
+                .with_config(ReconcileConfig {
+                    precedence: precedence.iter().map(|s| s.to_string()).collect(),
+                    ..Default::default()
+                })
+                .with_sink(sink.clone())
+                .with_log(log.clone())
+        };
+
+        // 本地文件优先，缺少的 6-07 采用服务器数据
+        let result = reconciler(&["local"]).reconcile("000001", date(3), date(7)).await.unwrap();
+        let rows = sink.take();
+        assert_eq!(rows, result.rows);
+        let sources: Vec<(NaiveDate, FactorSource, u32)> =
+            rows.iter().map(|r| (r.date, r.data_source, r.data_version)).collect();
+        assert_eq!(
+            sources,
+            vec![
+                (date(3), FactorSource::File, 1),
+                (date(4), FactorSource::File, 1),
+                (date(5), FactorSource::File, 1),
+                (date(6), FactorSource::File, 1),
+                (date(7), FactorSource::Api, 1),
+            ]
+        );
+        assert_eq!(rows[1].close, 10.05);
+        assert_eq!(rows[2].preclose, 10.05);
+
+        let issues: Vec<(NaiveDate, IssueType, Severity)> =
+            log.take().iter().map(|i| (i.date, i.issue_type, i.severity)).collect();
+        assert_eq!(
+            issues,
+            vec![
+                (date(4), IssueType::Abnormal, Severity::Warning),
+                (date(7), IssueType::Missing, Severity::Info),
+            ]
+        );
+        assert!(result.issues[0].description.contains("收盘 10.05 / 10.01"));
+
+        // 再次对账不重复写入
+        let again = reconciler(&["local"]).reconcile("000001", date(3), date(7)).await.unwrap();
+        assert!(again.rows.is_empty());
+        assert_eq!(again.unchanged, 5);
+        assert!(sink.take().is_empty());
+
+        // 改为服务器优先：来源变化的交易日版本号加 1，6-07 不变
+        let switched = reconciler(&["tdx", "local"]).reconcile("000001", date(3), date(7)).await.unwrap();
+        let versions: Vec<(NaiveDate, FactorSource, u32)> =
+            switched.rows.iter().map(|r| (r.date, r.data_source, r.data_version)).collect();
+        assert_eq!(
+            versions,
+            vec![
+                (date(3), FactorSource::Api, 2),
+                (date(4), FactorSource::Api, 2),
+                (date(5), FactorSource::Api, 2),
+                (date(6), FactorSource::Api, 2),
+            ]
+        );
+        assert_eq!(switched.rows[1].close, 10.01);
+
+        // 以前一交易日的对账结果接续计算，与整段对账一致
+        let tail = reconciler(&["tdx"]).reconcile("000001", date(6), date(7)).await.unwrap();
+        assert!(tail.rows.is_empty());
+        assert_eq!(tail.unchanged, 2);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_twice_keeps_one_row_per_day() {
+        let root = write_day_file("replacing");
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let table = Arc::new(ReplacingFactor::default());
+        let reconciler = |precedence: &str| {
+            Reconciler::new(db.clone())
+                .with_source(Arc::new(RemoteBars::new("tdx", Arc::new(fixture_source()))))
+                .with_source(Arc::new(DayFileBars::new("local", &root)))
+                .with_config(ReconcileConfig {
+                    precedence: vec![precedence.to_string()],
+                    ..Default::default()
+                })
+                .with_sink(table.clone())
+        };
+
+        reconciler("local").reconcile("000001", date(3), date(7)).await.unwrap();
+        reconciler("tdx").reconcile("000001", date(3), date(7)).await.unwrap();
+
+        let rows = table.0.lock().unwrap();
+        let visible: Vec<(NaiveDate, FactorSource, u32, f64)> = rows
+            .values()
+            .map(|r| (r.date, r.data_source, r.data_version, r.close))
+            .collect();
+        assert_eq!(
+            visible,
+            vec![
+                (date(3), FactorSource::Api, 2, 9.98),
+                (date(4), FactorSource::Api, 2, 10.01),
+                (date(5), FactorSource::Api, 2, 10.0),
+                (date(6), FactorSource::Api, 2, 10.0),
+                (date(7), FactorSource::Api, 1, 10.5),
+            ]
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_suspect_bar_reaches_quality_report() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let table = Arc::new(ReplacingFactor::default());
+        let log = Arc::new(Memory::<QualityIssue>::default());
+        let mut config = ValidationConfig::default();
+        config.rules.push(ExpressionRule {
+            name: "active".to_string(),
+            target: RuleTarget::Kline,
+            expr: "volume > 860000".to_string(),
+            enabled: true,
+            severity: RuleSeverity::Suspect,
+        });
+        let rules = Arc::new(RuleEngine::new(&config).unwrap());
+        let reconciler = Reconciler::new(db.clone())
+            .with_source(Arc::new(RemoteBars::new("tdx", Arc::new(fixture_source()))))
+            .with_rules(rules.clone())
+            .with_sink(table.clone())
+            .with_log(log.clone());
+
+        // 6-06 成交量 860000 不满足规则，标记为可疑
+        reconciler.reconcile("000001", date(3), date(7)).await.unwrap();
+        let scores: Vec<QualityScore> = table.0.lock().unwrap().values().map(|r| r.quality_score).collect();
+        assert_eq!(
+            scores,
+            vec![QualityScore::Good, QualityScore::Good, QualityScore::Good, QualityScore::Suspect, QualityScore::Good]
+        );
+        assert!(log.take().is_empty());
+
+        let report = QualityService::new(table.clone()).report(date(3), date(7)).await.unwrap();
+        assert_eq!(
+            (report.total_records, report.good_quality, report.suspect, report.error),
+            (5, 4, 1, 0)
+        );
+        assert_eq!(report.daily[3].date, date(6));
+        assert_eq!(report.daily[3].suspect, 1);
+
+        // 规则改为错误：评分变化的交易日重新写入并记入质量日志
+        config.rules[0].severity = RuleSeverity::Error;
+        rules.reload(&config).unwrap();
+        let result = reconciler.reconcile("000001", date(3), date(7)).await.unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!((result.rows[0].date, result.rows[0].data_version), (date(6), 2));
+        assert_eq!(result.rows[0].quality_score, QualityScore::Error);
+        let issues = log.take();
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].date, issues[0].severity), (date(6), Severity::Error));
+
+        let report = QualityService::new(table).report(date(3), date(7)).await.unwrap();
+        assert_eq!((report.good_quality, report.suspect, report.error), (4, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_first_run_covers_ex_rights_before_window() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let table = Arc::new(ReplacingFactor::default());
+        // 600000 于 6-05 10 派 10 元，除权价 9.0
+        let dividend = GbbqRecord {
+            market: Market::SH,
+            code: "600000".to_string(),
+            date: date(5),
+            category: 1,
+            event: GbbqEvent::Dividend { cash: 10.0, rights_price: 0.0, bonus: 0.0, rights: 0.0 },
+        };
+        let reconciler = Arc::new(
+            Reconciler::new(db.clone())
+                .with_source(Arc::new(RemoteBars::new("tdx", Arc::new(fixture_source()))))
+                .with_config(ReconcileConfig {
+                    lookback_days: 2,
+                    ..Default::default()
+                })
+                .with_ex_rights(vec![dividend])
+                .with_sink(table.clone()),
+        );
+        let securities = Arc::new(SecurityService::new(db.clone()).unwrap());
+        securities.sync(&fixture_source()).await.unwrap();
+        let job = ReconcileJob::new(reconciler.clone(), securities, Arc::new(TradingCalendar::bundled()));
+
+        // 窗口为 6-06 至 6-07，首次对账从上市日开始，因子包含 6-05 的除权
+        assert!(!reconciler.has_history_before("600000", date(6)).unwrap());
+        job.run(date(7)).await.unwrap();
+        let factors: Vec<(NaiveDate, f64)> = table
+            .0
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|r| r.code == "600000")
+            .map(|r| (r.date, r.factor))
+            .collect();
+        assert_eq!(factors.len(), 5);
+        assert_eq!(factors[1], (date(4), 1.0));
+        assert!((factors[4].1 - 10.0 / 9.0).abs() < 1e-12);
+
+        // 之后按窗口接续，因子不变
+        assert!(reconciler.has_history_before("600000", date(6)).unwrap());
+        let tail = reconciler.reconcile("600000", date(6), date(7)).await.unwrap();
+        assert!(tail.rows.is_empty());
+        assert_eq!(tail.unchanged, 2);
+    }
+}
@@ -0,0 +1,487 @@
+//! 行情录制与回放
+//!
+//! 录制文件为追加写入的 gzip 流：每次落盘结束一个 gzip 成员，进程中断最多丢失
+//! 最后一个未落盘的成员。解压后每行一个 JSON 事件：
+//!
+//! ```text
+//! {"ts":"2024-06-07T01:25:00Z","type":"auction","data":{...}}
+//! {"ts":"2024-06-07T01:30:03Z","type":"quote","data":{...}}
+//! ```
+//!
+//! `ts` 为录制时刻。回放按相邻事件的时间间隔除以倍速发布到 [`MarketFeed`]，
+//! 与实时采集走同一条管道；回放器同时实现 [`DataSource`]，返回截至当前回放进度的数据。
+
+use crate::collector::clock;
+use crate::collector::feed::{MarketEvent, MarketFeed};
+use crate::collector::protocol::{FinanceInfo, SecurityInfo};
+use crate::collector::source::DataSource;
+use crate::error::{AppError, Result};
+use crate::models::auction::Auction;
+use crate::models::money_flow::Transaction;
+use crate::models::quote::{KLine, KLinePeriod, Quote};
+use crate::models::stock::Market;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, RwLock};
+
+/// 录制时的落盘间隔
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 录制的单个事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub ts: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: MarketEvent,
+}
+
+/// 行情录制器
+pub struct SessionRecorder {
+    path: PathBuf,
+    file: File,
+    encoder: Option<GzEncoder<BufWriter<File>>>,
+    count: usize,
+}
+
+impl SessionRecorder {
+    /// 打开录制文件（不存在则创建，存在则追加）
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            encoder: None,
+            count: 0,
+        })
+    }
+
+    /// 录制文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 本次已录制的事件数
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// 录制一个事件（写入缓冲，`flush` 后落盘）
+    pub fn record(&mut self, ts: DateTime<Utc>, event: &MarketEvent) -> Result<()> {
+        let line = serde_json::to_vec(&RecordedEventRef { ts, event })
+            .map_err(|e| AppError::Parse(format!("事件序列化失败: {}", e)))?;
+
+        let encoder = match &mut self.encoder {
+            Some(encoder) => encoder,
+            None => {
+                let writer = BufWriter::new(self.file.try_clone()?);
+                self.encoder.insert(GzEncoder::new(writer, Compression::default()))
+            }
+        };
+        encoder.write_all(&line)?;
+        encoder.write_all(b"\n")?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// 落盘：结束当前 gzip 成员
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(encoder) = self.encoder.take() {
+            encoder.finish()?.flush()?;
+        }
+        Ok(())
+    }
+
+    /// 持续录制事件流直到其关闭，返回录制的事件数
+    pub async fn record_feed(mut self, mut events: broadcast::Receiver<MarketEvent>) -> Result<usize> {
+        tracing::info!("开始录制行情: {}", self.path.display());
+        let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.record(Utc::now(), &event)?,
+                    Err(RecvError::Lagged(n)) => tracing::warn!("录制落后，丢失 {} 个事件", n),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = ticker.tick() => self.flush()?,
+            }
+        }
+
+        self.flush()?;
+        tracing::info!("行情录制结束，共 {} 个事件", self.count);
+        Ok(self.count)
+    }
+}
+
+impl Drop for SessionRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!("录制文件 {} 落盘失败: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RecordedEventRef<'a> {
+    ts: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a MarketEvent,
+}
+
+/// 读取录制文件（按时间排序），末尾不完整的数据会被忽略
+pub fn load_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound(format!("录制文件不存在: {}", path.display())),
+        _ => e.into(),
+    })?;
+
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(MultiGzDecoder::new(file)).lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("录制文件 {} 在第 {} 行后不完整: {}", path.display(), index, e);
+                break;
+            }
+        };
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => {
+                tracing::warn!("录制文件 {} 第 {} 行无法解析: {}", path.display(), index + 1, e);
+                break;
+            }
+        }
+    }
+
+    records.sort_by_key(|r: &RecordedEvent| r.ts);
+    Ok(records)
+}
+
+/// 回放倍速
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReplaySpeed {
+    #[default]
+    #[serde(rename = "1x")]
+    X1,
+    #[serde(rename = "10x")]
+    X10,
+    #[serde(rename = "100x")]
+    X100,
+    #[serde(rename = "max")]
+    Max,  // 不等待，尽快发布
+}
+
+impl ReplaySpeed {
+    /// 录制时间间隔对应的回放间隔
+    pub fn scale(&self, elapsed: Duration) -> Duration {
+        match self {
+            ReplaySpeed::X1 => elapsed,
+            ReplaySpeed::X10 => elapsed / 10,
+            ReplaySpeed::X100 => elapsed / 100,
+            ReplaySpeed::Max => Duration::ZERO,
+        }
+    }
+}
+
+impl FromStr for ReplaySpeed {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "1x" => Ok(ReplaySpeed::X1),
+            "10x" => Ok(ReplaySpeed::X10),
+            "100x" => Ok(ReplaySpeed::X100),
+            "max" => Ok(ReplaySpeed::Max),
+            _ => Err(AppError::Parse(format!("未知回放倍速: {}", s))),
+        }
+    }
+}
+
+/// 截至当前回放进度的行情状态
+#[derive(Debug, Default)]
+struct SessionState {
+    quotes: HashMap<String, Quote>,
+    auctions: HashMap<String, Auction>,
+    transactions: HashMap<(String, NaiveDate), Vec<Transaction>>,
+    latest_date: Option<NaiveDate>,
+}
+
+impl SessionState {
+    fn apply(&mut self, event: &MarketEvent) {
+        match event {
+            MarketEvent::Quote(quote) => {
+                self.quotes.insert(quote.code.clone(), quote.clone());
+            }
+            MarketEvent::Auction(auction) => {
+                self.auctions.insert(auction.code.clone(), auction.clone());
+            }
+            MarketEvent::Transaction(transaction) => {
+                let date = clock::local_date(transaction.datetime);
+                self.latest_date = self.latest_date.max(Some(date));
+                self.transactions
+                    .entry((transaction.code.clone(), date))
+                    .or_default()
+                    .push(transaction.clone());
+            }
+        }
+    }
+}
+
+/// 行情回放器
+pub struct SessionReplayer {
+    records: Vec<RecordedEvent>,
+    speed: ReplaySpeed,
+    state: RwLock<SessionState>,
+}
+
+impl SessionReplayer {
+    /// 由录制文件创建
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::from_records(load_recording(path)?))
+    }
+
+    /// 由已排序的事件创建
+    pub fn from_records(records: Vec<RecordedEvent>) -> Self {
+        Self {
+            records,
+            speed: ReplaySpeed::default(),
+            state: RwLock::new(SessionState::default()),
+        }
+    }
+
+    /// 设置回放倍速
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// 录制的全部事件
+    pub fn records(&self) -> &[RecordedEvent] {
+        &self.records
+    }
+
+    /// 录制时长（首尾事件间隔）
+    pub fn span(&self) -> Duration {
+        match (self.records.first(), self.records.last()) {
+            (Some(first), Some(last)) => (last.ts - first.ts).to_std().unwrap_or_default(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// 从头回放到事件流，返回发布的事件数
+    ///
+    /// 每个事件按相对首个事件的偏移定时发布，不会因处理耗时累积误差。
+    pub async fn replay(&self, feed: &MarketFeed) -> Result<usize> {
+        let Some(first) = self.records.first() else {
+            return Ok(0);
+        };
+
+        tracing::info!(
+            "开始回放 {} 个事件（{:?}，倍速 {:?}）",
+            self.records.len(),
+            self.span(),
+            self.speed
+        );
+        *self.state.write().await = SessionState::default();
+        let started = tokio::time::Instant::now();
+
+        for record in &self.records {
+            let offset = (record.ts - first.ts).to_std().unwrap_or_default();
+            let delay = self.speed.scale(offset);
+            if !delay.is_zero() {
+                tokio::time::sleep_until(started + delay).await;
+            }
+
+            self.state.write().await.apply(&record.event);
+            feed.publish(record.event.clone());
+        }
+
+        Ok(self.records.len())
+    }
+}
+
+#[async_trait]
+impl DataSource for SessionReplayer {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    /// 回放中出现过行情的证券
+    async fn get_all_securities(&self, market: Market) -> Result<Vec<SecurityInfo>> {
+        let state = self.state.read().await;
+        let mut securities: Vec<SecurityInfo> = state
+            .quotes
+            .values()
+            .filter(|q| Market::from_code(&q.code) == Some(market))
+            .map(|q| SecurityInfo {
+                market,
+                code: q.code.clone(),
+                name: q.name.clone(),
+                volunit: 100,
+                decimal_point: 2,
+                pre_close: q.preclose,
+            })
+            .collect();
+        securities.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(securities)
+    }
+
+    async fn get_finance_info(&self, _market: Market, code: &str) -> Result<FinanceInfo> {
+        Err(AppError::NotFound(format!("回放会话不含财务数据: {}", code)))
+    }
+
+    async fn get_quotes(&self, codes: &[String]) -> Result<Vec<Quote>> {
+        let state = self.state.read().await;
+        Ok(codes.iter().filter_map(|code| state.quotes.get(code).cloned()).collect())
+    }
+
+    async fn get_kline(
+        &self,
+        code: &str,
+        _period: KLinePeriod,
+        _start: NaiveDate,
+        _end: NaiveDate,
+    ) -> Result<Vec<KLine>> {
+        Err(AppError::NotFound(format!("回放会话不含 K 线数据: {}", code)))
+    }
+
+    async fn get_transactions(&self, code: &str, date: Option<NaiveDate>) -> Result<Vec<Transaction>> {
+        let state = self.state.read().await;
+        let Some(date) = date.or(state.latest_date) else {
+            return Ok(Vec::new());
+        };
+        Ok(state
+            .transactions
+            .get(&(code.to_string(), date))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn get_auctions(&self, codes: &[String]) -> Result<Vec<Auction>> {
+        let state = self.state.read().await;
+        Ok(codes.iter().filter_map(|code| state.auctions.get(code).cloned()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::money_flow::TradeDirection;
+    use chrono::TimeZone;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("kaipanla_{}_{}.gz", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn at(second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 7, 1, 30, second).unwrap()
+    }
+
+    fn trade(second: u32, price: f64) -> MarketEvent {
+        MarketEvent::Transaction(Transaction {
+            code: "000001".to_string(),
+            datetime: at(second),
+            price,
+            volume: 100.0,
+            amount: price * 10000.0,
+            num: 3,
+            direction: TradeDirection::Buy,
+        })
+    }
+
+    #[test]
+    fn test_record_append_and_load() {
+        let path = temp_file("replay_append");
+
+        let mut recorder = SessionRecorder::open(&path).unwrap();
+        recorder.record(at(0), &trade(0, 10.0)).unwrap();
+        recorder.flush().unwrap();
+        recorder.record(at(3), &trade(3, 10.1)).unwrap();
+        drop(recorder);
+
+        // 重新打开后追加
+        let mut recorder = SessionRecorder::open(&path).unwrap();
+        recorder.record(at(6), &trade(6, 10.2)).unwrap();
+        drop(recorder);
+
+        let records = load_recording(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].ts, at(6));
+        assert!(matches!(&records[1].event, MarketEvent::Transaction(t) if t.price == 10.1));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_ignores_truncated_tail() {
+        let path = temp_file("replay_truncated");
+
+        let mut recorder = SessionRecorder::open(&path).unwrap();
+        recorder.record(at(0), &trade(0, 10.0)).unwrap();
+        recorder.flush().unwrap();
+        let first_member = std::fs::metadata(&path).unwrap().len() as usize;
+        recorder.record(at(3), &trade(3, 10.1)).unwrap();
+        drop(recorder);
+
+        // 截掉第二个 gzip 成员的一半，模拟写入中断
+        let bytes = std::fs::read(&path).unwrap();
+        let cut = first_member + (bytes.len() - first_member) / 2;
+        std::fs::write(&path, &bytes[..cut]).unwrap();
+
+        let records = load_recording(&path).unwrap();
+        assert_eq!(records.len(), 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_speed() {
+        let minute = Duration::from_secs(60);
+        assert_eq!(ReplaySpeed::X1.scale(minute), minute);
+        assert_eq!(ReplaySpeed::X10.scale(minute), Duration::from_secs(6));
+        assert_eq!(ReplaySpeed::X100.scale(minute), Duration::from_millis(600));
+        assert_eq!(ReplaySpeed::Max.scale(minute), Duration::ZERO);
+        assert_eq!("100X".parse::<ReplaySpeed>().unwrap(), ReplaySpeed::X100);
+        assert!("2x".parse::<ReplaySpeed>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replayer_as_data_source() {
+        let records = vec![
+            RecordedEvent { ts: at(0), event: trade(0, 10.0) },
+            RecordedEvent { ts: at(3), event: trade(3, 10.1) },
+        ];
+        let replayer = SessionReplayer::from_records(records).with_speed(ReplaySpeed::Max);
+
+        // 回放前没有数据
+        assert!(replayer.get_transactions("000001", None).await.unwrap().is_empty());
+
+        let feed = MarketFeed::default();
+        let mut rx = feed.subscribe();
+        assert_eq!(replayer.replay(&feed).await.unwrap(), 2);
+        assert_eq!(rx.recv().await.unwrap().code(), "000001");
+
+        let transactions = replayer.get_transactions("000001", None).await.unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[1].price, 10.1);
+        assert!(replayer.get_quotes(&["000001".to_string()]).await.unwrap().is_empty());
+    }
+}
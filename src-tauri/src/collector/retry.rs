@@ -0,0 +1,158 @@
+//! 重试策略 - 网络错误按指数退避重试
+//!
+//! 第 n 次重试前等待 `min(base × 2^(n-1), max)` 的 50%~100%（随机抖动），
+//! 避免多个任务在服务器恢复时同时重连。只有 `AppError::Network` 会重试。
+
+use crate::error::{AppError, Result};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// 重试策略
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,     // 最大尝试次数（含首次）
+    pub base_delay: Duration,  // 首次重试的基准等待
+    pub max_delay: Duration,   // 单次等待上限
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// 第 `retry` 次重试（从 1 开始）前的等待时间，含抖动
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1u32 << retry.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        exp.mul_f64(0.5 + 0.5 * jitter())
+    }
+
+    /// 执行操作，网络错误时按策略重试
+    pub async fn run<T, F, Fut>(&self, name: &str, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if is_retryable(&e) && attempt < self.max_attempts => {
+                    let delay = self.delay(attempt);
+                    tracing::warn!(
+                        "{} 失败（第 {}/{} 次）: {}，{:?} 后重试",
+                        name, attempt, self.max_attempts, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// 是否为可重试的临时错误
+pub fn is_retryable(error: &AppError) -> bool {
+    matches!(error, AppError::Network(_))
+}
+
+/// [0, 1) 随机数
+fn jitter() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn test_delay_bounds() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+
+        for _ in 0..50 {
+            let first = policy.delay(1);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.delay(3);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            // 超过上限后封顶
+            assert!(policy.delay(30) <= Duration::from_millis(1000));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_network_errors() {
+        let calls = AtomicU32::new(0);
+        let result = fast_policy(3)
+            .run("test", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(AppError::Network("连接重置".to_string())),
+                    _ => Ok(42),
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = fast_policy(2)
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(AppError::Network("超时".to_string()))
+            })
+            .await;
+
+        assert!(matches!(result, Err(AppError::Network(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_retry_other_errors() {
+        let calls = AtomicU32::new(0);
+        let result: Result<()> = fast_policy(5)
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(AppError::Parse("格式错误".to_string()))
+            })
+            .await;
+
+        assert!(matches!(result, Err(AppError::Parse(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
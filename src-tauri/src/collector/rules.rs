@@ -0,0 +1,759 @@
+//! 可配置的数据验证规则 - 由 `config.toml` 的 `[validation]` 编译，可在运行时重新加载
+//!
+//! 内置规则（价格区间、日期区间、价格关系、涨跌停）各有开关与严重程度；
+//! 自定义规则为表达式，为真时通过。严重程度为 `error` 的规则不通过时验证失败，
+//! 为 `suspect` 的规则不通过时标记为可疑。
+//!
+//! ```toml
+//! [validation.price]
+//! max = 5000.0
+//!
+//! [[validation.rules]]
+//! name = "zero_volume_flat"
+//! target = "kline"
+//! expr = "volume > 0 || (high == low && open == close)"
+//! severity = "suspect"
+//! ```
+
+use crate::collector::clock;
+use crate::collector::validator::{PriceLimit, QualityScore};
+use crate::config::{load_config, RuleSeverity, RuleTarget, ValidationConfig};
+use crate::error::{AppError, Result};
+use crate::models::quote::{KLine, Quote};
+use chrono::NaiveDate;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// K 线表达式可用的变量
+const KLINE_VARIABLES: &[&str] = &["open", "high", "low", "close", "volume", "amount", "preclose"];
+
+/// 行情快照表达式可用的变量
+const QUOTE_VARIABLES: &[&str] = &["price", "preclose", "open", "high", "low", "volume", "amount"];
+
+/// 内置规则（配置缺省值）
+static BUILTIN: LazyLock<ValidationRules> =
+    LazyLock::new(|| ValidationRules::compile(&ValidationConfig::default()).expect("内置验证规则无效"));
+
+/// 编译后的验证规则
+#[derive(Debug)]
+pub struct ValidationRules {
+    config: ValidationConfig,
+    expressions: Vec<CompiledRule>,
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    name: String,
+    target: RuleTarget,
+    expr: Expr,
+    severity: RuleSeverity,
+}
+
+impl ValidationRules {
+    /// 编译配置，表达式有语法错误或使用未知变量时返回配置错误
+    pub fn compile(config: &ValidationConfig) -> Result<Self> {
+        let mut expressions = Vec::new();
+        for rule in config.rules.iter().filter(|r| r.enabled) {
+            let expr = Expr::parse(&rule.expr)
+                .map_err(|e| AppError::Config(format!("验证规则 {}: {}", rule.name, e)))?;
+            let allowed = match rule.target {
+                RuleTarget::Kline => KLINE_VARIABLES,
+                RuleTarget::Quote => QUOTE_VARIABLES,
+            };
+            if let Some(unknown) = expr.variables().into_iter().find(|v| !allowed.contains(v)) {
+                return Err(AppError::Config(format!(
+                    "验证规则 {}: 未知变量 {}（可用: {}）",
+                    rule.name,
+                    unknown,
+                    allowed.join(" ")
+                )));
+            }
+            expressions.push(CompiledRule {
+                name: rule.name.clone(),
+                target: rule.target,
+                expr,
+                severity: rule.severity,
+            });
+        }
+
+        Ok(Self {
+            config: config.clone(),
+            expressions,
+        })
+    }
+
+    /// 内置规则
+    pub fn builtin() -> &'static Self {
+        &BUILTIN
+    }
+
+    /// 编译前的配置
+    pub fn config(&self) -> &ValidationConfig {
+        &self.config
+    }
+
+    /// 检查价格区间
+    pub fn check_price(&self, price: f64, field_name: &str) -> Result<QualityScore> {
+        let mut verdict = Verdict::default();
+        self.price(&mut verdict, price, field_name)?;
+        Ok(verdict.0)
+    }
+
+    /// 检查日期区间
+    pub fn check_date(&self, date: NaiveDate) -> Result<QualityScore> {
+        let mut verdict = Verdict::default();
+        self.date(&mut verdict, date)?;
+        Ok(verdict.0)
+    }
+
+    /// 检查 K 线；给出昨收时检查涨跌停，并计算引用 `preclose` 的表达式规则
+    pub fn check_kline(&self, kline: &KLine, preclose: Option<f64>, limit: PriceLimit) -> Result<QualityScore> {
+        let mut verdict = Verdict::default();
+        self.price(&mut verdict, kline.open, "开盘价")?;
+        self.price(&mut verdict, kline.high, "最高价")?;
+        self.price(&mut verdict, kline.low, "最低价")?;
+        self.price(&mut verdict, kline.close, "收盘价")?;
+        self.date(&mut verdict, clock::local_date(kline.datetime))?;
+
+        let ohlc = &self.config.ohlc;
+        if ohlc.enabled {
+            verdict.require(kline.high >= kline.low, ohlc.severity, || {
+                format!("最高价不能低于最低价: high={}, low={}", kline.high, kline.low)
+            })?;
+            verdict.require(kline.close >= kline.low && kline.close <= kline.high, ohlc.severity, || {
+                format!("收盘价超出范围: close={}, low={}, high={}", kline.close, kline.low, kline.high)
+            })?;
+        }
+
+        if let Some(preclose) = preclose {
+            self.price(&mut verdict, preclose, "昨收价")?;
+            self.limit(&mut verdict, limit, preclose, kline.low, kline.high)?;
+        }
+
+        let variables = |name: &str| match name {
+            "open" => Some(kline.open),
+            "high" => Some(kline.high),
+            "low" => Some(kline.low),
+            "close" => Some(kline.close),
+            "volume" => Some(kline.volume),
+            "amount" => Some(kline.amount),
+            "preclose" => preclose,
+            _ => None,
+        };
+        self.expressions(&mut verdict, RuleTarget::Kline, &variables)?;
+
+        Ok(verdict.0)
+    }
+
+    /// 检查行情快照（未成交的快照只检查价格区间与成交量）
+    pub fn check_quote(&self, quote: &Quote, limit: PriceLimit) -> Result<QualityScore> {
+        let mut verdict = Verdict::default();
+        self.price(&mut verdict, quote.price, "现价")?;
+        self.price(&mut verdict, quote.preclose, "昨收价")?;
+        self.price(&mut verdict, quote.high, "最高价")?;
+        self.price(&mut verdict, quote.low, "最低价")?;
+
+        if quote.volume < 0.0 || quote.amount < 0.0 {
+            return Err(AppError::Parse(format!(
+                "成交量或成交额为负: volume={}, amount={}",
+                quote.volume, quote.amount
+            )));
+        }
+
+        // 未成交（竞价前、停牌）时价格字段可能为 0，不做区间检查
+        if quote.volume == 0.0 {
+            return Ok(verdict.0);
+        }
+
+        let ohlc = &self.config.ohlc;
+        if ohlc.enabled {
+            verdict.require(quote.high >= quote.low, ohlc.severity, || {
+                format!("最高价不能低于最低价: high={}, low={}", quote.high, quote.low)
+            })?;
+            verdict.require(quote.price >= quote.low && quote.price <= quote.high, ohlc.severity, || {
+                format!("现价超出范围: price={}, low={}, high={}", quote.price, quote.low, quote.high)
+            })?;
+        }
+
+        self.limit(&mut verdict, limit, quote.preclose, quote.low, quote.high)?;
+
+        let variables = |name: &str| match name {
+            "price" => Some(quote.price),
+            "preclose" => Some(quote.preclose),
+            "open" => Some(quote.open),
+            "high" => Some(quote.high),
+            "low" => Some(quote.low),
+            "volume" => Some(quote.volume),
+            "amount" => Some(quote.amount),
+            _ => None,
+        };
+        self.expressions(&mut verdict, RuleTarget::Quote, &variables)?;
+
+        Ok(verdict.0)
+    }
+
+    fn price(&self, verdict: &mut Verdict, price: f64, field_name: &str) -> Result<()> {
+        let rule = &self.config.price;
+        if !rule.enabled {
+            return Ok(());
+        }
+        verdict.require(price >= rule.min, rule.severity, || {
+            if rule.min == 0.0 {
+                format!("{}不能为负数: {}", field_name, price)
+            } else {
+                format!("{}低于下限 {}: {}", field_name, rule.min, price)
+            }
+        })?;
+        verdict.require(price <= rule.max, rule.severity, || format!("{}异常过高: {}", field_name, price))
+    }
+
+    fn date(&self, verdict: &mut Verdict, date: NaiveDate) -> Result<()> {
+        let rule = &self.config.date;
+        if !rule.enabled {
+            return Ok(());
+        }
+        verdict.require(date >= rule.min, rule.severity, || format!("日期过早: {}", date))?;
+        verdict.require(
+            rule.allow_future || date <= clock::now_local().date(),
+            rule.severity,
+            || format!("日期不能是未来: {}", date),
+        )
+    }
+
+    fn limit(&self, verdict: &mut Verdict, limit: PriceLimit, preclose: f64, low: f64, high: f64) -> Result<()> {
+        let rule = &self.config.price_limit;
+        if !rule.enabled {
+            return Ok(());
+        }
+        verdict.require(limit.contains(preclose, low, high), rule.severity, || {
+            format!("超出涨跌停价: preclose={}, low={}, high={}", preclose, low, high)
+        })
+    }
+
+    fn expressions(
+        &self,
+        verdict: &mut Verdict,
+        target: RuleTarget,
+        variables: &dyn Fn(&str) -> Option<f64>,
+    ) -> Result<()> {
+        for rule in self.expressions.iter().filter(|r| r.target == target) {
+            // 缺少变量（如 K 线未给出昨收）时跳过
+            let Some(value) = rule.expr.eval(variables) else {
+                continue;
+            };
+            verdict.require(truthy(value), rule.severity, || format!("未通过验证规则 {}", rule.name))?;
+        }
+        Ok(())
+    }
+}
+
+/// 累计验证结果
+struct Verdict(QualityScore);
+
+impl Default for Verdict {
+    fn default() -> Self {
+        Self(QualityScore::Good)
+    }
+}
+
+impl Verdict {
+    /// 条件不成立时按严重程度失败或标记为可疑
+    fn require(&mut self, ok: bool, severity: RuleSeverity, message: impl FnOnce() -> String) -> Result<()> {
+        if ok {
+            return Ok(());
+        }
+        match severity {
+            RuleSeverity::Error => Err(AppError::Parse(message())),
+            RuleSeverity::Suspect => {
+                tracing::debug!("可疑数据: {}", message());
+                self.0 = QualityScore::Suspect;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 可重新加载的验证规则
+///
+/// 采集流程每次验证前取 [`Self::current`]，重新加载后新规则对下一次验证生效；
+/// 新配置编译失败时保留原规则。
+pub struct RuleEngine {
+    rules: RwLock<Arc<ValidationRules>>,
+    config_path: Option<PathBuf>,
+}
+
+impl RuleEngine {
+    pub fn new(config: &ValidationConfig) -> Result<Self> {
+        Ok(Self {
+            rules: RwLock::new(Arc::new(ValidationRules::compile(config)?)),
+            config_path: None,
+        })
+    }
+
+    /// 设置 [`Self::reload_from_file`] 读取的配置文件
+    pub fn with_config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// 当前规则
+    pub fn current(&self) -> Arc<ValidationRules> {
+        self.rules.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 替换为新配置
+    pub fn reload(&self, config: &ValidationConfig) -> Result<()> {
+        let rules = Arc::new(ValidationRules::compile(config)?);
+        *self.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+        tracing::info!("验证规则已更新: 自定义规则 {} 条", config.rules.iter().filter(|r| r.enabled).count());
+        Ok(())
+    }
+
+    /// 重新读取配置文件的 `[validation]`
+    pub fn reload_from_file(&self) -> Result<ValidationConfig> {
+        let path = self
+            .config_path
+            .as_ref()
+            .ok_or_else(|| AppError::Config("未设置配置文件路径".to_string()))?;
+        let config = load_config(&path.to_string_lossy())?.validation;
+        self.reload(&config)?;
+        Ok(config)
+    }
+}
+
+impl Default for RuleEngine {
+    fn default() -> Self {
+        Self::new(&ValidationConfig::default()).expect("内置验证规则无效")
+    }
+}
+
+fn truthy(value: f64) -> bool {
+    value != 0.0 && !value.is_nan()
+}
+
+fn approx_eq(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+}
+
+/// 规则表达式
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Func {
+    Abs,
+    Min,
+    Max,
+}
+
+impl Expr {
+    /// 解析表达式
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(AppError::Parse(format!("表达式多余的内容: {:?}", token))),
+        }
+    }
+
+    /// 引用的变量
+    pub fn variables(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_variables(&mut names);
+        names
+    }
+
+    fn collect_variables<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Var(name) => names.push(name),
+            Expr::Neg(inner) | Expr::Not(inner) => inner.collect_variables(names),
+            Expr::Binary(_, left, right) => {
+                left.collect_variables(names);
+                right.collect_variables(names);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|arg| arg.collect_variables(names)),
+        }
+    }
+
+    /// 求值（布尔值为 1 / 0），变量缺失时返回 `None`
+    pub fn eval(&self, variables: &dyn Fn(&str) -> Option<f64>) -> Option<f64> {
+        let bool_value = |b: bool| if b { 1.0 } else { 0.0 };
+        Some(match self {
+            Expr::Number(value) => *value,
+            Expr::Var(name) => variables(name)?,
+            Expr::Neg(inner) => -inner.eval(variables)?,
+            Expr::Not(inner) => bool_value(!truthy(inner.eval(variables)?)),
+            Expr::Binary(op, left, right) => {
+                let a = left.eval(variables)?;
+                let b = right.eval(variables)?;
+                match op {
+                    BinOp::Add => a + b,
+                    BinOp::Sub => a - b,
+                    BinOp::Mul => a * b,
+                    BinOp::Div => a / b,
+                    BinOp::Eq => bool_value(approx_eq(a, b)),
+                    BinOp::Ne => bool_value(!approx_eq(a, b)),
+                    BinOp::Lt => bool_value(a < b),
+                    BinOp::Le => bool_value(a <= b || approx_eq(a, b)),
+                    BinOp::Gt => bool_value(a > b),
+                    BinOp::Ge => bool_value(a >= b || approx_eq(a, b)),
+                    BinOp::And => bool_value(truthy(a) && truthy(b)),
+                    BinOp::Or => bool_value(truthy(a) || truthy(b)),
+                }
+            }
+            Expr::Call(func, args) => {
+                let values = args.iter().map(|arg| arg.eval(variables)).collect::<Option<Vec<_>>>()?;
+                match func {
+                    Func::Abs => values[0].abs(),
+                    Func::Min => values.into_iter().fold(f64::INFINITY, f64::min),
+                    Func::Max => values.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    const OPERATORS: &[&str] = &["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/"];
+
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() || c == '.' {
+            let len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+            let value = rest[..len]
+                .parse()
+                .map_err(|_| AppError::Parse(format!("无效的数字: {}", &rest[..len])))?;
+            tokens.push(Token::Number(value));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            len
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            1
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            1
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            1
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            op.len()
+        } else {
+            return Err(AppError::Parse(format!("表达式无法识别的字符: {}", c)));
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// 递归下降解析，优先级由低到高: `||`、`&&`、`!`、比较、加减、乘除、取负
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// 下一个是给定运算符之一时取出
+    fn take_op(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.take_op(&["||"]).is_some() {
+            left = Expr::Binary(BinOp::Or, Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.not()?;
+        while self.take_op(&["&&"]).is_some() {
+            left = Expr::Binary(BinOp::And, Box::new(left), Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.take_op(&["!"]).is_some() {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.sum()?;
+        let Some(op) = self.take_op(&["==", "!=", "<=", ">=", "<", ">"]) else {
+            return Ok(left);
+        };
+        let op = match op {
+            "==" => BinOp::Eq,
+            "!=" => BinOp::Ne,
+            "<=" => BinOp::Le,
+            ">=" => BinOp::Ge,
+            "<" => BinOp::Lt,
+            _ => BinOp::Gt,
+        };
+        Ok(Expr::Binary(op, Box::new(left), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut left = self.product()?;
+        while let Some(op) = self.take_op(&["+", "-"]) {
+            let op = if op == "+" { BinOp::Add } else { BinOp::Sub };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.take_op(&["*", "/"]) {
+            let op = if op == "*" { BinOp::Mul } else { BinOp::Div };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.take_op(&["-"]).is_some() {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let mut args = vec![self.or()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.or()?);
+                }
+                self.expect(Token::RParen)?;
+
+                let (func, arity) = match name.as_str() {
+                    "abs" => (Func::Abs, 1..=1),
+                    "min" => (Func::Min, 2..=usize::MAX),
+                    "max" => (Func::Max, 2..=usize::MAX),
+                    _ => return Err(AppError::Parse(format!("未知函数: {}", name))),
+                };
+                if !arity.contains(&args.len()) {
+                    return Err(AppError::Parse(format!("函数 {} 参数个数错误: {}", name, args.len())));
+                }
+                Ok(Expr::Call(func, args))
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Number(1.0)),
+                "false" => Ok(Expr::Number(0.0)),
+                _ => Ok(Expr::Var(name)),
+            },
+            Some(token) => Err(AppError::Parse(format!("表达式意外的内容: {:?}", token))),
+            None => Err(AppError::Parse("表达式不完整".to_string())),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(AppError::Parse(format!("表达式缺少 {:?}，实际为 {:?}", expected, other))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ExpressionRule};
+    use chrono::Utc;
+
+    fn eval(source: &str, vars: &[(&str, f64)]) -> Option<f64> {
+        let expr = Expr::parse(source).unwrap();
+        expr.eval(&|name| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| *v))
+    }
+
+    fn kline(low: f64, high: f64, close: f64, volume: f64) -> KLine {
+        KLine {
+            datetime: Utc::now(),
+            code: "000001".to_string(),
+            open: low,
+            high,
+            low,
+            close,
+            volume,
+            amount: volume * close,
+        }
+    }
+
+    #[test]
+    fn test_expression() {
+        assert_eq!(eval("1 + 2 * 3 - -4 / 2", &[]), Some(9.0));
+        assert_eq!(eval("(1 + 2) * 3 == 9 && !(2 > 3)", &[]), Some(1.0));
+        assert_eq!(eval("abs(close - open) / open <= 0.1", &[("open", 10.0), ("close", 11.0)]), Some(1.0));
+        assert_eq!(eval("max(1, low, 3) + min(high, 2)", &[("low", 5.0), ("high", 0.5)]), Some(5.5));
+        assert_eq!(eval("volume > 0 || false", &[("volume", 0.0)]), Some(0.0));
+        assert_eq!(eval("0.1 + 0.2 == 0.3", &[]), Some(1.0));
+        assert_eq!(eval("preclose > 0", &[]), None);
+
+        assert!(Expr::parse("1 +").is_err());
+        assert!(Expr::parse("(1 + 2").is_err());
+        assert!(Expr::parse("close $ 1").is_err());
+        assert!(Expr::parse("sqrt(close)").is_err());
+        assert!(Expr::parse("abs(1, 2)").is_err());
+        assert_eq!(Expr::parse("a + abs(b) * c").unwrap().variables(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_rules_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+            [database]
+            clickhouse_url = "tcp://localhost:9000"
+            sqlite_path = "kaipanla.db"
+
+            [api]
+            host = "127.0.0.1"
+            port = 8000
+
+            [data_source]
+            tdx_servers = []
+            update_interval_secs = 3
+
+            [validation.price]
+            max = 100.0
+
+            [validation.price_limit]
+            severity = "error"
+
+            [[validation.rules]]
+            name = "zero_volume_flat"
+            target = "kline"
+            expr = "volume > 0 || (high == low && open == close)"
+            severity = "suspect"
+
+            [[validation.rules]]
+            name = "disabled"
+            target = "kline"
+            expr = "false"
+            enabled = false
+            severity = "error"
+            "#,
+        )
+        .unwrap();
+        let validation = &config.validation;
+        assert_eq!(validation.price.max, 100.0);
+        assert_eq!(validation.price.severity, RuleSeverity::Error);
+        assert!(validation.ohlc.enabled);
+        assert_eq!(validation.price_limit.severity, RuleSeverity::Error);
+
+        let rules = ValidationRules::compile(validation).unwrap();
+        assert_eq!(
+            rules.check_kline(&kline(9.9, 10.2, 10.0, 1000.0), None, PriceLimit::Unlimited).unwrap(),
+            QualityScore::Good
+        );
+        assert!(rules.check_kline(&kline(99.0, 101.0, 100.0, 1000.0), None, PriceLimit::Unlimited).is_err());
+        assert_eq!(
+            rules.check_kline(&kline(9.9, 10.2, 10.0, 0.0), None, PriceLimit::Unlimited).unwrap(),
+            QualityScore::Suspect
+        );
+        // 涨跌停改为错误
+        assert!(rules.check_kline(&kline(11.0, 11.5, 11.5, 1000.0), Some(10.0), PriceLimit::Percent(10)).is_err());
+        assert!(Config::default().validation.price_limit.severity == RuleSeverity::Suspect);
+    }
+
+    #[test]
+    fn test_reload() {
+        let engine = RuleEngine::default();
+        let flat = kline(10.0, 10.0, 10.0, 1000.0);
+        assert_eq!(engine.current().check_kline(&flat, None, PriceLimit::Unlimited).unwrap(), QualityScore::Good);
+
+        let mut config = ValidationConfig::default();
+        config.rules.push(ExpressionRule {
+            name: "moves".to_string(),
+            target: RuleTarget::Kline,
+            expr: "high > low".to_string(),
+            enabled: true,
+            severity: RuleSeverity::Suspect,
+        });
+        let before = engine.current();
+        engine.reload(&config).unwrap();
+        assert_eq!(engine.current().check_kline(&flat, None, PriceLimit::Unlimited).unwrap(), QualityScore::Suspect);
+        // 重新加载前取得的规则不变
+        assert_eq!(before.check_kline(&flat, None, PriceLimit::Unlimited).unwrap(), QualityScore::Good);
+
+        // 编译失败时保留原规则
+        config.rules[0].expr = "price > 0".to_string();
+        assert!(engine.reload(&config).is_err());
+        config.rules[0].expr = "high >".to_string();
+        assert!(engine.reload(&config).is_err());
+        assert_eq!(engine.current().config().rules[0].expr, "high > low");
+
+        assert!(engine.reload_from_file().is_err());
+    }
+}
@@ -1,47 +1,106 @@
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::collector::pipeline::CollectionPipeline;
+use crate::collector::session::{self, PhaseTransition, SessionPhase};
 use crate::config::DataSourceConfig;
 use crate::{AppError, Result};
-use chrono::{Datelike, Timelike, Utc, Weekday};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
-use tokio::time::interval;
+use tokio::sync::{broadcast, watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+/// 非交易日且日历无后续交易日时的检查间隔
+const IDLE_RECHECK: Duration = Duration::from_secs(3600);
 
 /// 采集调度器
+///
+/// 按北京时间的交易时段运行：每个阶段可设置不同的采集间隔（`None` 为不采集），
+/// 阶段切换时向订阅者广播 [`PhaseTransition`]。配置了 [`CollectionPipeline`] 时
+/// 每次采集执行一轮流水线，停止时等待缓冲区写完。
 pub struct CollectionScheduler {
     config: Arc<RwLock<DataSourceConfig>>,
+    calendar: Arc<TradingCalendar>,
+    cadence: HashMap<SessionPhase, Option<Duration>>,
+    transitions: broadcast::Sender<PhaseTransition>,
+    pipeline: Option<Arc<CollectionPipeline>>,
     is_running: Arc<RwLock<bool>>,
+    shutdown: Mutex<Option<watch::Sender<()>>>,
+    task: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl CollectionScheduler {
     /// 创建新的调度器
     pub fn new(config: Arc<RwLock<DataSourceConfig>>) -> Self {
+        let (transitions, _) = broadcast::channel(64);
         Self {
             config,
+            calendar: Arc::new(TradingCalendar::bundled()),
+            cadence: HashMap::new(),
+            transitions,
+            pipeline: None,
             is_running: Arc::new(RwLock::new(false)),
+            shutdown: Mutex::new(None),
+            task: Mutex::new(None),
         }
     }
 
-    /// 判断是否为交易日
-    pub fn is_trading_day() -> bool {
-        let now = Utc::now();
-        let weekday = now.weekday();
+    /// 使用指定的交易日历（如从外部文件加载的）
+    pub fn with_calendar(mut self, calendar: Arc<TradingCalendar>) -> Self {
+        self.calendar = calendar;
+        self
+    }
 
-        // 周末不是交易日
-        if weekday == Weekday::Sat || weekday == Weekday::Sun {
-            return false;
-        }
+    /// 设置某一阶段的采集间隔，`None` 为该阶段不采集
+    ///
+    /// 未设置的阶段：竞价与连续竞价按配置的 `update_interval_secs` 采集，其余不采集。
+    pub fn with_cadence(mut self, phase: SessionPhase, every: Option<Duration>) -> Self {
+        self.cadence.insert(phase, every);
+        self
+    }
+
+    /// 每次采集执行的流水线
+    pub fn with_pipeline(mut self, pipeline: Arc<CollectionPipeline>) -> Self {
+        self.pipeline = Some(pipeline);
+        self
+    }
+
+    /// 交易日历
+    pub fn calendar(&self) -> &Arc<TradingCalendar> {
+        &self.calendar
+    }
+
+    /// 判断北京时间今天是否为交易日
+    pub fn is_trading_day(&self) -> bool {
+        self.calendar.is_trading_today()
+    }
 
-        // TODO: 添加节假日判断
-        true
+    /// 当前交易时段（北京时间）
+    pub fn current_phase(&self) -> SessionPhase {
+        SessionPhase::now(&self.calendar)
     }
 
-    /// 判断是否在交易时间
-    pub fn is_trading_time() -> bool {
-        let now = Utc::now();
-        let hour = now.hour();
+    /// 判断是否在交易时间（集合竞价或连续竞价，不含午休）
+    pub fn is_trading_time(&self) -> bool {
+        self.current_phase().is_trading()
+    }
 
-        // 交易时间: 9:00-15:00
-        hour >= 9 && hour < 15
+    /// 订阅阶段切换，启动时会先收到一次当前阶段
+    pub fn subscribe_phases(&self) -> broadcast::Receiver<PhaseTransition> {
+        self.transitions.subscribe()
+    }
+
+    /// 某一阶段的采集间隔
+    pub async fn poll_interval(&self, phase: SessionPhase) -> Option<Duration> {
+        let default = Duration::from_secs(self.config.read().await.update_interval_secs.max(1));
+        self.cadence_for(phase, default)
+    }
+
+    fn cadence_for(&self, phase: SessionPhase, default: Duration) -> Option<Duration> {
+        match self.cadence.get(&phase) {
+            Some(every) => *every,
+            None => phase.is_trading().then_some(default),
+        }
     }
 
     /// 启动定时采集
@@ -54,46 +113,97 @@ impl CollectionScheduler {
             *is_running = true;
         }
 
-        let interval_secs = {
-            let config = self.config.read().await;
-            config.update_interval_secs
-        };
+        if let Some(pipeline) = &self.pipeline {
+            if let Err(e) = pipeline.open().await {
+                *self.is_running.write().await = false;
+                return Err(e);
+            }
+        }
+
+        let interval = Duration::from_secs(self.config.read().await.update_interval_secs.max(1));
+        let cadence: HashMap<_, _> = SessionPhase::ALL
+            .into_iter()
+            .map(|phase| (phase, self.cadence_for(phase, interval)))
+            .collect();
+
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(());
+        *self.shutdown.lock().await = Some(shutdown_tx);
 
-        let mut timer = interval(Duration::from_secs(interval_secs));
         let is_running = self.is_running.clone();
+        let calendar = self.calendar.clone();
+        let transitions = self.transitions.clone();
+        let pipeline = self.pipeline.clone();
 
-        tokio::spawn(async move {
-            tracing::info!("数据采集调度器已启动，间隔: {}秒", interval_secs);
+        let task = tokio::spawn(async move {
+            tracing::info!("数据采集调度器已启动，默认间隔: {}秒", interval.as_secs());
+            let mut phase = None;
 
             loop {
-                timer.tick().await;
+                let now = clock::now_local();
+                let current = SessionPhase::at(&calendar, now);
+                if phase != Some(current) {
+                    tracing::info!("交易时段切换: {:?} -> {:?}", phase, current);
+                    let _ = transitions.send(PhaseTransition {
+                        from: phase,
+                        to: current,
+                        at: clock::to_utc(now),
+                    });
+                    phase = Some(current);
+                }
+
+                let poll = cadence[&current];
+                if poll.is_some() {
+                    tracing::debug!("触发数据采集任务 ({:?})", current);
+
+                    if let Some(pipeline) = &pipeline {
+                        if let Err(e) = pipeline.tick().await {
+                            tracing::warn!("数据采集失败: {}", e);
+                        }
+                    }
+                }
+
+                // 等到下一次采集或阶段切换，先到者为准
+                let until_switch = session::next_transition(&calendar, now)
+                    .map(|(at, _)| (at - now).to_std().unwrap_or_default());
+                let wait = match (poll, until_switch) {
+                    (Some(poll), Some(switch)) => poll.min(switch),
+                    (poll, switch) => poll.or(switch).unwrap_or(IDLE_RECHECK),
+                };
+
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = shutdown_rx.changed() => break,
+                }
 
                 // 检查是否应该继续运行
                 if !*is_running.read().await {
-                    tracing::info!("数据采集调度器已停止");
                     break;
                 }
+            }
 
-                // 仅在交易日交易时间采集
-                if Self::is_trading_day() && Self::is_trading_time() {
-                    tracing::debug!("触发数据采集任务");
-
-                    // TODO: 触发实际的采集任务
-                    // 这里暂时只是日志
-                } else {
-                    tracing::debug!("非交易时间，跳过采集");
+            if let Some(pipeline) = &pipeline {
+                if let Err(e) = pipeline.close().await {
+                    tracing::error!("关闭采集流水线失败: {}", e);
                 }
             }
+            tracing::info!("数据采集调度器已停止");
         });
+        *self.task.lock().await = Some(task);
 
         Ok(())
     }
 
-    /// 停止定时采集
+    /// 停止定时采集，等待进行中的采集完成、缓冲区写完后返回
     pub async fn stop(&self) -> Result<()> {
-        let mut is_running = self.is_running.write().await;
-        *is_running = false;
+        *self.is_running.write().await = false;
+        // 关闭通道唤醒等待中的调度任务
+        self.shutdown.lock().await.take();
         tracing::info!("数据采集调度器停止请求已发送");
+
+        if let Some(task) = self.task.lock().await.take() {
+            task.await
+                .map_err(|e| AppError::Internal(format!("调度任务异常退出: {}", e)))?;
+        }
         Ok(())
     }
 
@@ -108,11 +218,15 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_is_trading_day_weekday() {
-        // 这个测试依赖于实际运行日期
-        let result = CollectionScheduler::is_trading_day();
-        // 仅验证方法可调用
-        assert!(result == true || result == false);
+    fn test_is_trading_day_uses_calendar() {
+        let config = Arc::new(RwLock::new(DataSourceConfig::default()));
+        let today = clock::now_local().date();
+
+        // 今天设为节假日则不是交易日
+        let toml = format!("[{}]\nholidays = [\"{}\"]", today.format("%Y"), today);
+        let calendar = TradingCalendar::from_toml(&toml).unwrap();
+        let scheduler = CollectionScheduler::new(config).with_calendar(Arc::new(calendar));
+        assert!(!scheduler.is_trading_day());
     }
 
     #[tokio::test]
@@ -132,6 +246,65 @@ mod tests {
         assert!(!scheduler.is_running().await);
     }
 
+    #[tokio::test]
+    async fn test_phase_cadence() {
+        let config = Arc::new(RwLock::new(DataSourceConfig::default()));
+        let scheduler = CollectionScheduler::new(config)
+            .with_cadence(SessionPhase::OpeningAuction, Some(Duration::from_secs(1)))
+            .with_cadence(SessionPhase::ClosingAuction, None);
+
+        let every = |secs| Some(Duration::from_secs(secs));
+        assert_eq!(scheduler.poll_interval(SessionPhase::OpeningAuction).await, every(1));
+        assert_eq!(scheduler.poll_interval(SessionPhase::MorningContinuous).await, every(3));
+        assert_eq!(scheduler.poll_interval(SessionPhase::ClosingAuction).await, None);
+        assert_eq!(scheduler.poll_interval(SessionPhase::LunchBreak).await, None);
+        assert_eq!(scheduler.poll_interval(SessionPhase::Closed).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_phases() {
+        let config = Arc::new(RwLock::new(DataSourceConfig::default()));
+        let scheduler = CollectionScheduler::new(config);
+        let mut transitions = scheduler.subscribe_phases();
+
+        scheduler.start().await.unwrap();
+        let first = tokio::time::timeout(Duration::from_secs(5), transitions.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.from, None);
+        assert_eq!(first.to, scheduler.current_phase());
+
+        scheduler.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_drains_pipeline() {
+        use crate::collector::csv_source::fixture_source;
+        use crate::collector::pipeline::tests::MemorySink;
+        use crate::monitor::CollectorMonitor;
+
+        let sink = Arc::new(MemorySink::default());
+        let monitor = Arc::new(CollectorMonitor::new(Vec::new()));
+        let pipeline = CollectionPipeline::new(Arc::new(fixture_source()), sink.clone(), monitor.clone())
+            .with_codes(vec!["000001".to_string()]);
+
+        // 所有阶段都每 20ms 采集一次，与测试运行时刻无关
+        let mut scheduler = CollectionScheduler::new(Arc::new(RwLock::new(DataSourceConfig::default())))
+            .with_pipeline(Arc::new(pipeline));
+        for phase in SessionPhase::ALL {
+            scheduler = scheduler.with_cadence(phase, Some(Duration::from_millis(20)));
+        }
+
+        scheduler.start().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        scheduler.stop().await.unwrap();
+
+        let ticks = monitor.get_metrics().await.success_count as usize;
+        assert!(ticks >= 2);
+        assert_eq!(sink.rows.lock().unwrap().len(), ticks);
+    }
+
     #[tokio::test]
     async fn test_scheduler_double_start() {
         let config = Arc::new(RwLock::new(DataSourceConfig::default()));
@@ -0,0 +1,288 @@
+//! 序列级数据质量检查 - 同一证券按时间排列的日线之间的一致性
+//!
+//! | 检查 | 问题类型 | 严重程度 |
+//! |------|----------|----------|
+//! | 同一时间多条记录 | `duplicate` | 数值一致为警告，不一致为错误 |
+//! | 首尾之间缺少交易日 | `gap` | 警告 |
+//! | 成交量为 0 且价格不变（停牌） | `abnormal` | 信息 |
+//! | 成交量为 0 但价格变动 | `abnormal` | 错误 |
+//! | 与前一交易日完全相同（数据未更新） | `abnormal` | 警告 |
+//! | 超出涨跌停且无除权记录可解释的跳空 | `abnormal` | 警告 |
+//!
+//! 跳空以复权因子表中当日的昨收（除权日为除权参考价）为基准，没有因子时取前一日收盘价。
+
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::collector::factor::FactorData;
+use crate::collector::validator::PriceLimit;
+use crate::collector::writer::{BatchSink, LogSink};
+use crate::error::Result;
+use crate::models::quality::{IssueType, QualityIssue, Severity};
+use crate::models::quote::KLine;
+use crate::models::stock::Security;
+use chrono::{Days, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 序列级数据验证器
+pub struct SeriesValidator {
+    calendar: Arc<TradingCalendar>,
+    log: Arc<dyn BatchSink<QualityIssue>>,
+}
+
+impl SeriesValidator {
+    pub fn new(calendar: Arc<TradingCalendar>) -> Self {
+        Self {
+            calendar,
+            log: Arc::new(LogSink),
+        }
+    }
+
+    /// 设置质量日志写入目标（`data_quality_log`）
+    pub fn with_log(mut self, log: Arc<dyn BatchSink<QualityIssue>>) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// 检查日线序列并写入质量日志
+    pub async fn validate(
+        &self,
+        code: &str,
+        bars: &[KLine],
+        factors: &[FactorData],
+        security: Option<&Security>,
+    ) -> Result<Vec<QualityIssue>> {
+        let findings = self.check(code, bars, factors, security);
+        if !findings.is_empty() {
+            tracing::info!("{} 日线发现 {} 个质量问题", code, findings.len());
+            self.log.write_batch(&findings).await?;
+        }
+        Ok(findings)
+    }
+
+    /// 检查日线序列（无需有序），按日期返回发现的问题
+    ///
+    /// `factors` 为同一证券的复权因子，`security` 用于确定每日的涨跌幅（缺省按代码推断板块）。
+    pub fn check(
+        &self,
+        code: &str,
+        bars: &[KLine],
+        factors: &[FactorData],
+        security: Option<&Security>,
+    ) -> Vec<QualityIssue> {
+        let now = Utc::now();
+        let finding = |date, issue_type, severity, description: String| QualityIssue {
+            log_time: now,
+            date,
+            code: code.to_string(),
+            issue_type,
+            description,
+            severity,
+        };
+
+        let mut sorted: Vec<&KLine> = bars.iter().collect();
+        sorted.sort_by_key(|bar| bar.datetime);
+
+        let mut findings = Vec::new();
+
+        // 重复：同一时间只保留第一条参与后续检查
+        let mut series: Vec<&KLine> = Vec::with_capacity(sorted.len());
+        for group in sorted.chunk_by(|a, b| a.datetime == b.datetime) {
+            let first = group[0];
+            if group.len() > 1 {
+                let consistent = group.iter().all(|bar| same_values(bar, first));
+                let (severity, detail) = if consistent {
+                    (Severity::Warning, "数值一致")
+                } else {
+                    (Severity::Error, "数值不一致")
+                };
+                findings.push(finding(
+                    clock::local_date(first.datetime),
+                    IssueType::Duplicate,
+                    severity,
+                    format!("{} 有 {} 条记录，{}", clock::to_local(first.datetime), group.len(), detail),
+                ));
+            }
+            series.push(first);
+        }
+
+        let preclose_by_date: HashMap<NaiveDate, f64> = factors.iter().map(|f| (f.date, f.preclose)).collect();
+
+        for (i, bar) in series.iter().enumerate() {
+            let date = clock::local_date(bar.datetime);
+
+            // 停牌与成交量为 0 的异常
+            if bar.volume == 0.0 {
+                if bar.open == bar.close && bar.high == bar.close && bar.low == bar.close {
+                    findings.push(finding(
+                        date,
+                        IssueType::Abnormal,
+                        Severity::Info,
+                        format!("成交量为 0 且价格不变（{}），疑似停牌", bar.close),
+                    ));
+                } else {
+                    findings.push(finding(
+                        date,
+                        IssueType::Abnormal,
+                        Severity::Error,
+                        format!("成交量为 0 但价格变动: 最低 {} 最高 {}", bar.low, bar.high),
+                    ));
+                }
+            }
+
+            let Some(prev) = i.checked_sub(1).map(|j| series[j]) else {
+                continue;
+            };
+            let prev_date = clock::local_date(prev.datetime);
+
+            // 缺少交易日
+            if let Some(after_prev) = prev_date.checked_add_days(Days::new(1)) {
+                let missing = self.calendar.trading_days_between(after_prev, date - Days::new(1));
+                if let (Some(first), Some(last)) = (missing.first(), missing.last()) {
+                    findings.push(finding(
+                        *first,
+                        IssueType::Gap,
+                        Severity::Warning,
+                        format!("缺少 {} 个交易日: {} 至 {}", missing.len(), first, last),
+                    ));
+                }
+            }
+
+            // 数据未更新
+            if bar.volume > 0.0 && same_values(bar, prev) {
+                findings.push(finding(
+                    date,
+                    IssueType::Abnormal,
+                    Severity::Warning,
+                    format!("与 {} 的日线完全相同，疑似数据未更新", prev_date),
+                ));
+                continue;
+            }
+
+            // 无法解释的跳空
+            let preclose = preclose_by_date.get(&date).copied().unwrap_or(prev.close);
+            let limit = match security {
+                Some(security) => PriceLimit::for_security(security, date, &self.calendar),
+                None => PriceLimit::for_code(code),
+            };
+            if preclose > 0.0 && !limit.contains(preclose, bar.low, bar.high) {
+                let change = (bar.close - preclose) / preclose * 100.0;
+                findings.push(finding(
+                    date,
+                    IssueType::Abnormal,
+                    Severity::Warning,
+                    format!(
+                        "相对昨收 {} 最高 {} 最低 {}（收盘 {:+.2}%）超出涨跌停，且无除权记录可解释",
+                        preclose, bar.high, bar.low, change
+                    ),
+                ));
+            }
+        }
+
+        findings.sort_by_key(|f| f.date);
+        findings
+    }
+}
+
+fn same_values(a: &KLine, b: &KLine) -> bool {
+    a.open == b.open
+        && a.high == b.high
+        && a.low == b.low
+        && a.close == b.close
+        && a.volume == b.volume
+        && a.amount == b.amount
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::factor::FactorSource;
+    use crate::collector::validator::QualityScore;
+
+    /// 记录写入日志的内存目标
+    #[derive(Default)]
+    struct MemoryLog {
+        rows: std::sync::Mutex<Vec<QualityIssue>>,
+    }
+
+    #[async_trait::async_trait]
+    impl BatchSink<QualityIssue> for MemoryLog {
+        async fn write_batch(&self, batch: &[QualityIssue]) -> Result<()> {
+            self.rows.lock().unwrap().extend_from_slice(batch);
+            Ok(())
+        }
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, d).unwrap()
+    }
+
+    fn bar(d: u32, low: f64, high: f64, close: f64, volume: f64) -> KLine {
+        KLine {
+            datetime: clock::to_utc(day(d).and_hms_opt(15, 0, 0).unwrap()),
+            code: "000001".to_string(),
+            open: low,
+            high,
+            low,
+            close,
+            volume,
+            amount: volume * close,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_series_findings() {
+        let mut changed = bar(4, 9.9, 10.2, 10.1, 1000.0);
+        changed.close = 10.0;
+        let bars = vec![
+            bar(3, 9.9, 10.1, 10.0, 1000.0),
+            bar(4, 9.9, 10.2, 10.1, 1000.0),
+            changed,                                  // 6-04 重复且数值不一致
+            // 6-05 缺失
+            bar(6, 10.0, 10.3, 10.2, 1000.0),
+            bar(7, 10.2, 10.2, 10.2, 0.0),            // 停牌
+            // 6-10 端午休市
+            bar(11, 11.0, 12.5, 12.5, 3000.0),        // 超过 11.22 涨停价
+            bar(12, 6.2, 6.4, 6.3, 5000.0),           // 10 送 10 除权，参考价 6.25
+            bar(13, 6.2, 6.4, 6.3, 5000.0),           // 与前一日完全相同
+        ];
+        let factors = vec![FactorData {
+            date: day(12),
+            code: "000001".to_string(),
+            open: 6.2,
+            high: 6.4,
+            low: 6.2,
+            close: 6.3,
+            preclose: 6.25,
+            factor: 2.0,
+            volume: 5000.0,
+            amount: 31500.0,
+            data_source: FactorSource::Api,
+            data_version: 1,
+            quality_score: QualityScore::Good,
+        }];
+
+        let log = Arc::new(MemoryLog::default());
+        let validator = SeriesValidator::new(Arc::new(TradingCalendar::bundled())).with_log(log.clone());
+        let findings = validator.validate("000001", &bars, &factors, None).await.unwrap();
+
+        let summary: Vec<(NaiveDate, IssueType, Severity)> =
+            findings.iter().map(|f| (f.date, f.issue_type, f.severity)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (day(4), IssueType::Duplicate, Severity::Error),
+                (day(5), IssueType::Gap, Severity::Warning),
+                (day(7), IssueType::Abnormal, Severity::Info),
+                (day(11), IssueType::Abnormal, Severity::Warning),
+                (day(13), IssueType::Abnormal, Severity::Warning),
+            ]
+        );
+        assert_eq!(log.rows.lock().unwrap().len(), 5);
+
+        // 无问题的序列不写日志
+        let clean = validator.validate("000001", &bars[..2], &[], None).await.unwrap();
+        assert!(clean.is_empty());
+        assert_eq!(log.rows.lock().unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,202 @@
+//! 交易时段 - 按北京时间划分竞价、连续竞价与休市阶段
+//!
+//! | 时间 | 阶段 |
+//! |------|------|
+//! | 9:15–9:20 | 开盘集合竞价（可撤单） |
+//! | 9:20–9:25 | 开盘集合竞价（不可撤单） |
+//! | 9:25–9:30 | 开盘撮合完成，等待连续竞价 |
+//! | 9:30–11:30 | 上午连续竞价 |
+//! | 11:30–13:00 | 午间休市 |
+//! | 13:00–14:57 | 下午连续竞价 |
+//! | 14:57–15:00 | 收盘集合竞价（不可撤单） |
+
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use chrono::{DateTime, Days, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 交易时段阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionPhase {
+    Closed,                // 非交易日
+    PreMarket,             // 交易日 9:15 前
+    OpeningAuction,        // 开盘集合竞价，可撤单
+    OpeningAuctionLocked,  // 开盘集合竞价，9:20 后不可撤单
+    OpeningPause,          // 开盘撮合完成至 9:30
+    MorningContinuous,     // 上午连续竞价
+    LunchBreak,            // 午间休市
+    AfternoonContinuous,   // 下午连续竞价
+    ClosingAuction,        // 收盘集合竞价
+    AfterClose,            // 交易日 15:00 后
+}
+
+/// 交易日内各阶段的起始时刻，9:15 之前为 [`SessionPhase::PreMarket`]
+const SCHEDULE: [(u32, u32, SessionPhase); 8] = [
+    (9, 15, SessionPhase::OpeningAuction),
+    (9, 20, SessionPhase::OpeningAuctionLocked),
+    (9, 25, SessionPhase::OpeningPause),
+    (9, 30, SessionPhase::MorningContinuous),
+    (11, 30, SessionPhase::LunchBreak),
+    (13, 0, SessionPhase::AfternoonContinuous),
+    (14, 57, SessionPhase::ClosingAuction),
+    (15, 0, SessionPhase::AfterClose),
+];
+
+impl SessionPhase {
+    /// 全部阶段，按一天中的先后排列
+    pub const ALL: [SessionPhase; 10] = [
+        SessionPhase::Closed,
+        SessionPhase::PreMarket,
+        SessionPhase::OpeningAuction,
+        SessionPhase::OpeningAuctionLocked,
+        SessionPhase::OpeningPause,
+        SessionPhase::MorningContinuous,
+        SessionPhase::LunchBreak,
+        SessionPhase::AfternoonContinuous,
+        SessionPhase::ClosingAuction,
+        SessionPhase::AfterClose,
+    ];
+
+    /// 北京时间 `now` 所处的阶段
+    pub fn at(calendar: &TradingCalendar, now: NaiveDateTime) -> Self {
+        if !calendar.is_trading_day(now.date()) {
+            return SessionPhase::Closed;
+        }
+
+        SCHEDULE
+            .iter()
+            .rev()
+            .find(|(h, m, _)| now.time() >= hm(*h, *m))
+            .map_or(SessionPhase::PreMarket, |(_, _, phase)| *phase)
+    }
+
+    /// 当前阶段（北京时间）
+    pub fn now(calendar: &TradingCalendar) -> Self {
+        Self::at(calendar, clock::now_local())
+    }
+
+    /// 是否有行情变化（集合竞价或连续竞价）
+    pub fn is_trading(self) -> bool {
+        self.is_auction() || self.is_continuous()
+    }
+
+    /// 是否为集合竞价
+    pub fn is_auction(self) -> bool {
+        matches!(
+            self,
+            SessionPhase::OpeningAuction | SessionPhase::OpeningAuctionLocked | SessionPhase::ClosingAuction
+        )
+    }
+
+    /// 是否为连续竞价
+    pub fn is_continuous(self) -> bool {
+        matches!(self, SessionPhase::MorningContinuous | SessionPhase::AfternoonContinuous)
+    }
+
+    /// 是否允许撤单
+    pub fn allows_cancel(self) -> bool {
+        matches!(self, SessionPhase::OpeningAuction) || self.is_continuous()
+    }
+}
+
+/// 阶段切换事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseTransition {
+    pub from: Option<SessionPhase>,  // 调度器启动时为空
+    pub to: SessionPhase,
+    pub at: DateTime<Utc>,
+}
+
+/// `now` 之后第一次阶段切换的北京时间及新阶段
+///
+/// 跨日切换发生在零点（如收盘后进入非交易日）。日历一年内都没有交易日时返回 `None`。
+pub fn next_transition(calendar: &TradingCalendar, now: NaiveDateTime) -> Option<(NaiveDateTime, SessionPhase)> {
+    let current = SessionPhase::at(calendar, now);
+
+    for offset in 0..=366 {
+        let date = now.date().checked_add_days(Days::new(offset))?;
+        let midnight = std::iter::once(NaiveTime::MIN);
+        let boundaries = SCHEDULE.iter().map(|(h, m, _)| hm(*h, *m));
+
+        for time in midnight.chain(boundaries) {
+            let candidate = date.and_time(time);
+            if candidate <= now {
+                continue;
+            }
+            let phase = SessionPhase::at(calendar, candidate);
+            if phase != current {
+                return Some((candidate, phase));
+            }
+        }
+    }
+
+    None
+}
+
+fn hm(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).expect("交易时段时刻合法")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_phase_boundaries() {
+        let calendar = TradingCalendar::bundled();
+        let phase = |h, m, s| SessionPhase::at(&calendar, at(2024, 6, 7, h, m, s));
+
+        assert_eq!(phase(9, 14, 59), SessionPhase::PreMarket);
+        assert_eq!(phase(9, 15, 0), SessionPhase::OpeningAuction);
+        assert_eq!(phase(9, 19, 59), SessionPhase::OpeningAuction);
+        assert_eq!(phase(9, 20, 0), SessionPhase::OpeningAuctionLocked);
+        assert_eq!(phase(9, 27, 0), SessionPhase::OpeningPause);
+        assert_eq!(phase(9, 30, 0), SessionPhase::MorningContinuous);
+        assert_eq!(phase(11, 30, 0), SessionPhase::LunchBreak);
+        assert_eq!(phase(13, 0, 0), SessionPhase::AfternoonContinuous);
+        assert_eq!(phase(14, 57, 0), SessionPhase::ClosingAuction);
+        assert_eq!(phase(15, 0, 0), SessionPhase::AfterClose);
+
+        // 周六、国庆
+        assert_eq!(SessionPhase::at(&calendar, at(2024, 6, 8, 10, 0, 0)), SessionPhase::Closed);
+        assert_eq!(SessionPhase::at(&calendar, at(2024, 10, 2, 10, 0, 0)), SessionPhase::Closed);
+
+        assert!(SessionPhase::OpeningAuction.allows_cancel());
+        assert!(!SessionPhase::OpeningAuctionLocked.allows_cancel());
+        assert!(!SessionPhase::ClosingAuction.allows_cancel());
+        assert!(!SessionPhase::LunchBreak.is_trading());
+    }
+
+    #[test]
+    fn test_next_transition() {
+        let calendar = TradingCalendar::bundled();
+
+        assert_eq!(
+            next_transition(&calendar, at(2024, 6, 7, 9, 20, 0)),
+            Some((at(2024, 6, 7, 9, 25, 0), SessionPhase::OpeningPause))
+        );
+        assert_eq!(
+            next_transition(&calendar, at(2024, 6, 7, 12, 0, 0)),
+            Some((at(2024, 6, 7, 13, 0, 0), SessionPhase::AfternoonContinuous))
+        );
+
+        // 周五收盘后零点进入周末，周一零点进入盘前
+        assert_eq!(
+            next_transition(&calendar, at(2024, 6, 7, 15, 30, 0)),
+            Some((at(2024, 6, 8, 0, 0, 0), SessionPhase::Closed))
+        );
+        assert_eq!(
+            next_transition(&calendar, at(2024, 6, 8, 0, 0, 0)),
+            Some((at(2024, 6, 11, 0, 0, 0), SessionPhase::PreMarket))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! 数据源抽象 - 行情服务与历史导入不直接依赖通达信客户端
+//!
+//! - [`TdxClient`]：通达信行情服务器（默认）
+//! - [`CsvDataSource`]：本地 CSV 样本，用于离线开发与测试
+
+use crate::collector::csv_source::CsvDataSource;
+use crate::collector::pool::ServerSnapshot;
+use crate::collector::protocol::{FinanceInfo, SecurityInfo};
+use crate::collector::tdx::TdxClient;
+use crate::config::DataSourceConfig;
+use crate::error::Result;
+use crate::models::auction::Auction;
+use crate::models::money_flow::Transaction;
+use crate::models::quote::{KLine, KLinePeriod, Quote};
+use crate::models::stock::Market;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::sync::Arc;
+
+/// 行情数据源
+#[async_trait]
+pub trait DataSource: Send + Sync {
+    /// 数据源名称（用于日志与数据溯源）
+    fn name(&self) -> &str;
+
+    /// 获取市场全部证券
+    async fn get_all_securities(&self, market: Market) -> Result<Vec<SecurityInfo>>;
+
+    /// 获取基本财务信息（股本、上市日期）
+    async fn get_finance_info(&self, market: Market, code: &str) -> Result<FinanceInfo>;
+
+    /// 批量获取实时行情，无数据的代码不返回
+    async fn get_quotes(&self, codes: &[String]) -> Result<Vec<Quote>>;
+
+    /// 获取指定日期区间的 K 线（含首尾，按时间升序）
+    async fn get_kline(
+        &self,
+        code: &str,
+        period: KLinePeriod,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<KLine>>;
+
+    /// 获取某日全部逐笔成交（`None` 为当日，按时间升序）
+    async fn get_transactions(&self, code: &str, date: Option<NaiveDate>) -> Result<Vec<Transaction>>;
+
+    /// 获取集合竞价快照，无数据的代码不返回
+    async fn get_auctions(&self, codes: &[String]) -> Result<Vec<Auction>>;
+
+    /// 上游服务器状态（无远程连接的数据源为空）
+    fn server_status(&self) -> Vec<ServerSnapshot> {
+        Vec::new()
+    }
+}
+
+/// 按配置创建数据源：配置了 `csv_dir` 时读取本地 CSV，否则连接通达信服务器
+pub fn from_config(config: &DataSourceConfig) -> Arc<dyn DataSource> {
+    match &config.csv_dir {
+        Some(dir) => {
+            tracing::info!("使用本地 CSV 数据源: {}", dir.display());
+            Arc::new(CsvDataSource::new(dir))
+        }
+        None => Arc::new(TdxClient::new(config.tdx_servers.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_from_config() {
+        let mut config = DataSourceConfig {
+            tdx_servers: vec!["127.0.0.1:7709".to_string()],
+            update_interval_secs: 3,
+            csv_dir: None,
+            calendar_path: None,
+        };
+        assert_eq!(from_config(&config).name(), "tdx");
+
+        config.csv_dir = Some(PathBuf::from("fixtures"));
+        assert_eq!(from_config(&config).name(), "csv");
+    }
+}
@@ -1,81 +1,496 @@
 //! 通达信数据采集客户端
 
+use crate::collector::clock;
+use crate::collector::pool::{PoolConfig, ServerPool, ServerSnapshot};
+use crate::collector::protocol::{self, FinanceInfo, Request, ResponseHeader, SecurityInfo, RESPONSE_HEADER_LEN};
+use crate::collector::retry::RetryPolicy;
+use crate::collector::source::DataSource;
 use crate::error::{AppError, Result};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::models::auction::Auction;
+use crate::models::money_flow::Transaction;
+use crate::models::quote::{KLine, KLinePeriod, Quote};
+use crate::models::stock::Market;
+use crate::monitor::CollectorMonitor;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
 
-/// 通达信客户端（支持多服务器）
+/// 默认连接/读写超时
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 单个通达信服务器连接（已完成握手）
+pub struct TdxConnection {
+    addr: String,
+    stream: TcpStream,
+    seq: AtomicU32,
+    timeout: Duration,
+}
+
+impl TdxConnection {
+    /// 建立连接并完成握手
+    pub async fn connect(addr: &str, io_timeout: Duration) -> Result<Self> {
+        let stream = timeout(io_timeout, TcpStream::connect(addr))
+            .await
+            .map_err(|_| AppError::Network(format!("连接 {} 超时", addr)))?
+            .map_err(|e| AppError::Network(format!("连接 {} 失败: {}", addr, e)))?;
+        stream.set_nodelay(true).ok();
+
+        let mut conn = Self {
+            addr: addr.to_string(),
+            stream,
+            seq: AtomicU32::new(1),
+            timeout: io_timeout,
+        };
+
+        for request in protocol::setup_requests() {
+            conn.request(&request).await?;
+        }
+
+        Ok(conn)
+    }
+
+    /// 发送请求并读取解压后的响应 body
+    pub async fn request(&mut self, request: &Request) -> Result<Vec<u8>> {
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst);
+        let packet = request.encode(seq);
+
+        timeout(self.timeout, self.roundtrip(&packet))
+            .await
+            .map_err(|_| AppError::Network(format!("服务器 {} 响应超时", self.addr)))?
+    }
+
+    async fn roundtrip(&mut self, packet: &[u8]) -> Result<Vec<u8>> {
+        self.stream
+            .write_all(packet)
+            .await
+            .map_err(|e| AppError::Network(format!("发送请求失败: {}", e)))?;
+
+        let mut head = [0u8; RESPONSE_HEADER_LEN];
+        self.stream
+            .read_exact(&mut head)
+            .await
+            .map_err(|e| AppError::Network(format!("读取响应头失败: {}", e)))?;
+        let header = ResponseHeader::parse(&head);
+
+        let mut body = vec![0u8; header.zip_size];
+        self.stream
+            .read_exact(&mut body)
+            .await
+            .map_err(|e| AppError::Network(format!("读取响应数据失败: {}", e)))?;
+
+        header.decode_body(body)
+    }
+
+    /// 连接的服务器地址
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+}
+
+/// 通达信客户端（支持多服务器，基于连接池，网络错误自动重试）
 pub struct TdxClient {
-    servers: Vec<String>,
-    current_index: Arc<AtomicUsize>,
+    pool: ServerPool,
+    retry: RetryPolicy,
 }
 
 impl TdxClient {
     /// 创建新的通达信客户端
     pub fn new(servers: Vec<String>) -> Self {
-        let current_index = Arc::new(AtomicUsize::new(0));
-        Self { servers, current_index }
+        Self {
+            pool: ServerPool::new(servers, PoolConfig {
+                timeout: DEFAULT_TIMEOUT,
+                ..PoolConfig::default()
+            }),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// 设置连接/读写超时
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.pool = self.pool.with_timeout(timeout);
+        self
+    }
+
+    /// 设置保持的连接数
+    pub fn with_pool_size(mut self, size: usize) -> Self {
+        self.pool = self.pool.with_size(size);
+        self
+    }
+
+    /// 设置熔断后的冷却时间
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.pool = self.pool.with_cooldown(cooldown);
+        self
+    }
+
+    /// 设置熔断阈值（单台服务器连续失败次数）
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.pool = self.pool.with_failure_threshold(threshold);
+        self
+    }
+
+    /// 限制每台服务器每秒的请求数（含重试），请求分摊到最早有名额的服务器
+    pub fn with_server_budget(mut self, requests_per_sec: f64) -> Self {
+        self.pool = self.pool.with_server_budget(requests_per_sec);
+        self
+    }
+
+    /// 设置重试策略
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
     }
 
-    /// 测试连接到通达信服务器（自动切换）
+    /// 上报服务器健康状态到监控器
+    pub fn with_monitor(mut self, monitor: Arc<CollectorMonitor>) -> Self {
+        self.pool = self.pool.with_monitor(monitor);
+        self
+    }
+
+    /// 探测全部服务器并建立连接，至少一台可用时成功
     pub async fn test_connection(&self) -> Result<()> {
-        let start_index = self.current_index.load(Ordering::SeqCst);
-        let server_count = self.servers.len();
+        match self.pool.probe_all().await {
+            0 => Err(AppError::Network("无法连接到任何通达信服务器".to_string())),
+            healthy => {
+                tracing::info!("通达信服务器探测完成: {}/{} 可用", healthy, self.pool.servers().len());
+                Ok(())
+            }
+        }
+    }
 
-        for i in 0..server_count {
-            let index = (start_index + i) % server_count;
-            let server = &self.servers[index];
+    /// 配置的服务器地址
+    pub fn servers(&self) -> Vec<String> {
+        self.pool.servers()
+    }
 
-            tracing::info!("尝试连接到服务器 [{}/{}]: {}", index + 1, server_count, server);
+    /// 全部服务器状态（按排名）
+    pub fn server_status(&self) -> Vec<ServerSnapshot> {
+        self.pool.snapshot()
+    }
 
-            match self.connect_single(server).await {
-                Ok(_) => {
-                    // 更新当前服务器索引
-                    self.current_index.store(index, Ordering::SeqCst);
-                    tracing::info!("成功连接到服务器: {}", server);
-                    return Ok(());
-                }
-                Err(e) => {
-                    tracing::warn!("连接服务器 {} 失败: {}", server, e);
-                    continue;
-                }
+    /// 经连接池执行请求，网络错误按重试策略退避重试（失败的服务器会被降级或熔断）
+    async fn execute(&self, request: &Request) -> Result<Vec<u8>> {
+        let name = format!("通达信请求 0x{:04x}", request.cmd);
+        self.retry.run(&name, || self.pool.execute(request)).await
+    }
+
+    /// 获取市场证券数量
+    pub async fn get_security_count(&self, market: Market) -> Result<u16> {
+        let body = self.execute(&protocol::security_count_request(market)).await?;
+        protocol::parse_security_count(&body)
+    }
+
+    /// 获取市场证券列表（单页，从 start 开始最多 1000 条）
+    pub async fn get_security_list(&self, market: Market, start: u16) -> Result<Vec<SecurityInfo>> {
+        let body = self.execute(&protocol::security_list_request(market, start)).await?;
+        protocol::parse_security_list(market, &body)
+    }
+
+    /// 获取市场全部证券（自动翻页）
+    pub async fn get_all_securities(&self, market: Market) -> Result<Vec<SecurityInfo>> {
+        let count = self.get_security_count(market).await? as usize;
+        let mut all = Vec::with_capacity(count);
+
+        while all.len() < count {
+            let page = self.get_security_list(market, all.len() as u16).await?;
+            if page.is_empty() {
+                break;
             }
+            all.extend(page);
+        }
+
+        Ok(all)
+    }
+
+    /// 获取基本财务信息（股本、上市日期）
+    pub async fn get_finance_info(&self, market: Market, code: &str) -> Result<FinanceInfo> {
+        let body = self.execute(&protocol::finance_info_request(market, code)?).await?;
+        protocol::parse_finance_info(&body)
+    }
+
+    /// 批量获取实时行情（超过单次上限时自动分批）
+    pub async fn get_quotes(&self, codes: &[String]) -> Result<Vec<Quote>> {
+        let mut targets = Vec::with_capacity(codes.len());
+        for code in codes {
+            let market = Market::from_code(code)
+                .ok_or_else(|| AppError::Parse(format!("无法识别股票代码的市场: {}", code)))?;
+            targets.push((market, code.clone()));
         }
 
-        Err(AppError::Network("无法连接到任何通达信服务器".to_string()))
+        let mut quotes = Vec::with_capacity(codes.len());
+        for chunk in targets.chunks(protocol::MAX_QUOTES_PER_REQUEST) {
+            let body = self.execute(&protocol::quotes_request(chunk)?).await?;
+            quotes.extend(protocol::parse_quotes(&body)?);
+        }
+
+        Ok(quotes)
+    }
+
+    /// 获取单页 K 线（offset 为距最新一根的偏移，按时间升序返回）
+    pub async fn get_bars(
+        &self,
+        code: &str,
+        period: KLinePeriod,
+        offset: u16,
+        count: u16,
+    ) -> Result<Vec<KLine>> {
+        let market = Market::from_code(code)
+            .ok_or_else(|| AppError::Parse(format!("无法识别股票代码的市场: {}", code)))?;
+        let request = protocol::bars_request(market, code, period, offset, count)?;
+        let body = self.execute(&request).await?;
+        protocol::parse_bars(code, period, &body)
     }
 
-    /// 连接到单个服务器
-    async fn connect_single(&self, addr: &str) -> Result<()> {
-        let parts: Vec<&str> = addr.split(':').collect();
-        if parts.len() != 2 {
-            return Err(AppError::Config(format!("无效的地址格式: {}", addr)));
+    /// 获取指定日期区间的 K 线（含首尾，按北京时间日期过滤）
+    ///
+    /// 服务器单次最多返回 800 根，从最新一根开始向前翻页，
+    /// 直到覆盖起始日期或服务器无更多数据。
+    pub async fn get_kline(
+        &self,
+        code: &str,
+        period: KLinePeriod,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<KLine>> {
+        let page_size = protocol::MAX_BARS_PER_REQUEST;
+        let mut pages: Vec<Vec<KLine>> = Vec::new();
+        let mut offset: u16 = 0;
+
+        loop {
+            let page = self.get_bars(code, period, offset, page_size).await?;
+            let fetched = page.len() as u16;
+            let covered = page
+                .first()
+                .is_none_or(|bar| clock::local_date(bar.datetime) < start);
+            pages.push(page);
+
+            if covered || fetched < page_size {
+                break;
+            }
+
+            offset = match offset.checked_add(fetched) {
+                Some(next) => next,
+                None => {
+                    tracing::warn!("{} K 线偏移超过协议上限，停止翻页", code);
+                    break;
+                }
+            };
         }
 
-        let host = parts[0];
-        let _port: u16 = parts[1].parse()
-            .map_err(|_| AppError::Config(format!("无效的端口号: {}", parts[1])))?;
+        // 页面从新到旧获取，倒序拼接后为升序
+        let bars = pages
+            .into_iter()
+            .rev()
+            .flatten()
+            .filter(|bar| {
+                let date = clock::local_date(bar.datetime);
+                date >= start && date <= end
+            })
+            .collect();
+
+        Ok(bars)
+    }
+
+    /// 获取日线数据（日期格式 `%Y-%m-%d`）
+    pub async fn get_daily_data(&self, code: &str, start: &str, end: &str) -> Result<Vec<KLine>> {
+        let start = parse_date(start)?;
+        let end = parse_date(end)?;
+        self.get_kline(code, KLinePeriod::Day, start, end).await
+    }
+
+    /// 获取当日全部逐笔成交（按时间升序）
+    pub async fn get_transactions(&self, code: &str) -> Result<Vec<Transaction>> {
+        self.get_all_transactions(code, None).await
+    }
+
+    /// 获取历史某日全部逐笔成交（按时间升序）
+    pub async fn get_history_transactions(&self, code: &str, date: NaiveDate) -> Result<Vec<Transaction>> {
+        self.get_all_transactions(code, Some(date)).await
+    }
+
+    /// 获取单页逐笔成交（offset 为距最新一笔的偏移，按时间升序返回）
+    pub async fn get_transaction_page(
+        &self,
+        code: &str,
+        date: Option<NaiveDate>,
+        offset: u16,
+        count: u16,
+    ) -> Result<Vec<Transaction>> {
+        let market = Market::from_code(code)
+            .ok_or_else(|| AppError::Parse(format!("无法识别股票代码的市场: {}", code)))?;
+        let request = protocol::transactions_request(market, code, date, offset, count)?;
+        let body = self.execute(&request).await?;
+        let day = date.unwrap_or_else(|| clock::now_local().date());
+        protocol::parse_transactions(code, day, date.is_some(), &body)
+    }
+
+    /// 从最新一笔向前翻页直到取完
+    async fn get_all_transactions(&self, code: &str, date: Option<NaiveDate>) -> Result<Vec<Transaction>> {
+        let page_size = protocol::MAX_TRANSACTIONS_PER_REQUEST;
+        let mut pages = Vec::new();
+        let mut offset: u16 = 0;
+
+        loop {
+            let page = self.get_transaction_page(code, date, offset, page_size).await?;
+            let fetched = page.len() as u16;
+            pages.push(page);
+
+            if fetched < page_size {
+                break;
+            }
 
-        // TODO: 实际使用 rustdx API 连接
-        // 这里暂时仅验证地址格式
-        if host.contains("invalid") {
-            return Err(AppError::Network("无效的主机地址".to_string()));
+            offset = match offset.checked_add(fetched) {
+                Some(next) => next,
+                None => {
+                    tracing::warn!("{} 逐笔成交偏移超过协议上限，停止翻页", code);
+                    break;
+                }
+            };
         }
 
-        tracing::debug!("连接到 {} (占位实现)", addr);
-        Ok(())
+        Ok(pages.into_iter().rev().flatten().collect())
     }
 
-    /// 获取当前服务器地址
+    /// 获取当前排名最高的服务器地址
     pub fn current_server(&self) -> String {
-        let index = self.current_index.load(Ordering::SeqCst);
-        self.servers.get(index).cloned().unwrap_or_default()
+        self.pool.current_server()
+    }
+}
+
+#[async_trait]
+impl DataSource for TdxClient {
+    fn name(&self) -> &str {
+        "tdx"
+    }
+
+    async fn get_all_securities(&self, market: Market) -> Result<Vec<SecurityInfo>> {
+        TdxClient::get_all_securities(self, market).await
+    }
+
+    async fn get_finance_info(&self, market: Market, code: &str) -> Result<FinanceInfo> {
+        TdxClient::get_finance_info(self, market, code).await
+    }
+
+    async fn get_quotes(&self, codes: &[String]) -> Result<Vec<Quote>> {
+        TdxClient::get_quotes(self, codes).await
+    }
+
+    async fn get_kline(
+        &self,
+        code: &str,
+        period: KLinePeriod,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<KLine>> {
+        TdxClient::get_kline(self, code, period, start, end).await
+    }
+
+    async fn get_transactions(&self, code: &str, date: Option<NaiveDate>) -> Result<Vec<Transaction>> {
+        self.get_all_transactions(code, date).await
+    }
+
+    /// 集合竞价期间行情接口的最新价即虚拟撮合价，成交量额为匹配量额
+    async fn get_auctions(&self, codes: &[String]) -> Result<Vec<Auction>> {
+        let quotes = TdxClient::get_quotes(self, codes).await?;
+        Ok(quotes
+            .into_iter()
+            .map(|q| Auction {
+                code: q.code,
+                name: q.name,
+                price: q.price,
+                preclose: q.preclose,
+                volume: q.volume,
+                amount: q.amount,
+                timestamp: q.timestamp,
+            })
+            .collect())
+    }
+
+    fn server_status(&self) -> Vec<ServerSnapshot> {
+        TdxClient::server_status(self)
     }
 }
 
+/// 解析 `%Y-%m-%d` 格式日期
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| AppError::Parse(format!("日期解析失败 {}: {}", date, e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collector::mock_server::{closed_addr, MockData, MockTdxServer};
+    use crate::models::money_flow::TradeDirection;
+    use chrono::{Datelike, Duration as ChronoDuration, NaiveDateTime, Timelike, Utc, Weekday};
+
+    /// 从 start 开始连续 n 个工作日的日线
+    fn daily_bars(code: &str, start: NaiveDate, n: usize) -> Vec<KLine> {
+        let mut bars = Vec::with_capacity(n);
+        let mut date = start;
+        while bars.len() < n {
+            if date.weekday() != Weekday::Sat && date.weekday() != Weekday::Sun {
+                let close = (1000 + bars.len()) as f64 / 100.0;
+                bars.push(bar(code, date.and_hms_opt(15, 0, 0).unwrap(), close));
+            }
+            date += ChronoDuration::days(1);
+        }
+        bars
+    }
+
+    fn bar(code: &str, local: NaiveDateTime, close: f64) -> KLine {
+        KLine {
+            datetime: clock::to_utc(local),
+            code: code.to_string(),
+            open: close - 0.05,
+            high: close + 0.1,
+            low: close - 0.1,
+            close,
+            volume: 12345.0,
+            amount: 1_234_500.0,
+        }
+    }
+
+    fn sample_data() -> MockData {
+        let securities = (0..1500)
+            .map(|i| SecurityInfo {
+                market: Market::SZ,
+                code: format!("{:06}", i + 1),
+                name: "平安银行".to_string(),
+                volunit: 100,
+                decimal_point: 2,
+                pre_close: 10.0,
+            })
+            .collect();
+
+        let quote = Quote {
+            code: "000001".to_string(),
+            name: String::new(),
+            price: 10.5,
+            preclose: 10.0,
+            open: 10.2,
+            high: 10.6,
+            low: 10.1,
+            volume: 100000.0,
+            amount: 105_000_000.0,
+            bid: [10.49, 10.48, 10.47, 10.46, 10.45],
+            bid_vol: [100.0, 200.0, 300.0, 400.0, 500.0],
+            ask: [10.51, 10.52, 10.53, 10.54, 10.55],
+            ask_vol: [100.0, 200.0, 300.0, 400.0, 500.0],
+            timestamp: Utc::now(),
+        };
+
+        MockData {
+            securities,
+            quotes: vec![quote],
+            ..Default::default()
+        }
+    }
 
     #[tokio::test]
     async fn test_tdx_client_creation() {
@@ -85,33 +500,280 @@ mod tests {
         ];
 
         let client = TdxClient::new(servers);
-        assert_eq!(client.servers.len(), 2);
+        assert_eq!(client.servers().len(), 2);
     }
 
     #[tokio::test]
     async fn test_server_rotation() {
-        let servers = vec![
-            "invalid.host:7709".to_string(),
-            "124.71.187.122:7709".to_string(),
-        ];
+        let server = MockTdxServer::start(MockData::default()).await;
+        let servers = vec![closed_addr().await, server.addr()];
 
         let client = TdxClient::new(servers);
         // 第一个服务器失败，应该自动切换到第二个
         let result = client.test_connection().await;
 
         assert!(result.is_ok(), "Should successfully connect to backup server");
+        assert_eq!(client.current_server(), server.addr());
+        // 三个握手包
+        assert_eq!(server.request_count(), 3);
     }
 
     #[tokio::test]
     async fn test_all_servers_fail() {
-        let servers = vec![
-            "invalid1.host:7709".to_string(),
-            "invalid2.host:7709".to_string(),
-        ];
+        let servers = vec![closed_addr().await, closed_addr().await];
 
         let client = TdxClient::new(servers);
         let result = client.test_connection().await;
 
         assert!(result.is_err(), "Should fail when all servers are unavailable");
     }
+
+    #[tokio::test]
+    async fn test_invalid_address() {
+        let client = TdxClient::new(vec!["127.0.0.1".to_string(), "127.0.0.1:port".to_string()]);
+        assert!(client.test_connection().await.is_err());
+        assert!(client.server_status().iter().all(|s| !s.healthy));
+    }
+
+    #[tokio::test]
+    async fn test_security_count_and_list() {
+        let server = MockTdxServer::start(sample_data()).await;
+        let client = TdxClient::new(vec![server.addr()]);
+
+        assert_eq!(client.get_security_count(Market::SZ).await.unwrap(), 1500);
+        assert_eq!(client.get_security_count(Market::SH).await.unwrap(), 0);
+
+        let page = client.get_security_list(Market::SZ, 0).await.unwrap();
+        assert_eq!(page.len(), 1000);
+        assert_eq!(page[0].code, "000001");
+        assert_eq!(page[0].name, "平安银行");
+        assert_eq!(page[0].pre_close, 10.0);
+
+        let all = client.get_all_securities(Market::SZ).await.unwrap();
+        assert_eq!(all.len(), 1500);
+        assert_eq!(all[1499].code, "001500");
+    }
+
+    #[tokio::test]
+    async fn test_get_finance_info() {
+        let info = FinanceInfo {
+            market: Market::SZ,
+            code: "000001".to_string(),
+            list_date: NaiveDate::from_ymd_opt(1991, 4, 3),
+            updated_date: NaiveDate::from_ymd_opt(2024, 4, 20),
+            total_shares: 19_405_920_000.0,
+            float_shares: 19_405_600_000.0,
+        };
+        let server = MockTdxServer::start(sample_data().with_finance(info.clone())).await;
+        let client = TdxClient::new(vec![server.addr()]);
+
+        assert_eq!(client.get_finance_info(Market::SZ, "000001").await.unwrap(), info);
+
+        // 无数据的证券日期为空、股本为 0
+        let empty = client.get_finance_info(Market::SZ, "000002").await.unwrap();
+        assert_eq!(empty.list_date, None);
+        assert_eq!(empty.total_shares, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_get_quotes() {
+        let server = MockTdxServer::start(sample_data()).await;
+        let client = TdxClient::new(vec![server.addr()]);
+
+        let quotes = client.get_quotes(&["000001".to_string()]).await.unwrap();
+        assert_eq!(quotes.len(), 1);
+
+        let quote = &quotes[0];
+        assert_eq!(quote.code, "000001");
+        assert_eq!(quote.price, 10.5);
+        assert_eq!(quote.preclose, 10.0);
+        assert_eq!(quote.low, 10.1);
+        assert_eq!(quote.volume, 100000.0);
+        assert!((quote.amount - 105_000_000.0).abs() < 1.0);
+        assert_eq!(quote.bid[4], 10.45);
+        assert_eq!(quote.ask_vol[2], 300.0);
+    }
+
+    #[tokio::test]
+    async fn test_failover_after_server_down() {
+        let primary = MockTdxServer::start(sample_data()).await;
+        let backup = MockTdxServer::start(sample_data()).await;
+        let client = TdxClient::new(vec![primary.addr(), backup.addr()])
+            .with_timeout(Duration::from_millis(500));
+
+        assert!(client.get_security_count(Market::SZ).await.is_ok());
+        assert_eq!(client.current_server(), primary.addr());
+
+        // 主服务器下线：请求失败后重试，切换到备用服务器
+        drop(primary);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(client.get_security_count(Market::SZ).await.unwrap(), 1500);
+        assert_eq!(client.current_server(), backup.addr());
+    }
+
+    #[tokio::test]
+    async fn test_get_daily_data_paginates() {
+        let first = NaiveDate::from_ymd_opt(2018, 1, 1).unwrap();
+        let bars = daily_bars("000001", first, 2000);
+        let last = clock::local_date(bars[1999].datetime);
+        let data = MockData::default().with_bars("000001", KLinePeriod::Day, bars.clone());
+        let server = MockTdxServer::start(data).await;
+        let client = TdxClient::new(vec![server.addr()]);
+
+        // 全区间：需要 3 页
+        let all = client
+            .get_daily_data("000001", "2018-01-01", &last.format("%Y-%m-%d").to_string())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2000);
+        assert_eq!(all[0].datetime, bars[0].datetime);
+        assert_eq!(all[1999].close, bars[1999].close);
+        assert!(all.windows(2).all(|w| w[0].datetime < w[1].datetime));
+
+        // 子区间：只返回区间内数据，且覆盖起始日期后停止翻页
+        let requests_before = server.request_count();
+        let start = clock::local_date(bars[1500].datetime);
+        let end = clock::local_date(bars[1509].datetime);
+        let part = client.get_kline("000001", KLinePeriod::Day, start, end).await.unwrap();
+        assert_eq!(part.len(), 10);
+        assert_eq!(part[0].datetime, bars[1500].datetime);
+        assert_eq!(server.request_count() - requests_before, 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_kline_all_periods() {
+        let day = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let minute1: Vec<KLine> = (0..5)
+            .map(|i| bar("600036", day.and_hms_opt(9, 31 + i, 0).unwrap(), (3000 + i) as f64 / 100.0))
+            .collect();
+        let minute5: Vec<KLine> = (0..3)
+            .map(|i| bar("600036", day.and_hms_opt(9, 35 + i * 5, 0).unwrap(), 30.0))
+            .collect();
+        let week = vec![bar("600036", NaiveDate::from_ymd_opt(2024, 1, 5).unwrap().and_hms_opt(15, 0, 0).unwrap(), 31.0)];
+        let month = vec![bar("600036", NaiveDate::from_ymd_opt(2024, 1, 31).unwrap().and_hms_opt(15, 0, 0).unwrap(), 32.0)];
+
+        let data = MockData::default()
+            .with_bars("600036", KLinePeriod::Minute1, minute1.clone())
+            .with_bars("600036", KLinePeriod::Minute5, minute5)
+            .with_bars("600036", KLinePeriod::Day, daily_bars("600036", day, 3))
+            .with_bars("600036", KLinePeriod::Week, week)
+            .with_bars("600036", KLinePeriod::Month, month);
+        let server = MockTdxServer::start(data).await;
+        let client = TdxClient::new(vec![server.addr()]);
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let m1 = client.get_kline("600036", KLinePeriod::Minute1, start, end).await.unwrap();
+        assert_eq!(m1.len(), 5);
+        assert_eq!(m1[4].datetime, minute1[4].datetime);
+        assert_eq!(m1[4].close, 30.04);
+
+        let m5 = client.get_kline("600036", KLinePeriod::Minute5, start, end).await.unwrap();
+        assert_eq!(m5.len(), 3);
+        let d = client.get_kline("600036", KLinePeriod::Day, start, end).await.unwrap();
+        assert_eq!(d.len(), 3);
+        let w = client.get_kline("600036", KLinePeriod::Week, start, end).await.unwrap();
+        assert_eq!(w[0].close, 31.0);
+        let m = client.get_kline("600036", KLinePeriod::Month, start, end).await.unwrap();
+        assert_eq!(m[0].close, 32.0);
+    }
+
+    #[tokio::test]
+    async fn test_get_daily_data_invalid_date() {
+        let client = TdxClient::new(vec![closed_addr().await]);
+        let result = client.get_daily_data("000001", "2024/01/01", "2024-01-31").await;
+        assert!(matches!(result, Err(AppError::Parse(_))));
+    }
+
+    fn transaction(code: &str, local: NaiveDateTime, price: f64, volume: f64, direction: TradeDirection) -> Transaction {
+        Transaction {
+            code: code.to_string(),
+            datetime: clock::to_utc(local),
+            price,
+            volume,
+            amount: price * volume * 100.0,
+            num: 3,
+            direction,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_transactions_today_paginates() {
+        let today = clock::now_local().date();
+        let directions = [TradeDirection::Buy, TradeDirection::Sell, TradeDirection::Neutral];
+        let list: Vec<Transaction> = (0..4500)
+            .map(|i| {
+                let local = today.and_hms_opt(9, 30, 0).unwrap() + ChronoDuration::seconds(i as i64 * 3);
+                let price = (1000 + i % 50) as f64 / 100.0;
+                transaction("000001", local, price, (i % 7 + 1) as f64, directions[i % 3])
+            })
+            .collect();
+
+        let server = MockTdxServer::start(MockData::default().with_transactions("000001", None, list.clone())).await;
+        let client = TdxClient::new(vec![server.addr()]);
+
+        let result = client.get_transactions("000001").await.unwrap();
+        assert_eq!(result.len(), 4500);
+        // 3 次握手 + 3 页
+        assert_eq!(server.request_count(), 6);
+
+        for (got, want) in result.iter().zip(&list).step_by(997) {
+            // 协议只精确到分钟
+            assert_eq!(clock::to_local(got.datetime).minute(), clock::to_local(want.datetime).minute());
+            assert_eq!(got.price, want.price);
+            assert_eq!(got.volume, want.volume);
+            assert_eq!(got.num, 3);
+            assert_eq!(got.direction, want.direction);
+        }
+        assert_eq!(result[4499].price, list[4499].price);
+    }
+
+    #[tokio::test]
+    async fn test_get_history_transactions() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let list = vec![
+            transaction("600000", date.and_hms_opt(9, 25, 0).unwrap(), 7.01, 500.0, TradeDirection::Neutral),
+            transaction("600000", date.and_hms_opt(9, 30, 0).unwrap(), 7.02, 120.0, TradeDirection::Buy),
+            transaction("600000", date.and_hms_opt(14, 59, 0).unwrap(), 6.98, 80.0, TradeDirection::Sell),
+        ];
+
+        let server = MockTdxServer::start(MockData::default().with_transactions("600000", Some(date), list)).await;
+        let client = TdxClient::new(vec![server.addr()]);
+
+        let result = client.get_history_transactions("600000", date).await.unwrap();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0].direction, TradeDirection::Neutral);
+        assert_eq!(clock::to_local(result[1].datetime), date.and_hms_opt(9, 30, 0).unwrap());
+        assert_eq!(result[2].price, 6.98);
+        assert_eq!(result[2].num, 0);
+        assert!((result[2].amount - 6.98 * 8000.0).abs() < 1e-6);
+
+        // 其他日期无数据
+        let other = client.get_history_transactions("600000", date.succ_opt().unwrap()).await.unwrap();
+        assert!(other.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_after_repeated_failures() {
+        let server = MockTdxServer::start(sample_data()).await;
+        let client = TdxClient::new(vec![server.addr()])
+            .with_timeout(Duration::from_millis(300))
+            .with_failure_threshold(2)
+            .with_retry(RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(5),
+            });
+        assert!(client.get_security_count(Market::SZ).await.is_ok());
+
+        drop(server);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // 两次失败后熔断，第三次尝试无可用服务器
+        assert!(matches!(client.get_security_count(Market::SZ).await, Err(AppError::Network(_))));
+        let status = client.server_status();
+        assert_eq!(status[0].breaker, crate::collector::breaker::BreakerState::Open);
+        assert!(!status[0].healthy);
+    }
 }
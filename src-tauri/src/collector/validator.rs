@@ -1,17 +1,137 @@
-use crate::models::stock::Market;
-use crate::models::quote::KLine;
-use chrono::{NaiveDate, Utc};
+use crate::collector::calendar::TradingCalendar;
+use crate::collector::clock;
+use crate::models::stock::{Board, Market, Security, StStatus};
+use crate::models::quote::{KLine, Quote};
+use crate::collector::rules::ValidationRules;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 use crate::{Result, AppError};
 
-/// 数据质量评分
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// 数据质量评分（与 `factor.quality_score` 一致）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum QualityScore {
+    #[default]
     Good = 1,      // 良好
     Suspect = 2,   // 可疑
     Error = 3,     // 错误
 }
 
+impl QualityScore {
+    /// 由 ClickHouse 枚举值转换
+    pub fn from_value(value: i8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Good),
+            2 => Some(Self::Suspect),
+            3 => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Suspect => "suspect",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// 涨跌幅限制
+///
+/// | 板块 | 涨跌幅 |
+/// |------|--------|
+/// | 主板 | 10%（ST、*ST 在 2025-07-07 之前为 5%） |
+/// | 创业板、科创板 | 20% |
+/// | 北交所 | 30% |
+///
+/// 新股上市初期不设涨跌幅：北交所首日，其余板块前 5 个交易日。
+/// 沪深主板风险警示股票的涨跌幅自 2025-07-07 起由 5% 调整为 10%（沪深交易所股票上市规则修订）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceLimit {
+    Percent(u32),  // 相对昨收的涨跌幅（百分比）
+    Unlimited,     // 不设涨跌幅
+}
+
+impl PriceLimit {
+    /// 板块与风险警示状态在 `date` 当日对应的涨跌幅（不考虑新股）
+    pub fn for_board(board: Board, st: StStatus, date: NaiveDate) -> Self {
+        match board {
+            Board::Main if st != StStatus::Normal && date < main_st_limit_change() => PriceLimit::Percent(5),
+            Board::Main => PriceLimit::Percent(10),
+            Board::ChiNext | Board::Star => PriceLimit::Percent(20),
+            Board::Bse => PriceLimit::Percent(30),
+        }
+    }
+
+    /// 由代码推断板块（没有证券主数据时使用，不识别 ST 与新股；非股票不设限制）
+    pub fn for_code(code: &str) -> Self {
+        let today = clock::now_local().date();
+        Market::from_code(code)
+            .and_then(|market| Board::classify(market, code))
+            .map_or(PriceLimit::Unlimited, |board| Self::for_board(board, StStatus::Normal, today))
+    }
+
+    /// 证券在 `date` 当日的涨跌幅
+    pub fn for_security(security: &Security, date: NaiveDate, calendar: &TradingCalendar) -> Self {
+        let Some(board) = security.board else {
+            return PriceLimit::Unlimited;
+        };
+
+        if let Some(list_date) = security.list_date {
+            let free_days = if board == Board::Bse { 1 } else { 5 };
+            if date >= list_date && calendar.trading_days_between(list_date, date).len() <= free_days {
+                return PriceLimit::Unlimited;
+            }
+        }
+
+        Self::for_board(board, security.st, date)
+    }
+
+    /// 跌停价与涨停价（元）
+    ///
+    /// 按交易所规则以昨收乘以涨跌幅后四舍五入到分，跌停价不低于 0.01。
+    pub fn limit_prices(self, preclose: f64) -> Option<(f64, f64)> {
+        let (down, up) = self.limit_cents(to_cents(preclose))?;
+        Some((down as f64 / 100.0, up as f64 / 100.0))
+    }
+
+    /// 以分计的跌停价与涨停价
+    fn limit_cents(self, preclose: i64) -> Option<(i64, i64)> {
+        let PriceLimit::Percent(pct) = self else {
+            return None;
+        };
+        if preclose <= 0 {
+            return None;
+        }
+        let pct = i64::from(pct);
+        let up = (preclose * (100 + pct) + 50) / 100;
+        let down = ((preclose * (100 - pct) + 50) / 100).max(1);
+        Some((down, up))
+    }
+
+    /// 价格区间 `[low, high]` 是否在涨跌停价以内（按分比较）
+    pub fn contains(self, preclose: f64, low: f64, high: f64) -> bool {
+        match self.limit_cents(to_cents(preclose)) {
+            Some((down, up)) => to_cents(low) >= down && to_cents(high) <= up,
+            None => true,
+        }
+    }
+}
+
+/// 沪深主板风险警示股票涨跌幅改为 10% 的首个交易日
+fn main_st_limit_change() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 7, 7).expect("有效日期")
+}
+
+/// 价格换算为分（四舍五入消除浮点误差）
+fn to_cents(price: f64) -> i64 {
+    (price * 100.0).round() as i64
+}
+
 /// 数据验证器
+///
+/// 代码格式为固定检查，其余阈值来自验证规则，见 [`crate::collector::rules`]。
 pub struct DataValidator;
 
 impl DataValidator {
@@ -33,90 +153,58 @@ impl DataValidator {
         Ok(())
     }
 
-    /// 验证价格数据
+    /// 验证价格数据（内置规则）
     pub fn validate_price(price: f64, field_name: &str) -> Result<()> {
-        if price < 0.0 {
-            return Err(AppError::Parse(format!("{}不能为负数: {}", field_name, price)));
-        }
-
-        if price > 1_000_000.0 {
-            return Err(AppError::Parse(format!("{}异常过高: {}", field_name, price)));
-        }
-
-        Ok(())
+        ValidationRules::builtin().check_price(price, field_name).map(|_| ())
     }
 
-    /// 验证日期
+    /// 验证日期（内置规则）
     pub fn validate_date(date: NaiveDate) -> Result<()> {
-        let min_date = NaiveDate::from_ymd_opt(1990, 1, 1).unwrap();
-        let max_date = Utc::now().date_naive();
-
-        if date < min_date {
-            return Err(AppError::Parse(format!("日期过早: {}", date)));
-        }
-
-        if date > max_date {
-            return Err(AppError::Parse(format!("日期不能是未来: {}", date)));
-        }
-
-        Ok(())
+        ValidationRules::builtin().check_date(date).map(|_| ())
     }
 
-    /// 验证 K 线数据
+    /// 验证 K 线数据（只做结构检查，涨跌幅检查见 [`Self::validate_kline_with_limit`]）
     pub fn validate_kline(kline: &KLine) -> Result<QualityScore> {
-        // 基础验证
         Self::validate_code(&kline.code)?;
-        Self::validate_price(kline.open, "开盘价")?;
-        Self::validate_price(kline.high, "最高价")?;
-        Self::validate_price(kline.low, "最低价")?;
-        Self::validate_price(kline.close, "收盘价")?;
-
-        // 逻辑验证: high >= low
-        if kline.high < kline.low {
-            return Err(AppError::Parse(format!(
-                "最高价不能低于最低价: high={}, low={}",
-                kline.high, kline.low
-            )));
-        }
-
-        // 逻辑验证: close 在 [low, high] 范围内
-        if kline.close < kline.low || kline.close > kline.high {
-            return Err(AppError::Parse(format!(
-                "收盘价超出范围: close={}, low={}, high={}",
-                kline.close, kline.low, kline.high
-            )));
-        }
+        ValidationRules::builtin().check_kline(kline, None, PriceLimit::Unlimited)
+    }
 
-        // 异常检测: 涨跌停
-        let change_pct = if kline.low > 0.0 {
-            (kline.close - kline.low) / kline.low * 100.0
-        } else {
-            0.0
-        };
+    /// 验证 K 线数据并检查涨跌幅
+    ///
+    /// 涨跌停价以内（含涨跌停）为良好，最高价或最低价超出涨跌停价为可疑。
+    pub fn validate_kline_with_limit(kline: &KLine, preclose: f64, limit: PriceLimit) -> Result<QualityScore> {
+        Self::validate_code(&kline.code)?;
+        ValidationRules::builtin().check_kline(kline, Some(preclose), limit)
+    }
 
-        if change_pct.abs() > 20.0 {
-            // 科创板、创业板涨跌幅20%
-            return Ok(QualityScore::Suspect);
-        }
+    /// 验证实时行情快照（涨跌幅按代码所属板块）
+    pub fn validate_quote(quote: &Quote) -> Result<QualityScore> {
+        Self::validate_quote_with_limit(quote, PriceLimit::for_code(&quote.code))
+    }
 
-        if change_pct.abs() > 10.0 {
-            // 主板涨跌幅10%，可能是涨跌停
-            return Ok(QualityScore::Suspect);
-        }
+    /// 验证实时行情快照并按给定涨跌幅检查（内置规则，可配置的规则见 [`ValidationRules`]）
+    pub fn validate_quote_with_limit(quote: &Quote, limit: PriceLimit) -> Result<QualityScore> {
+        Self::validate_quote_with_rules(quote, limit, ValidationRules::builtin())
+    }
 
-        Ok(QualityScore::Good)
+    /// 按给定规则验证实时行情快照
+    pub fn validate_quote_with_rules(quote: &Quote, limit: PriceLimit, rules: &ValidationRules) -> Result<QualityScore> {
+        Self::validate_code(&quote.code)?;
+        rules.check_quote(quote, limit)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
 
     #[test]
     fn test_validate_code_valid() {
         assert!(DataValidator::validate_code("000001").is_ok());
         assert!(DataValidator::validate_code("600036").is_ok());
         assert!(DataValidator::validate_code("300001").is_ok());
+        assert!(DataValidator::validate_code("920118").is_ok()); // 北交所新代码段
     }
 
     #[test]
@@ -161,20 +249,124 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_kline_limit_up() {
-        let kline = KLine {
-            datetime: Utc::now(),
+    fn test_validate_quote() {
+        let mut quote = Quote {
             code: "000001".to_string(),
-            open: 10.0,
-            high: 11.01,  // 涨停 > 10%
-            low: 10.0,
-            close: 11.01,
+            name: "平安银行".to_string(),
+            price: 10.5,
+            preclose: 10.0,
+            open: 10.2,
+            high: 10.6,
+            low: 10.1,
+            volume: 100000.0,
+            amount: 105_000_000.0,
+            bid: [0.0; 5],
+            bid_vol: [0.0; 5],
+            ask: [0.0; 5],
+            ask_vol: [0.0; 5],
+            timestamp: Utc::now(),
+        };
+        assert_eq!(DataValidator::validate_quote(&quote).unwrap(), QualityScore::Good);
+
+        quote.price = 10.7;
+        assert!(DataValidator::validate_quote(&quote).is_err());
+
+        // 未成交的快照不检查价格区间
+        quote.volume = 0.0;
+        quote.amount = 0.0;
+        assert_eq!(DataValidator::validate_quote(&quote).unwrap(), QualityScore::Good);
+    }
+
+    fn bar(code: &str, low: f64, high: f64, close: f64) -> KLine {
+        KLine {
+            datetime: Utc::now(),
+            code: code.to_string(),
+            open: low,
+            high,
+            low,
+            close,
             volume: 1000000.0,
             amount: 11000000.0,
+        }
+    }
+
+    #[test]
+    fn test_validate_kline_limit_up() {
+        let main = PriceLimit::for_code("000001");
+        assert_eq!(main, PriceLimit::Percent(10));
+
+        // 昨收 10.01，涨停价 11.011 四舍五入为 11.01
+        let kline = bar("000001", 10.0, 11.01, 11.01);
+        assert_eq!(DataValidator::validate_kline_with_limit(&kline, 10.01, main).unwrap(), QualityScore::Good);
+
+        // 日内振幅超过 10% 但未越过涨跌停价
+        let kline = bar("000001", 9.2, 10.4, 10.3);
+        assert_eq!(DataValidator::validate_kline_with_limit(&kline, 10.0, main).unwrap(), QualityScore::Good);
+
+        // 超过涨停价一分
+        let kline = bar("000001", 10.0, 11.02, 11.02);
+        assert_eq!(DataValidator::validate_kline_with_limit(&kline, 10.01, main).unwrap(), QualityScore::Suspect);
+
+        assert_eq!(PriceLimit::for_code("920118"), PriceLimit::Percent(30));
+
+        // 创业板 20% 涨停
+        let kline = bar("300750", 200.0, 240.0, 240.0);
+        assert_eq!(
+            DataValidator::validate_kline_with_limit(&kline, 200.0, PriceLimit::for_code("300750")).unwrap(),
+            QualityScore::Good
+        );
+    }
+
+    #[test]
+    fn test_price_limits_by_board() {
+        let calendar = TradingCalendar::bundled();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut security = Security {
+            code: "000004".to_string(),
+            name: "*ST国华".to_string(),
+            market: Market::SZ,
+            security_type: crate::models::stock::SecurityType::Stock,
+            board: Some(Board::Main),
+            st: StStatus::StarSt,
+            list_date: Some(date(1991, 1, 14)),
+            status: crate::models::stock::ListingStatus::Listed,
+            total_shares: None,
+            float_shares: None,
+            updated_at: Utc::now(),
         };
+        assert_eq!(PriceLimit::for_security(&security, date(2024, 6, 7), &calendar), PriceLimit::Percent(5));
 
-        let result = DataValidator::validate_kline(&kline);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), QualityScore::Suspect); // 涨停标记为可疑
+        // 2025-07-07 起主板 ST、*ST 为 10%
+        assert_eq!(PriceLimit::for_security(&security, date(2025, 7, 4), &calendar), PriceLimit::Percent(5));
+        assert_eq!(PriceLimit::for_security(&security, date(2025, 7, 7), &calendar), PriceLimit::Percent(10));
+        let kline = bar("000004", 8.0, 9.46, 9.46);
+        let limit = PriceLimit::for_security(&security, date(2025, 7, 7), &calendar);
+        assert_eq!(DataValidator::validate_kline_with_limit(&kline, 8.6, limit).unwrap(), QualityScore::Good);
+        let limit = PriceLimit::for_security(&security, date(2025, 7, 4), &calendar);
+        assert_eq!(DataValidator::validate_kline_with_limit(&kline, 8.6, limit).unwrap(), QualityScore::Suspect);
+
+        // 四舍五入到分：3.33 × 1.05 = 3.4965 → 3.50，3.33 × 0.95 = 3.1635 → 3.16
+        assert_eq!(PriceLimit::Percent(5).limit_prices(3.33), Some((3.16, 3.5)));
+        assert_eq!(PriceLimit::Percent(30).limit_prices(12.34), Some((8.64, 16.04)));
+        assert_eq!(PriceLimit::Unlimited.limit_prices(12.34), None);
+
+        // 创业板 ST 仍为 20%
+        security.board = Some(Board::ChiNext);
+        assert_eq!(PriceLimit::for_security(&security, date(2024, 6, 7), &calendar), PriceLimit::Percent(20));
+
+        // 新股：前 5 个交易日不设涨跌幅，北交所仅首日
+        security.st = StStatus::Normal;
+        security.list_date = Some(date(2024, 6, 3));
+        assert_eq!(PriceLimit::for_security(&security, date(2024, 6, 7), &calendar), PriceLimit::Unlimited);
+        assert_eq!(PriceLimit::for_security(&security, date(2024, 6, 11), &calendar), PriceLimit::Percent(20));
+        security.board = Some(Board::Bse);
+        assert_eq!(PriceLimit::for_security(&security, date(2024, 6, 3), &calendar), PriceLimit::Unlimited);
+        assert_eq!(PriceLimit::for_security(&security, date(2024, 6, 4), &calendar), PriceLimit::Percent(30));
+
+        let kline = bar("300750", 100.0, 180.0, 170.0);
+        assert_eq!(
+            DataValidator::validate_kline_with_limit(&kline, 50.0, PriceLimit::Unlimited).unwrap(),
+            QualityScore::Good
+        );
     }
 }
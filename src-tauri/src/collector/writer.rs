@@ -1,33 +1,72 @@
-use crate::models::quote::KLine;
 use crate::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::time::timeout;
 
+/// 批量写入目标
+#[async_trait]
+pub trait BatchSink<T>: Send + Sync {
+    /// 写入一批数据
+    async fn write_batch(&self, batch: &[T]) -> Result<()>;
+}
+
+/// 只记录日志的写入目标（未配置数据库时使用）
+pub struct LogSink;
+
+#[async_trait]
+impl<T: Send + Sync> BatchSink<T> for LogSink {
+    async fn write_batch(&self, batch: &[T]) -> Result<()> {
+        tracing::debug!("丢弃 {} 条记录（未配置写入目标）", batch.len());
+        Ok(())
+    }
+}
+
 /// 批量写入器
-pub struct BatchWriter {
+pub struct BatchWriter<T> {
     batch_size: usize,
     batch_timeout: Duration,
+    sink: Arc<dyn BatchSink<T>>,
 }
 
-impl BatchWriter {
+impl<T: Send + Sync + 'static> BatchWriter<T> {
     /// 创建新的批量写入器
     pub fn new() -> Self {
         Self {
             batch_size: 100,
             batch_timeout: Duration::from_secs(5),
+            sink: Arc::new(LogSink),
         }
     }
 
-    /// 启动批量写入任务
-    pub async fn start(&self, mut receiver: mpsc::Receiver<KLine>) -> Result<()> {
+    /// 设置写入目标
+    pub fn with_sink(mut self, sink: Arc<dyn BatchSink<T>>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    /// 设置批量大小
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// 设置未满一批时的最长等待时间
+    pub fn with_batch_timeout(mut self, batch_timeout: Duration) -> Self {
+        self.batch_timeout = batch_timeout;
+        self
+    }
+
+    /// 启动批量写入任务，通道关闭后写完剩余数据返回
+    pub async fn start(&self, mut receiver: mpsc::Receiver<T>) -> Result<()> {
         let mut batch = Vec::with_capacity(self.batch_size);
 
         loop {
             // 等待数据或超时
             match timeout(self.batch_timeout, receiver.recv()).await {
-                Ok(Some(kline)) => {
-                    batch.push(kline);
+                Ok(Some(item)) => {
+                    batch.push(item);
 
                     // 达到批量大小，写入
                     if batch.len() >= self.batch_size {
@@ -55,35 +94,64 @@ impl BatchWriter {
         Ok(())
     }
 
-    /// 写入一批数据到 ClickHouse
-    async fn write_batch(&self, batch: &[KLine]) -> Result<()> {
+    /// 写入一批数据
+    async fn write_batch(&self, batch: &[T]) -> Result<()> {
         if batch.is_empty() {
             return Ok(());
         }
 
-        tracing::debug!("批量写入 {} 条记录到 ClickHouse", batch.len());
-
-        // TODO: 实际写入 ClickHouse
-        // 这里暂时只是日志
-        for kline in batch {
-            tracing::trace!("写入: {} {}", kline.code, kline.datetime);
-        }
+        tracing::debug!("批量写入 {} 条记录", batch.len());
+        self.sink.write_batch(batch).await
+    }
+}
 
-        // 模拟写入成功
-        Ok(())
+impl<T: Send + Sync + 'static> Default for BatchWriter<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::quote::KLine;
+    use std::sync::Mutex;
+
+    /// 记录每批大小的写入目标
+    #[derive(Default)]
+    struct MemorySink {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl BatchSink<u32> for MemorySink {
+        async fn write_batch(&self, batch: &[u32]) -> Result<()> {
+            self.batches.lock().unwrap().push(batch.len());
+            Ok(())
+        }
+    }
 
     #[tokio::test]
     async fn test_batch_writer_creation() {
         // 测试写入器创建（需要 ClickHouse 连接）
         // 这里暂时跳过实际连接测试
-        let writer = BatchWriter::new();
+        let writer = BatchWriter::<KLine>::new();
         assert_eq!(writer.batch_size, 100);
         assert_eq!(writer.batch_timeout, Duration::from_secs(5));
     }
+
+    #[tokio::test]
+    async fn test_flush_remaining_on_close() {
+        let sink = Arc::new(MemorySink::default());
+        let writer = BatchWriter::new().with_sink(sink.clone()).with_batch_size(4);
+        let (sender, receiver) = mpsc::channel(16);
+
+        for i in 0..10 {
+            sender.send(i).await.unwrap();
+        }
+        drop(sender);
+        writer.start(receiver).await.unwrap();
+
+        assert_eq!(*sink.batches.lock().unwrap(), vec![4, 4, 2]);
+    }
 }
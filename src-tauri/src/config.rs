@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -7,6 +8,10 @@ pub struct Config {
     pub database: DatabaseConfig,
     pub api: ApiConfig,
     pub data_source: DataSourceConfig,
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    #[serde(default)]
+    pub reconcile: ReconcileConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +30,153 @@ pub struct ApiConfig {
 pub struct DataSourceConfig {
     pub tdx_servers: Vec<String>,
     pub update_interval_secs: u64,
+    #[serde(default)]
+    pub csv_dir: Option<PathBuf>,  // 本地 CSV 样本目录，配置后离线运行
+    #[serde(default)]
+    pub calendar_path: Option<PathBuf>,  // 外部交易日历文件，缺省使用内置日历
+}
+
+/// 数据验证规则（`[validation]`），缺省值即内置规则
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationConfig {
+    pub price: PriceRangeRule,
+    pub date: DateRangeRule,
+    pub ohlc: OhlcRule,
+    pub price_limit: PriceLimitRule,
+    pub rules: Vec<ExpressionRule>,
+}
+
+/// 规则不通过时的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    Suspect,  // 标记为可疑，照常写入
+    Error,    // 验证失败，丢弃
+}
+
+/// 价格区间（各价格字段）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceRangeRule {
+    pub enabled: bool,
+    pub min: f64,
+    pub max: f64,
+    pub severity: RuleSeverity,
+}
+
+/// 日期区间（K 线日期）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DateRangeRule {
+    pub enabled: bool,
+    pub min: NaiveDate,
+    pub allow_future: bool,
+    pub severity: RuleSeverity,
+}
+
+/// 价格关系：最高价不低于最低价，收盘价（现价）在最高最低之间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OhlcRule {
+    pub enabled: bool,
+    pub severity: RuleSeverity,
+}
+
+/// 涨跌停：最高价、最低价不超出板块涨跌停价
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriceLimitRule {
+    pub enabled: bool,
+    pub severity: RuleSeverity,
+}
+
+/// 自定义表达式规则：表达式为真时通过
+///
+/// K 线可用 `open high low close volume amount preclose`，
+/// 行情快照可用 `price preclose open high low volume amount`；
+/// 支持四则运算、比较、`&& || !` 与 `abs min max`。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpressionRule {
+    pub name: String,
+    pub target: RuleTarget,
+    pub expr: String,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    pub severity: RuleSeverity,
+}
+
+/// 表达式规则的检查对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleTarget {
+    Kline,
+    Quote,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// 多数据源对账（`[reconcile]`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconcileConfig {
+    pub precedence: Vec<String>,  // 数据源名称，靠前的优先；未列出的排在最后
+    pub price_tolerance: f64,     // 价格允许的差异（元）
+    pub volume_tolerance: f64,    // 成交量、成交额允许的相对差异
+    pub lookback_days: u32,       // 日终任务对账最近的交易日数
+}
+
+impl Default for ReconcileConfig {
+    fn default() -> Self {
+        Self {
+            precedence: Vec::new(),
+            price_tolerance: 0.01,
+            volume_tolerance: 0.001,
+            lookback_days: 5,
+        }
+    }
+}
+
+impl Default for PriceRangeRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min: 0.0,
+            max: 1_000_000.0,
+            severity: RuleSeverity::Error,
+        }
+    }
+}
+
+impl Default for OhlcRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            severity: RuleSeverity::Error,
+        }
+    }
+}
+
+impl Default for PriceLimitRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            severity: RuleSeverity::Suspect,
+        }
+    }
+}
+
+impl Default for DateRangeRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min: NaiveDate::from_ymd_opt(1990, 1, 1).unwrap(),
+            allow_future: false,
+            severity: RuleSeverity::Error,
+        }
+    }
 }
 
 impl Default for Config {
@@ -38,13 +190,23 @@ impl Default for Config {
                 host: "127.0.0.1".to_string(),
                 port: 8000,
             },
-            data_source: DataSourceConfig {
-                tdx_servers: vec![
-                    "124.71.187.122:7709".to_string(),
-                    "122.51.120.217:7709".to_string(),
-                ],
-                update_interval_secs: 3,
-            },
+            data_source: DataSourceConfig::default(),
+            validation: ValidationConfig::default(),
+            reconcile: ReconcileConfig::default(),
+        }
+    }
+}
+
+impl Default for DataSourceConfig {
+    fn default() -> Self {
+        Self {
+            tdx_servers: vec![
+                "124.71.187.122:7709".to_string(),
+                "122.51.120.217:7709".to_string(),
+            ],
+            update_interval_secs: 3,
+            csv_dir: None,
+            calendar_path: None,
         }
     }
 }
@@ -1,6 +1,18 @@
-use clickhouse_rs::Pool;
+use crate::collector::factor::{FactorData, FactorSource};
+use crate::collector::validator::{DataValidator, QualityScore};
+use crate::collector::writer::BatchSink;
 use crate::config::DatabaseConfig;
-use crate::error::Result;
+use crate::error::{AppError, Result};
+use crate::models::quality::{DailyQuality, IssueType, QualityIssue, Severity};
+use crate::models::quote::Quote;
+use crate::service::{FactorStore, QualityStore};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Asia::Shanghai;
+use chrono_tz::Tz;
+use clickhouse_rs::types::Enum8;
+use clickhouse_rs::{Block, Pool};
+use std::collections::BTreeSet;
 
 /// ClickHouse 客户端池
 pub struct Client {
@@ -24,4 +36,209 @@ impl Client {
     pub fn pool(&self) -> &Pool {
         &self.pool
     }
-}
\ No newline at end of file
+}
+
+/// 实时行情写入 `quote_realtime` 表
+#[async_trait]
+impl BatchSink<Quote> for Client {
+    async fn write_batch(&self, batch: &[Quote]) -> Result<()> {
+        let block = Block::new()
+            .column("datetime", batch.iter().map(|q| q.timestamp.with_timezone(&Shanghai)).collect::<Vec<_>>())
+            .column("code", batch.iter().map(|q| q.code.clone()).collect::<Vec<_>>())
+            .column("price", batch.iter().map(|q| q.price).collect::<Vec<_>>())
+            .column("volume", batch.iter().map(|q| q.volume).collect::<Vec<_>>())
+            .column("amount", batch.iter().map(|q| q.amount).collect::<Vec<_>>())
+            .column("bids", batch.iter().map(|q| q.bid.to_vec()).collect::<Vec<_>>())
+            .column("asks", batch.iter().map(|q| q.ask.to_vec()).collect::<Vec<_>>());
+
+        let mut handle = self.pool.get_handle().await.map_err(ch_error)?;
+        handle
+            .insert("kaipanla.quote_realtime", block)
+            .await
+            .map_err(ch_error)
+    }
+}
+
+/// 对账后的日线写入 `factor` 表
+///
+/// 同一交易日的新版本直接追加，由 `ReplacingMergeTree(data_version)` 合并（见 migrations/005）。
+#[async_trait]
+impl BatchSink<FactorData> for Client {
+    async fn write_batch(&self, batch: &[FactorData]) -> Result<()> {
+        let block = Block::new()
+            .column("date", batch.iter().map(|f| f.date).collect::<Vec<_>>())
+            .column("code", batch.iter().map(|f| f.code.clone()).collect::<Vec<_>>())
+            .column("open", batch.iter().map(|f| f.open).collect::<Vec<_>>())
+            .column("high", batch.iter().map(|f| f.high).collect::<Vec<_>>())
+            .column("low", batch.iter().map(|f| f.low).collect::<Vec<_>>())
+            .column("close", batch.iter().map(|f| f.close).collect::<Vec<_>>())
+            .column("preclose", batch.iter().map(|f| f.preclose).collect::<Vec<_>>())
+            .column("factor", batch.iter().map(|f| f.factor).collect::<Vec<_>>())
+            .column("volume", batch.iter().map(|f| f.volume).collect::<Vec<_>>())
+            .column("amount", batch.iter().map(|f| f.amount).collect::<Vec<_>>())
+            .column("data_version", batch.iter().map(|f| f.data_version).collect::<Vec<_>>())
+            .column("data_source", batch.iter().map(|f| Enum8::of(f.data_source as i8)).collect::<Vec<_>>())
+            .column("quality_score", batch.iter().map(|f| Enum8::of(f.quality_score as i8)).collect::<Vec<_>>());
+
+        let mut handle = self.pool.get_handle().await.map_err(ch_error)?;
+        handle
+            .insert("kaipanla.factor", block)
+            .await
+            .map_err(ch_error)
+    }
+}
+
+/// 数据质量问题写入 `data_quality_log` 表
+#[async_trait]
+impl BatchSink<QualityIssue> for Client {
+    async fn write_batch(&self, batch: &[QualityIssue]) -> Result<()> {
+        let block = Block::new()
+            .column("log_time", batch.iter().map(|f| f.log_time.with_timezone(&Shanghai)).collect::<Vec<_>>())
+            .column("date", batch.iter().map(|f| f.date).collect::<Vec<_>>())
+            .column("code", batch.iter().map(|f| f.code.clone()).collect::<Vec<_>>())
+            .column("issue_type", batch.iter().map(|f| Enum8::of(f.issue_type as i8)).collect::<Vec<_>>())
+            .column("description", batch.iter().map(|f| f.description.clone()).collect::<Vec<_>>())
+            .column("severity", batch.iter().map(|f| Enum8::of(f.severity as i8)).collect::<Vec<_>>());
+
+        let mut handle = self.pool.get_handle().await.map_err(ch_error)?;
+        handle
+            .insert("kaipanla.data_quality_log", block)
+            .await
+            .map_err(ch_error)
+    }
+}
+
+/// 质量报告查询：`factor` 与 `data_quality_log`
+///
+/// `factor` 为 `ReplacingMergeTree(data_version)`，统计时加 `FINAL` 只计每个交易日的最新版本。
+#[async_trait]
+impl QualityStore for Client {
+    async fn daily_quality(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<DailyQuality>> {
+        let sql = format!(
+            "SELECT date, toInt8(quality_score) AS score, count() AS records
+             FROM kaipanla.factor FINAL
+             WHERE date BETWEEN {}
+             GROUP BY date, score
+             ORDER BY date",
+            date_range(start, end)
+        );
+        let mut handle = self.pool.get_handle().await.map_err(ch_error)?;
+        let block = handle.query(sql).fetch_all().await.map_err(ch_error)?;
+
+        let mut daily: Vec<DailyQuality> = Vec::new();
+        for row in block.rows() {
+            let date: NaiveDate = row.get("date").map_err(ch_error)?;
+            let score: i8 = row.get("score").map_err(ch_error)?;
+            let records: u64 = row.get("records").map_err(ch_error)?;
+
+            if daily.last().is_none_or(|d| d.date != date) {
+                daily.push(DailyQuality { date, ..DailyQuality::default() });
+            }
+            if let Some(day) = daily.last_mut() {
+                match score {
+                    1 => day.good += records,
+                    2 => day.suspect += records,
+                    _ => day.error += records,
+                }
+            }
+        }
+        Ok(daily)
+    }
+
+    async fn quality_issues(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<QualityIssue>> {
+        let sql = format!(
+            "SELECT log_time, date, toString(code) AS code, toInt8(issue_type) AS issue_type,
+                    description, toInt8(severity) AS severity
+             FROM kaipanla.data_quality_log
+             WHERE date BETWEEN {}
+             ORDER BY date, code, log_time",
+            date_range(start, end)
+        );
+        let mut handle = self.pool.get_handle().await.map_err(ch_error)?;
+        let block = handle.query(sql).fetch_all().await.map_err(ch_error)?;
+
+        let mut issues = Vec::with_capacity(block.row_count());
+        for row in block.rows() {
+            let log_time: DateTime<Tz> = row.get("log_time").map_err(ch_error)?;
+            let issue_type: i8 = row.get("issue_type").map_err(ch_error)?;
+            let severity: i8 = row.get("severity").map_err(ch_error)?;
+            let (Some(issue_type), Some(severity)) = (IssueType::from_value(issue_type), Severity::from_value(severity))
+            else {
+                continue;
+            };
+            issues.push(QualityIssue {
+                log_time: log_time.with_timezone(&Utc),
+                date: row.get("date").map_err(ch_error)?,
+                code: row.get("code").map_err(ch_error)?,
+                issue_type,
+                description: row.get("description").map_err(ch_error)?,
+                severity,
+            });
+        }
+        Ok(issues)
+    }
+
+    async fn collected_codes(&self, start: NaiveDate, end: NaiveDate) -> Result<BTreeSet<String>> {
+        let sql = format!(
+            "SELECT DISTINCT toString(code) AS code FROM kaipanla.factor WHERE date BETWEEN {}",
+            date_range(start, end)
+        );
+        let mut handle = self.pool.get_handle().await.map_err(ch_error)?;
+        let block = handle.query(sql).fetch_all().await.map_err(ch_error)?;
+
+        block
+            .rows()
+            .map(|row| row.get::<String, _>("code").map_err(ch_error))
+            .collect()
+    }
+}
+
+/// 复权因子读取 `factor` 表（`FINAL` 去掉对账前的旧版本）
+#[async_trait]
+impl FactorStore for Client {
+    async fn factors(&self, code: &str) -> Result<Vec<FactorData>> {
+        // 代码来自 REST 路径，拼入 SQL 前确认为 6 位数字
+        DataValidator::validate_code(code)?;
+        let sql = format!(
+            "SELECT date, toString(code) AS code, open, high, low, close, preclose, factor, volume, amount,
+                    data_version, toInt8(data_source) AS data_source, toInt8(quality_score) AS quality_score
+             FROM kaipanla.factor FINAL
+             WHERE code = '{}'
+             ORDER BY date",
+            code
+        );
+        let mut handle = self.pool.get_handle().await.map_err(ch_error)?;
+        let block = handle.query(sql).fetch_all().await.map_err(ch_error)?;
+
+        let mut rows = Vec::with_capacity(block.row_count());
+        for row in block.rows() {
+            let data_source: i8 = row.get("data_source").map_err(ch_error)?;
+            let quality_score: i8 = row.get("quality_score").map_err(ch_error)?;
+            rows.push(FactorData {
+                date: row.get("date").map_err(ch_error)?,
+                code: row.get("code").map_err(ch_error)?,
+                open: row.get("open").map_err(ch_error)?,
+                high: row.get("high").map_err(ch_error)?,
+                low: row.get("low").map_err(ch_error)?,
+                close: row.get("close").map_err(ch_error)?,
+                preclose: row.get("preclose").map_err(ch_error)?,
+                factor: row.get("factor").map_err(ch_error)?,
+                volume: row.get("volume").map_err(ch_error)?,
+                amount: row.get("amount").map_err(ch_error)?,
+                data_source: FactorSource::from_value(data_source).unwrap_or_default(),
+                data_version: row.get("data_version").map_err(ch_error)?,
+                quality_score: QualityScore::from_value(quality_score).unwrap_or_default(),
+            });
+        }
+        Ok(rows)
+    }
+}
+
+/// `BETWEEN` 的日期区间（由 `NaiveDate` 按 `%Y-%m-%d` 格式化，不含用户输入的字符）
+fn date_range(start: NaiveDate, end: NaiveDate) -> String {
+    format!("'{}' AND '{}'", start.format("%Y-%m-%d"), end.format("%Y-%m-%d"))
+}
+
+fn ch_error(e: clickhouse_rs::errors::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
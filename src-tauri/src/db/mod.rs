@@ -1,3 +1,5 @@
 pub mod clickhouse;
+pub mod sqlite;
 
-pub use clickhouse::Client;
\ No newline at end of file
+pub use clickhouse::Client;
+pub use sqlite::LocalDb;
@@ -0,0 +1,155 @@
+//! 本地 SQLite 缓存 - 证券主数据等不依赖 ClickHouse 的本地状态
+
+use crate::error::{AppError, Result};
+use rusqlite::Connection;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// 表结构迁移，按顺序执行，已执行的版本号记录在 `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    // 1: 证券主数据
+    "CREATE TABLE securities (
+        market        TEXT NOT NULL,
+        code          TEXT NOT NULL,
+        name          TEXT NOT NULL,
+        security_type TEXT NOT NULL,
+        board         TEXT,
+        st            TEXT NOT NULL,
+        list_date     TEXT,
+        status        TEXT NOT NULL,
+        total_shares  REAL,
+        float_shares  REAL,
+        updated_at    TEXT NOT NULL,
+        PRIMARY KEY (market, code)
+    );",
+    // 2: 日终任务运行历史
+    "CREATE TABLE job_runs (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        job         TEXT NOT NULL,
+        trade_date  TEXT NOT NULL,
+        status      TEXT NOT NULL,
+        started_at  TEXT NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        error       TEXT
+    );
+    CREATE INDEX idx_job_runs_date ON job_runs (trade_date, job);",
+    // 3: 历史导入进度（与 ClickHouse import_progress 同构，单行）
+    "CREATE TABLE import_progress (
+        id               INTEGER PRIMARY KEY CHECK (id = 0),
+        stage            TEXT NOT NULL,
+        total_stocks     INTEGER NOT NULL,
+        imported_stocks  INTEGER NOT NULL,
+        total_batches    INTEGER NOT NULL,
+        imported_batches INTEGER NOT NULL,
+        current_code     TEXT NOT NULL,
+        start_date       TEXT NOT NULL,
+        end_date         TEXT NOT NULL,
+        anchor_date      TEXT NOT NULL,
+        error_count      INTEGER NOT NULL,
+        updated_at       TEXT NOT NULL
+    );",
+    // 4: K 线覆盖索引（逐交易日）
+    "CREATE TABLE kline_coverage (
+        code       TEXT NOT NULL,
+        trade_date TEXT NOT NULL,
+        has_bar    INTEGER NOT NULL,
+        PRIMARY KEY (code, trade_date)
+    );",
+    // 5: 多数据源对账结果（每个交易日的采用值与版本号）
+    "CREATE TABLE factor_versions (
+        code          TEXT NOT NULL,
+        trade_date    TEXT NOT NULL,
+        data_source   TEXT NOT NULL,
+        source_name   TEXT NOT NULL,
+        open          REAL NOT NULL,
+        high          REAL NOT NULL,
+        low           REAL NOT NULL,
+        close         REAL NOT NULL,
+        volume        REAL NOT NULL,
+        amount        REAL NOT NULL,
+        factor        REAL NOT NULL,
+        data_version  INTEGER NOT NULL,
+        quality_score TEXT NOT NULL,
+        updated_at    TEXT NOT NULL,
+        PRIMARY KEY (code, trade_date)
+    );",
+];
+
+/// 本地数据库
+pub struct LocalDb {
+    conn: Mutex<Connection>,
+}
+
+impl LocalDb {
+    /// 打开（不存在时创建）数据库文件并执行迁移
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path.as_ref()).map_err(db_error)?;
+        tracing::info!("打开本地数据库: {}", path.as_ref().display());
+        Self::init(conn)
+    }
+
+    /// 内存数据库，用于测试
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory().map_err(db_error)?)
+    }
+
+    fn init(mut conn: Connection) -> Result<Self> {
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(db_error)?;
+
+        let tx = conn.transaction().map_err(db_error)?;
+        for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+            tracing::info!("执行本地数据库迁移 {}", i + 1);
+            tx.execute_batch(sql).map_err(db_error)?;
+        }
+        tx.pragma_update(None, "user_version", MIGRATIONS.len()).map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// 在连接上执行操作
+    pub fn with_conn<T>(&self, f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|_| AppError::Internal("本地数据库锁已损坏".to_string()))?;
+        f(&mut conn).map_err(db_error)
+    }
+}
+
+fn db_error(e: rusqlite::Error) -> AppError {
+    AppError::Database(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_run_once() {
+        let path = std::env::temp_dir().join(format!("kaipanla_localdb_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let db = LocalDb::open(&path).unwrap();
+        db.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO securities VALUES ('SZ', '000001', '平安银行', 'Stock', 'main', 'normal', NULL, 'listed', NULL, NULL, '')",
+                [],
+            )
+        })
+        .unwrap();
+        drop(db);
+
+        // 重新打开不重复建表，数据保留
+        let db = LocalDb::open(&path).unwrap();
+        let count: i64 = db
+            .with_conn(|conn| conn.query_row("SELECT COUNT(*) FROM securities", [], |row| row.get(0)))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
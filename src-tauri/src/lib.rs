@@ -1,7 +1,14 @@
 //! 开盘啦 - 库入口
 
+pub mod api;
 pub mod cmd;
+pub mod collector;
 pub mod config;
+pub mod db;
 pub mod error;
+pub mod models;
+pub mod monitor;
+pub mod service;
+pub mod websocket;
 
-pub use error::Result;
+pub use error::{AppError, Result};
@@ -1,11 +1,15 @@
 //! 数据模型定义
 
+pub mod auction;
 pub mod dragon_tiger;
 pub mod money_flow;
+pub mod quality;
 pub mod quote;
 pub mod stock;
 
+pub use auction::*;
 pub use dragon_tiger::*;
 pub use money_flow::*;
+pub use quality::*;
 pub use quote::*;
 pub use stock::*;
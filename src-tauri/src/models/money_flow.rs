@@ -41,10 +41,23 @@ pub struct BigTrade {
 }
 
 /// 交易方向
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TradeDirection {
-    Buy,   // 主动买
-    Sell,  // 主动卖
+    Buy,      // 主动买
+    Sell,     // 主动卖
+    Neutral,  // 中性盘（集合竞价等无法判断方向的成交）
+}
+
+/// 逐笔成交
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub code: String,
+    pub datetime: DateTime<Utc>,
+    pub price: f64,
+    pub volume: f64,      // 成交量 (手)
+    pub amount: f64,      // 成交额 (元)
+    pub num: u32,         // 成交笔数（历史数据无此字段，为 0）
+    pub direction: TradeDirection,
 }
 
 impl BigTrade {
@@ -54,6 +67,19 @@ impl BigTrade {
     }
 }
 
+impl From<Transaction> for BigTrade {
+    fn from(t: Transaction) -> Self {
+        Self {
+            code: t.code,
+            datetime: t.datetime,
+            price: t.price,
+            volume: t.volume,
+            amount: t.amount,
+            direction: t.direction,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
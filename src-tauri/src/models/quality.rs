@@ -0,0 +1,226 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 数据质量问题类型（与 `data_quality_log.issue_type` 一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueType {
+    Duplicate = 1,  // 重复
+    Gap = 2,        // 序列中间缺少交易日
+    Abnormal = 3,   // 异常（停牌、数据未更新、无法解释的跳空）
+    Missing = 4,    // 遗漏
+}
+
+impl IssueType {
+    /// 由 ClickHouse 枚举值转换
+    pub fn from_value(value: i8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Duplicate),
+            2 => Some(Self::Gap),
+            3 => Some(Self::Abnormal),
+            4 => Some(Self::Missing),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Duplicate => "duplicate",
+            Self::Gap => "gap",
+            Self::Abnormal => "abnormal",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+/// 严重程度（与 `data_quality_log.severity` 一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info = 1,     // 信息
+    Warning = 2,  // 警告
+    Error = 3,    // 错误
+}
+
+impl Severity {
+    /// 由 ClickHouse 枚举值转换
+    pub fn from_value(value: i8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Info),
+            2 => Some(Self::Warning),
+            3 => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// 一条数据质量问题（`data_quality_log` 表的一行）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityIssue {
+    pub log_time: DateTime<Utc>,
+    pub date: NaiveDate,          // 数据日期
+    pub code: String,
+    pub issue_type: IssueType,
+    pub description: String,
+    pub severity: Severity,
+}
+
+/// 单日各质量评分的记录数（`factor` 表每个交易日的最新版本）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DailyQuality {
+    pub date: NaiveDate,
+    pub good: u64,
+    pub suspect: u64,
+    pub error: u64,
+}
+
+impl DailyQuality {
+    pub fn total(&self) -> u64 {
+        self.good + self.suspect + self.error
+    }
+}
+
+/// 采集覆盖率：应采集的股票与实际有数据的股票
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Coverage {
+    pub expected: usize,
+    pub collected: usize,
+    pub ratio: Option<f64>,      // collected / expected，无主数据时为空
+    pub missing: Vec<String>,    // 应采集但没有数据的股票
+}
+
+/// 数据质量报告
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataQualityReport {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub total_records: u64,
+    pub good_quality: u64,
+    pub suspect: u64,
+    pub error: u64,
+    pub daily: Vec<DailyQuality>,
+    pub coverage: Coverage,
+    pub issues: Vec<QualityIssue>,
+}
+
+/// 报告下载格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn parse(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(AppError::Parse(format!("未知的报告格式: {}", s))),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+impl DataQualityReport {
+    /// 下载文件名
+    pub fn file_name(&self, format: ReportFormat) -> String {
+        format!("data_quality_{}_{}.{}", self.start, self.end, format.extension())
+    }
+
+    /// 按格式导出
+    pub fn export(&self, format: ReportFormat) -> Result<String> {
+        match format {
+            ReportFormat::Json => {
+                serde_json::to_string_pretty(self).map_err(|e| AppError::Internal(e.to_string()))
+            }
+            ReportFormat::Csv => self.to_csv(),
+        }
+    }
+
+    /// 导出为 CSV
+    ///
+    /// 汇总、每日统计、缺失股票与问题明细放在同一张表中，以 `section` 列区分：
+    ///
+    /// | section | date | code | item | value | description |
+    /// |---------|------|------|------|-------|-------------|
+    /// | summary | | | total_records / good / suspect / error / expected / collected | 数量 | |
+    /// | daily | 日期 | | good / suspect / error | 数量 | |
+    /// | missing | | 代码 | | | |
+    /// | issue | 日期 | 代码 | 问题类型 | 严重程度 | 描述 |
+    pub fn to_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let csv_error = |e: csv::Error| AppError::Internal(e.to_string());
+
+        writer
+            .write_record(["section", "date", "code", "item", "value", "description"])
+            .map_err(csv_error)?;
+
+        let summary = [
+            ("total_records", self.total_records),
+            ("good", self.good_quality),
+            ("suspect", self.suspect),
+            ("error", self.error),
+            ("expected", self.coverage.expected as u64),
+            ("collected", self.coverage.collected as u64),
+        ];
+        for (item, value) in summary {
+            writer
+                .write_record(["summary", "", "", item, &value.to_string(), ""])
+                .map_err(csv_error)?;
+        }
+
+        for day in &self.daily {
+            let date = day.date.to_string();
+            for (item, value) in [("good", day.good), ("suspect", day.suspect), ("error", day.error)] {
+                writer
+                    .write_record(["daily", &date, "", item, &value.to_string(), ""])
+                    .map_err(csv_error)?;
+            }
+        }
+
+        for code in &self.coverage.missing {
+            writer
+                .write_record(["missing", "", code, "", "", ""])
+                .map_err(csv_error)?;
+        }
+
+        for issue in &self.issues {
+            writer
+                .write_record([
+                    "issue",
+                    &issue.date.to_string(),
+                    &issue.code,
+                    issue.issue_type.as_str(),
+                    issue.severity.as_str(),
+                    &issue.description,
+                ])
+                .map_err(csv_error)?;
+        }
+
+        let bytes = writer.into_inner().map_err(|e| AppError::Internal(e.to_string()))?;
+        String::from_utf8(bytes).map_err(|e| AppError::Internal(e.to_string()))
+    }
+}
@@ -50,7 +50,7 @@ pub struct KLine {
 }
 
 /// K线周期
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum KLinePeriod {
     Minute1,   // 1分钟
     Minute5,   // 5分钟
@@ -59,6 +59,16 @@ pub enum KLinePeriod {
     Month,     // 月线
 }
 
+/// 复权方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdjustMode {
+    #[default]
+    None,      // 不复权
+    Forward,   // 前复权
+    Backward,  // 后复权
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
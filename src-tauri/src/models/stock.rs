@@ -1,3 +1,4 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 /// 股票基本信息
@@ -9,7 +10,7 @@ pub struct Stock {
 }
 
 /// 市场类型
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Market {
     SZ,  // 深交所
     SH,  // 上交所
@@ -17,6 +18,15 @@ pub enum Market {
 }
 
 impl Market {
+    /// 存储名称（与 serde 一致）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Market::SZ => "SZ",
+            Market::SH => "SH",
+            Market::BJ => "BJ",
+        }
+    }
+
     /// 从股票代码判断市场
     pub fn from_code(code: &str) -> Option<Self> {
         if code.len() != 6 {
@@ -27,12 +37,211 @@ impl Market {
         match first {
             "00" | "30" => Some(Market::SZ),
             "60" | "68" => Some(Market::SH),
-            "43" | "83" | "87" => Some(Market::BJ),
+            "43" | "83" | "87" | "92" => Some(Market::BJ),
             _ => None,
         }
     }
 }
 
+/// 证券类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityType {
+    Stock,  // 股票
+    Index,  // 指数
+    Etf,    // 基金（ETF/LOF）
+    Bond,   // 债券（含可转债）
+}
+
+impl SecurityType {
+    /// 存储名称（与 serde 一致）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecurityType::Stock => "Stock",
+            SecurityType::Index => "Index",
+            SecurityType::Etf => "Etf",
+            SecurityType::Bond => "Bond",
+        }
+    }
+
+    /// 价格小数位：股票、指数两位，基金、债券三位
+    pub fn price_decimals(&self) -> u32 {
+        match self {
+            SecurityType::Stock | SecurityType::Index => 2,
+            SecurityType::Etf | SecurityType::Bond => 3,
+        }
+    }
+
+    /// 根据市场和代码前缀判断证券类型
+    ///
+    /// 同一代码在不同市场含义不同（如 000001 在上交所是上证指数，在深交所是平安银行），
+    /// 因此必须同时给出市场。
+    pub fn classify(market: Market, code: &str) -> Option<Self> {
+        if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let p2 = &code[0..2];
+        let p3 = &code[0..3];
+        match market {
+            Market::SH => match p2 {
+                "60" | "68" | "90" => Some(SecurityType::Stock),
+                "00" | "88" | "99" => Some(SecurityType::Index),
+                "50" | "51" | "52" | "56" | "58" => Some(SecurityType::Etf),
+                "01" | "02" | "10" | "11" | "13" | "20" => Some(SecurityType::Bond),
+                _ => None,
+            },
+            Market::SZ => match p2 {
+                "00" | "20" | "30" => Some(SecurityType::Stock),
+                "39" => Some(SecurityType::Index),
+                "15" | "16" | "18" => Some(SecurityType::Etf),
+                "10" | "11" | "12" | "13" => Some(SecurityType::Bond),
+                _ => None,
+            },
+            Market::BJ => match (p2, p3) {
+                (_, "899") => Some(SecurityType::Index),
+                ("43" | "83" | "87" | "92", _) => Some(SecurityType::Stock),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// 上市板块（仅股票）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Board {
+    Main,     // 主板
+    ChiNext,  // 创业板
+    Star,     // 科创板
+    Bse,      // 北交所
+}
+
+impl Board {
+    /// 存储名称（与 serde 一致）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Board::Main => "main",
+            Board::ChiNext => "chinext",
+            Board::Star => "star",
+            Board::Bse => "bse",
+        }
+    }
+
+    /// 根据市场和代码前缀判断股票所属板块
+    pub fn classify(market: Market, code: &str) -> Option<Self> {
+        if SecurityType::classify(market, code) != Some(SecurityType::Stock) {
+            return None;
+        }
+
+        match market {
+            Market::SH if code.starts_with("68") => Some(Board::Star),
+            Market::SZ if code.starts_with("30") => Some(Board::ChiNext),
+            Market::SH | Market::SZ => Some(Board::Main),
+            Market::BJ => Some(Board::Bse),
+        }
+    }
+}
+
+/// 风险警示状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StStatus {
+    #[default]
+    Normal,  // 正常
+    St,      // ST（其他风险警示）
+    StarSt,  // *ST（退市风险警示）
+}
+
+impl StStatus {
+    /// 存储名称（与 serde 一致）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StStatus::Normal => "normal",
+            StStatus::St => "st",
+            StStatus::StarSt => "star_st",
+        }
+    }
+
+    /// 由证券简称判断（如 `ST曙光`、`*ST大集`、`S*ST前锋`）
+    pub fn from_name(name: &str) -> Self {
+        let name = name.trim();
+        if name.contains("*ST") {
+            StStatus::StarSt
+        } else if name.starts_with("ST") || name.starts_with("SST") {
+            StStatus::St
+        } else {
+            StStatus::Normal
+        }
+    }
+}
+
+/// 上市状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingStatus {
+    #[default]
+    Listed,     // 正常上市
+    Delisting,  // 退市整理期（简称带"退"）
+    Delisted,   // 已退市（数据源不再返回）
+}
+
+impl ListingStatus {
+    /// 存储名称（与 serde 一致）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingStatus::Listed => "listed",
+            ListingStatus::Delisting => "delisting",
+            ListingStatus::Delisted => "delisted",
+        }
+    }
+
+    /// 由证券简称判断是否处于退市整理期
+    pub fn from_name(name: &str) -> Self {
+        if name.trim().ends_with('退') || name.trim().starts_with("退市") {
+            ListingStatus::Delisting
+        } else {
+            ListingStatus::Listed
+        }
+    }
+}
+
+/// 证券主数据
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Security {
+    pub code: String,
+    pub name: String,
+    pub market: Market,
+    pub security_type: SecurityType,
+    pub board: Option<Board>,          // 仅股票
+    pub st: StStatus,
+    pub list_date: Option<NaiveDate>,  // 上市日期
+    pub status: ListingStatus,
+    pub total_shares: Option<f64>,     // 总股本（股）
+    pub float_shares: Option<f64>,     // 流通股本（股）
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Security {
+    /// 是否为 ST / *ST
+    pub fn is_st(&self) -> bool {
+        self.st != StStatus::Normal
+    }
+
+    /// 是否仍在交易（含退市整理期）
+    pub fn is_listed(&self) -> bool {
+        self.status != ListingStatus::Delisted
+    }
+
+    /// 转为股票基本信息
+    pub fn to_stock(&self) -> Stock {
+        Stock {
+            code: self.code.clone(),
+            name: self.name.clone(),
+            market: self.market,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,7 +253,70 @@ mod tests {
         assert_eq!(Market::from_code("600000"), Some(Market::SH));
         assert_eq!(Market::from_code("688001"), Some(Market::SH));
         assert_eq!(Market::from_code("430001"), Some(Market::BJ));
+        assert_eq!(Market::from_code("920118"), Some(Market::BJ));
         assert_eq!(Market::from_code("123456"), None);
         assert_eq!(Market::from_code("12345"), None);
     }
+
+    #[test]
+    fn test_security_type_classify() {
+        assert_eq!(SecurityType::classify(Market::SH, "600000"), Some(SecurityType::Stock));
+        assert_eq!(SecurityType::classify(Market::SH, "000001"), Some(SecurityType::Index));
+        assert_eq!(SecurityType::classify(Market::SZ, "000001"), Some(SecurityType::Stock));
+        assert_eq!(SecurityType::classify(Market::SZ, "399001"), Some(SecurityType::Index));
+        assert_eq!(SecurityType::classify(Market::SH, "510300"), Some(SecurityType::Etf));
+        assert_eq!(SecurityType::classify(Market::SZ, "159915"), Some(SecurityType::Etf));
+        assert_eq!(SecurityType::classify(Market::SH, "113050"), Some(SecurityType::Bond));
+        assert_eq!(SecurityType::classify(Market::BJ, "899050"), Some(SecurityType::Index));
+        assert_eq!(SecurityType::classify(Market::BJ, "830799"), Some(SecurityType::Stock));
+        assert_eq!(SecurityType::classify(Market::SZ, "990001"), None);
+    }
+
+    #[test]
+    fn test_board_classify() {
+        assert_eq!(Board::classify(Market::SH, "600000"), Some(Board::Main));
+        assert_eq!(Board::classify(Market::SH, "688981"), Some(Board::Star));
+        assert_eq!(Board::classify(Market::SZ, "000001"), Some(Board::Main));
+        assert_eq!(Board::classify(Market::SZ, "300750"), Some(Board::ChiNext));
+        assert_eq!(Board::classify(Market::BJ, "830799"), Some(Board::Bse));
+        assert_eq!(Board::classify(Market::BJ, "920118"), Some(Board::Bse));
+        // 指数、基金没有板块
+        assert_eq!(Board::classify(Market::SH, "000001"), None);
+        assert_eq!(Board::classify(Market::SZ, "159915"), None);
+    }
+
+    #[test]
+    fn test_st_and_listing_status() {
+        assert_eq!(StStatus::from_name("平安银行"), StStatus::Normal);
+        assert_eq!(StStatus::from_name("ST曙光"), StStatus::St);
+        assert_eq!(StStatus::from_name("*ST大集"), StStatus::StarSt);
+        assert_eq!(StStatus::from_name("S*ST前锋"), StStatus::StarSt);
+
+        assert_eq!(ListingStatus::from_name("*ST大集"), ListingStatus::Listed);
+        assert_eq!(ListingStatus::from_name("大集退"), ListingStatus::Delisting);
+        assert_eq!(ListingStatus::from_name("退市海医"), ListingStatus::Delisting);
+    }
+
+    #[test]
+    fn test_as_str_matches_serde() {
+        fn serde_name<T: Serialize>(value: T) -> String {
+            serde_json::to_value(value).unwrap().as_str().unwrap().to_string()
+        }
+
+        for market in [Market::SZ, Market::SH, Market::BJ] {
+            assert_eq!(market.as_str(), serde_name(market));
+        }
+        for kind in [SecurityType::Stock, SecurityType::Index, SecurityType::Etf, SecurityType::Bond] {
+            assert_eq!(kind.as_str(), serde_name(kind));
+        }
+        for board in [Board::Main, Board::ChiNext, Board::Star, Board::Bse] {
+            assert_eq!(board.as_str(), serde_name(board));
+        }
+        for st in [StStatus::Normal, StStatus::St, StStatus::StarSt] {
+            assert_eq!(st.as_str(), serde_name(st));
+        }
+        for status in [ListingStatus::Listed, ListingStatus::Delisting, ListingStatus::Delisted] {
+            assert_eq!(status.as_str(), serde_name(status));
+        }
+    }
 }
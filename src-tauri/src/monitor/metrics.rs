@@ -151,7 +151,7 @@ impl CollectorMonitor {
                 if is_healthy {
                     server.fail_count = 0;
                     // 更新平均延迟
-                    server.avg_latency_ms = (server.avg_latency_ms * 0.9 + latency_ms * 0.1);
+                    server.avg_latency_ms = server.avg_latency_ms * 0.9 + latency_ms * 0.1;
                 } else {
                     server.fail_count += 1;
                 }
@@ -344,7 +344,7 @@ mod tests {
         monitor.update_server_health("localhost:7709".to_string(), false, 0.0).await;
 
         let servers = monitor.servers.read().await;
-        assert_eq!(servers[0].is_healthy, false);
+        assert!(!servers[0].is_healthy);
         assert_eq!(servers[0].fail_count, 1);
     }
 }
@@ -112,6 +112,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::default_constructed_unit_structs)] // 专门测试 Default 实现
     fn test_dragon_tiger_service_default() {
         let service = DragonTigerService::default();
         let _ = service; // 避免未使用警告
@@ -2,8 +2,12 @@
 
 pub mod dragon_tiger_service;
 pub mod money_flow_service;
+pub mod quality_service;
 pub mod quote_service;
+pub mod security_service;
 
 pub use dragon_tiger_service::DragonTigerService;
 pub use money_flow_service::MoneyFlowService;
-pub use quote_service::QuoteService;
+pub use quality_service::{QualityReportJob, QualityService, QualityStore};
+pub use quote_service::{FactorRefreshJob, FactorStore, QuoteService};
+pub use security_service::{SecurityService, SyncSummary};
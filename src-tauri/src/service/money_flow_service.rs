@@ -1,4 +1,4 @@
-use crate::models::{MoneyFlow, TradeDirection};
+use crate::models::{MoneyFlow, TradeDirection, Transaction};
 use crate::error::{AppError, Result};
 use chrono::{DateTime, Utc};
 
@@ -41,6 +41,7 @@ impl MoneyFlowService {
             TradeDirection::Sell if is_big => (0.0, amount / 10_000.0, 0.0, 0.0),
             TradeDirection::Buy => (0.0, 0.0, amount / 10_000.0, 0.0),
             TradeDirection::Sell => (0.0, 0.0, 0.0, amount / 10_000.0),
+            TradeDirection::Neutral => (0.0, 0.0, 0.0, 0.0),
         };
 
         MoneyFlow {
@@ -87,6 +88,22 @@ impl MoneyFlowService {
         })
     }
 
+    /// 由逐笔成交计算资金流向
+    ///
+    /// # 参数
+    /// * `transactions` - 同一股票的逐笔成交
+    ///
+    /// # 返回
+    /// 聚合后的资金流向数据，时间取第一笔成交
+    pub fn analyze_transactions(&self, transactions: &[Transaction]) -> Result<MoneyFlow> {
+        let flows = transactions
+            .iter()
+            .map(|t| self.analyze_trade(&t.code, t.price, t.volume, t.direction, t.datetime))
+            .collect();
+
+        self.aggregate_money_flow(flows)
+    }
+
     /// 获取当日资金流向（占位实现）
     ///
     /// # 参数
@@ -282,6 +299,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::default_constructed_unit_structs)] // 专门测试 Default 实现
     fn test_money_flow_service_default() {
         let service = MoneyFlowService::default();
         // 测试默认创建
@@ -294,4 +312,31 @@ mod tests {
         );
         assert_eq!(flow.code, "000001");
     }
+
+    #[test]
+    fn test_analyze_transactions() {
+        let service = MoneyFlowService::new();
+        let trade = |volume: f64, direction: TradeDirection| Transaction {
+            code: "000001".to_string(),
+            datetime: Utc::now(),
+            price: 10.0,
+            volume,
+            amount: 10.0 * volume * 100.0,
+            num: 1,
+            direction,
+        };
+
+        let transactions = vec![
+            trade(2000.0, TradeDirection::Buy),    // 大单买入 200 万
+            trade(100.0, TradeDirection::Sell),    // 散户卖出 10 万
+            trade(5000.0, TradeDirection::Neutral), // 中性盘不计入
+        ];
+
+        let flow = service.analyze_transactions(&transactions).unwrap();
+        assert_eq!(flow.main_inflow, 200.0);
+        assert_eq!(flow.retail_outflow, 10.0);
+        assert_eq!(flow.net_amount(), 190.0);
+
+        assert!(service.analyze_transactions(&[]).is_err());
+    }
 }
@@ -0,0 +1,249 @@
+use crate::collector::clock;
+use crate::collector::jobs::DailyJob;
+use crate::collector::tdx::parse_date;
+use crate::error::{AppError, Result};
+use crate::models::quality::{Coverage, DailyQuality, DataQualityReport, QualityIssue, ReportFormat};
+use crate::models::stock::SecurityType;
+use crate::service::SecurityService;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 质量报告的数据来源（ClickHouse 实现见 `db::clickhouse`）
+#[async_trait]
+pub trait QualityStore: Send + Sync {
+    /// 区间内每日各质量评分的记录数（每个代码每个交易日只计最新版本）
+    async fn daily_quality(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<DailyQuality>>;
+
+    /// 区间内记录的质量问题
+    async fn quality_issues(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<QualityIssue>>;
+
+    /// 区间内有日线数据的股票代码
+    async fn collected_codes(&self, start: NaiveDate, end: NaiveDate) -> Result<BTreeSet<String>>;
+}
+
+/// 数据质量报告服务
+///
+/// 每日统计在查询时按 `factor` 表（`FINAL`）的 `quality_score` 分组计数，评分由对账时的验证规则
+/// 给出。未使用 `mv_daily_quality_stats` 物化视图：它按写入次数累加，对账重新写入的版本会被重复
+/// 计数，已由 migrations/005 删除。
+pub struct QualityService {
+    store: Arc<dyn QualityStore>,
+    securities: Option<Arc<SecurityService>>,
+}
+
+impl QualityService {
+    pub fn new(store: Arc<dyn QualityStore>) -> Self {
+        Self { store, securities: None }
+    }
+
+    /// 使用证券主数据计算应采集的股票（否则覆盖率为空）
+    pub fn with_security_master(mut self, securities: Arc<SecurityService>) -> Self {
+        self.securities = Some(securities);
+        self
+    }
+
+    /// 生成 `[start, end]` 的质量报告
+    pub async fn report(&self, start: NaiveDate, end: NaiveDate) -> Result<DataQualityReport> {
+        if start > end {
+            return Err(AppError::Parse(format!("开始日期 {} 晚于结束日期 {}", start, end)));
+        }
+        tracing::debug!("生成数据质量报告: {} 至 {}", start, end);
+
+        let mut daily = self.store.daily_quality(start, end).await?;
+        daily.sort_by_key(|d| d.date);
+        let mut issues = self.store.quality_issues(start, end).await?;
+        issues.sort_by(|a, b| (a.date, &a.code, b.severity).cmp(&(b.date, &b.code, a.severity)));
+        let collected = self.store.collected_codes(start, end).await?;
+
+        Ok(DataQualityReport {
+            start,
+            end,
+            total_records: daily.iter().map(DailyQuality::total).sum(),
+            good_quality: daily.iter().map(|d| d.good).sum(),
+            suspect: daily.iter().map(|d| d.suspect).sum(),
+            error: daily.iter().map(|d| d.error).sum(),
+            daily,
+            coverage: self.coverage(end, &collected).await,
+            issues,
+        })
+    }
+
+    /// 应采集的股票：截至 `end` 已上市且未退市
+    async fn coverage(&self, end: NaiveDate, collected: &BTreeSet<String>) -> Coverage {
+        let Some(securities) = &self.securities else {
+            return Coverage {
+                collected: collected.len(),
+                ..Coverage::default()
+            };
+        };
+
+        let expected: BTreeSet<String> = securities
+            .all()
+            .await
+            .into_iter()
+            .filter(|s| s.security_type == SecurityType::Stock && s.is_listed())
+            .filter(|s| s.list_date.is_none_or(|date| date <= end))
+            .map(|s| s.code)
+            .collect();
+        let covered = expected.intersection(collected).count();
+
+        Coverage {
+            expected: expected.len(),
+            collected: covered,
+            ratio: (!expected.is_empty()).then(|| covered as f64 / expected.len() as f64),
+            missing: expected.difference(collected).cloned().collect(),
+        }
+    }
+}
+
+/// 日终质量报告任务：生成当日报告并记录摘要，设置目录时同时导出 JSON 文件
+pub struct QualityReportJob {
+    service: Arc<QualityService>,
+    output_dir: Option<PathBuf>,
+}
+
+impl QualityReportJob {
+    pub fn new(service: Arc<QualityService>) -> Self {
+        Self { service, output_dir: None }
+    }
+
+    /// 报告导出目录（文件名见 `DataQualityReport::file_name`）
+    pub fn with_output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = Some(dir.into());
+        self
+    }
+}
+
+#[async_trait]
+impl DailyJob for QualityReportJob {
+    async fn run(&self, date: NaiveDate) -> Result<()> {
+        let report = self.service.report(date, date).await?;
+        tracing::info!(
+            "{} 数据质量: 共 {} 条，可疑 {}，错误 {}，缺少 {} 只股票",
+            date,
+            report.total_records,
+            report.suspect,
+            report.error,
+            report.coverage.missing.len()
+        );
+
+        if let Some(dir) = &self.output_dir {
+            std::fs::create_dir_all(dir)?;
+            let path = dir.join(report.file_name(ReportFormat::Json));
+            std::fs::write(&path, report.export(ReportFormat::Json)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// 解析报告区间：只给一端时为单日，都不给时为今天
+pub fn report_range(start: Option<&str>, end: Option<&str>) -> Result<(NaiveDate, NaiveDate)> {
+    let start = start.filter(|s| !s.is_empty()).map(parse_date).transpose()?;
+    let end = end.filter(|s| !s.is_empty()).map(parse_date).transpose()?;
+    Ok(match (start, end) {
+        (Some(start), Some(end)) => (start, end),
+        (Some(day), None) | (None, Some(day)) => (day, day),
+        (None, None) => {
+            let today = clock::now_local().date();
+            (today, today)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::csv_source::fixture_source;
+    use crate::db::LocalDb;
+    use crate::models::quality::{IssueType, ReportFormat, Severity};
+    use chrono::Utc;
+
+    struct FixedStore;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, d).unwrap()
+    }
+
+    fn issue(d: u32, code: &str, issue_type: IssueType, severity: Severity, description: &str) -> QualityIssue {
+        QualityIssue {
+            log_time: Utc::now(),
+            date: date(d),
+            code: code.to_string(),
+            issue_type,
+            description: description.to_string(),
+            severity,
+        }
+    }
+
+    #[async_trait]
+    impl QualityStore for FixedStore {
+        async fn daily_quality(&self, _: NaiveDate, _: NaiveDate) -> Result<Vec<DailyQuality>> {
+            Ok(vec![
+                DailyQuality { date: date(4), good: 5, suspect: 1, error: 0 },
+                DailyQuality { date: date(3), good: 6, suspect: 0, error: 1 },
+            ])
+        }
+
+        async fn quality_issues(&self, _: NaiveDate, _: NaiveDate) -> Result<Vec<QualityIssue>> {
+            Ok(vec![
+                issue(4, "600000", IssueType::Gap, Severity::Warning, "缺少 1 个交易日"),
+                issue(3, "000001", IssueType::Duplicate, Severity::Error, "有 2 条记录，数值不一致, \"已去重\""),
+            ])
+        }
+
+        async fn collected_codes(&self, _: NaiveDate, _: NaiveDate) -> Result<BTreeSet<String>> {
+            Ok(["000001", "300750", "600000", "600036", "688981"].map(String::from).into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_counts_and_coverage() {
+        let securities = Arc::new(SecurityService::new(Arc::new(LocalDb::open_in_memory().unwrap())).unwrap());
+        securities.sync(&fixture_source()).await.unwrap();
+        let service = QualityService::new(Arc::new(FixedStore)).with_security_master(securities);
+
+        let report = service.report(date(3), date(4)).await.unwrap();
+        assert_eq!(
+            (report.total_records, report.good_quality, report.suspect, report.error),
+            (13, 11, 1, 1)
+        );
+        assert_eq!(report.daily[0].date, date(3));
+        assert_eq!(report.issues[0].code, "000001");
+
+        // 样本共 7 只股票，000004 与 830799 无数据
+        assert_eq!(report.coverage.expected, 7);
+        assert_eq!(report.coverage.collected, 5);
+        assert_eq!(report.coverage.missing, vec!["000004", "830799"]);
+        assert!((report.coverage.ratio.unwrap() - 5.0 / 7.0).abs() < 1e-9);
+
+        let csv = report.export(ReportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "section,date,code,item,value,description");
+        assert_eq!(lines[1], "summary,,,total_records,13,");
+        assert!(lines.contains(&"daily,2024-06-04,,suspect,1,"));
+        assert!(lines.contains(&"missing,,830799,,,"));
+        assert!(lines.contains(&"issue,2024-06-03,000001,duplicate,error,\"有 2 条记录，数值不一致, \"\"已去重\"\"\""));
+
+        let json: DataQualityReport = serde_json::from_str(&report.export(ReportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json, report);
+
+        assert!(service.report(date(4), date(3)).await.is_err());
+        assert_eq!(report_range(Some("2024-06-03"), None).unwrap(), (date(3), date(3)));
+        assert!(report_range(Some("20240603"), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_report_job_exports_json() {
+        let dir = std::env::temp_dir().join(format!("kaipanla_quality_{}", std::process::id()));
+        let job = QualityReportJob::new(Arc::new(QualityService::new(Arc::new(FixedStore)))).with_output_dir(&dir);
+        job.run(date(4)).await.unwrap();
+
+        let path = dir.join("data_quality_2024-06-04_2024-06-04.json");
+        let report: DataQualityReport = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!((report.start, report.total_records), (date(4), 13));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,25 +1,58 @@
-use crate::collector::tdx::TdxClient;
-use crate::models::{Quote, Stock};
-use crate::error::Result;
+use crate::collector::factor::{self, FactorData};
+use crate::collector::feed::{MarketEvent, MarketFeed};
+use crate::collector::jobs::DailyJob;
+use crate::collector::source::DataSource;
+use crate::models::{AdjustMode, KLine, KLinePeriod, Market, Quote, SecurityType, Stock};
+use crate::service::security_service::SecurityService;
+use crate::error::{AppError, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// 复权因子的数据来源（ClickHouse 实现见 `db::clickhouse`）
+#[async_trait]
+pub trait FactorStore: Send + Sync {
+    /// 单只股票按日期升序的全部复权因子
+    async fn factors(&self, code: &str) -> Result<Vec<FactorData>>;
+}
 
 /// 实时行情服务
 pub struct QuoteService {
-    tdx_client: TdxClient,
+    source: Arc<dyn DataSource>,
     cache: Arc<RwLock<HashMap<String, Quote>>>,
+    factor_store: Option<Arc<dyn FactorStore>>,
+    factors: Arc<RwLock<HashMap<String, Arc<Vec<FactorData>>>>>,
+    securities: Option<Arc<SecurityService>>,
 }
 
 impl QuoteService {
     /// 创建新的行情服务
-    pub fn new(tdx_client: TdxClient) -> Self {
+    pub fn new(source: Arc<dyn DataSource>) -> Self {
         Self {
-            tdx_client,
+            source,
             cache: Arc::new(RwLock::new(HashMap::new())),
+            factor_store: None,
+            factors: Arc::new(RwLock::new(HashMap::new())),
+            securities: None,
         }
     }
 
+    /// 复权查询从 `factor` 表读取复权因子（按股票缓存）
+    pub fn with_factor_store(mut self, store: Arc<dyn FactorStore>) -> Self {
+        self.factor_store = Some(store);
+        self
+    }
+
+    /// 股票列表改由证券主数据提供
+    pub fn with_security_master(mut self, securities: Arc<SecurityService>) -> Self {
+        self.securities = Some(securities);
+        self
+    }
+
     /// 获取单只股票实时行情
     pub async fn get_quote(&self, code: &str) -> Result<Quote> {
         // 先从缓存查找
@@ -30,7 +63,7 @@ impl QuoteService {
             }
         }
 
-        // 缓存未命中，获取实时数据（占位实现）
+        // 缓存未命中，从数据源获取
         let quote = self.fetch_quote(code).await?;
 
         // 更新缓存
@@ -58,33 +91,110 @@ impl QuoteService {
         Ok(quotes)
     }
 
-    /// 获取股票列表
+    /// 单只股票的复权因子，首次查询时从 `factor` 表读取并缓存
+    async fn factors(&self, code: &str) -> Result<Arc<Vec<FactorData>>> {
+        if let Some(rows) = self.factors.read().await.get(code) {
+            return Ok(rows.clone());
+        }
+        let Some(store) = &self.factor_store else {
+            return Ok(Arc::default());
+        };
+
+        let rows = Arc::new(store.factors(code).await?);
+        tracing::debug!("加载股票 {} 复权因子 {} 条", code, rows.len());
+        self.factors.write().await.insert(code.to_string(), rows.clone());
+        Ok(rows)
+    }
+
+    /// 清空复权因子缓存，下次查询时重新读取（日终因子任务完成后调用）
+    pub async fn refresh_factors(&self) {
+        let mut factors = self.factors.write().await;
+        tracing::info!("清空 {} 只股票的复权因子缓存", factors.len());
+        factors.clear();
+    }
+
+    /// 获取 K 线并按指定方式复权
+    ///
+    /// `factor` 表中没有复权因子的股票返回原始价格。
+    pub async fn get_kline(
+        &self,
+        code: &str,
+        period: KLinePeriod,
+        start: NaiveDate,
+        end: NaiveDate,
+        adjust: AdjustMode,
+    ) -> Result<Vec<KLine>> {
+        let mut bars = self.source.get_kline(code, period, start, end).await?;
+
+        if adjust != AdjustMode::None {
+            let rows = self.factors(code).await?;
+            if rows.is_empty() {
+                tracing::warn!("股票 {} 无复权因子，返回不复权数据", code);
+            } else {
+                factor::adjust_klines(&mut bars, &rows, adjust);
+            }
+        }
+
+        Ok(bars)
+    }
+
+    /// 获取股票列表（不含指数、基金、债券）
+    ///
+    /// 配置了证券主数据时返回其中仍在交易的股票，否则直接查询数据源。
     pub async fn get_stock_list(&self) -> Result<Vec<Stock>> {
-        // TODO: 从数据库或通达信获取
-        Ok(vec![])
+        if let Some(securities) = &self.securities {
+            return Ok(securities.stocks().await);
+        }
+
+        let mut stocks = Vec::new();
+        for market in [Market::SH, Market::SZ, Market::BJ] {
+            stocks.extend(
+                self.source
+                    .get_all_securities(market)
+                    .await?
+                    .into_iter()
+                    .filter(|s| SecurityType::classify(market, &s.code) == Some(SecurityType::Stock))
+                    .map(|s| Stock {
+                        code: s.code,
+                        name: s.name,
+                        market,
+                    }),
+            );
+        }
+        Ok(stocks)
     }
 
-    /// 从通达信获取实时行情（占位实现）
+    /// 从数据源获取实时行情
     async fn fetch_quote(&self, code: &str) -> Result<Quote> {
-        tracing::debug!("获取股票 {} 实时行情", code);
-
-        // TODO: 使用 rustdx API 获取真实数据
-        // 这里返回模拟数据用于测试
-        Ok(Quote {
-            code: code.to_string(),
-            name: "测试股票".to_string(),
-            price: 10.5,
-            preclose: 10.0,
-            open: 10.2,
-            high: 10.6,
-            low: 10.1,
-            volume: 100000.0,
-            amount: 1050000.0,
-            bid: [10.49, 10.48, 10.47, 10.46, 10.45],
-            bid_vol: [1000.0, 2000.0, 3000.0, 4000.0, 5000.0],
-            ask: [10.51, 10.52, 10.53, 10.54, 10.55],
-            ask_vol: [1000.0, 2000.0, 3000.0, 4000.0, 5000.0],
-            timestamp: chrono::Utc::now(),
+        tracing::debug!("从 {} 获取股票 {} 实时行情", self.source.name(), code);
+
+        self.source
+            .get_quotes(&[code.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::NotFound(format!("股票 {} 无行情数据", code)))
+    }
+
+    /// 订阅行情事件流并持续刷新缓存，事件流关闭后任务退出
+    ///
+    /// 实时采集与录制回放都经由 [`MarketFeed`] 发布，服务不区分数据来自哪里。
+    pub fn spawn_feed_consumer(self: &Arc<Self>, feed: &MarketFeed) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let mut events = feed.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(MarketEvent::Quote(quote)) => {
+                        let mut cache = service.cache.write().await;
+                        cache.insert(quote.code.clone(), quote);
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(n)) => tracing::warn!("行情缓存更新落后，丢失 {} 个事件", n),
+                    Err(RecvError::Closed) => break,
+                }
+            }
         })
     }
 
@@ -97,21 +207,57 @@ impl QuoteService {
     }
 }
 
+/// 日终任务：复权因子写入后刷新行情服务的缓存（注册在因子任务之后）
+pub struct FactorRefreshJob {
+    service: Arc<QuoteService>,
+}
+
+impl FactorRefreshJob {
+    pub fn new(service: Arc<QuoteService>) -> Self {
+        Self { service }
+    }
+}
+
+#[async_trait]
+impl DailyJob for FactorRefreshJob {
+    async fn run(&self, _date: NaiveDate) -> Result<()> {
+        self.service.refresh_factors().await;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collector::csv_source::fixture_source;
+    use crate::collector::factor::FactorSource;
+    use crate::collector::validator::QualityScore;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 记录读取次数的复权因子表
+    struct FixedFactors {
+        rows: std::sync::Mutex<Vec<FactorData>>,
+        loads: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl FactorStore for FixedFactors {
+        async fn factors(&self, code: &str) -> Result<Vec<FactorData>> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            let rows = self.rows.lock().unwrap();
+            Ok(rows.iter().filter(|row| row.code == code).cloned().collect())
+        }
+    }
 
     #[tokio::test]
     async fn test_quote_service_creation() {
-        let tdx_client = TdxClient::new(vec!["127.0.0.1:7709".to_string()]);
-        let service = QuoteService::new(tdx_client);
+        let service = QuoteService::new(Arc::new(fixture_source()));
         assert_eq!(service.cache.read().await.len(), 0);
     }
 
     #[tokio::test]
     async fn test_get_quote() {
-        let tdx_client = TdxClient::new(vec!["127.0.0.1:7709".to_string()]);
-        let service = QuoteService::new(tdx_client);
+        let service = QuoteService::new(Arc::new(fixture_source()));
 
         let quote = service.get_quote("000001").await.unwrap();
         assert_eq!(quote.code, "000001");
@@ -120,8 +266,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_quotes() {
-        let tdx_client = TdxClient::new(vec!["127.0.0.1:7709".to_string()]);
-        let service = QuoteService::new(tdx_client);
+        let service = QuoteService::new(Arc::new(fixture_source()));
 
         let codes = vec!["000001".to_string(), "600036".to_string()];
         let quotes = service.get_quotes(&codes).await.unwrap();
@@ -133,8 +278,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_quote_cache() {
-        let tdx_client = TdxClient::new(vec!["127.0.0.1:7709".to_string()]);
-        let service = QuoteService::new(tdx_client);
+        let service = QuoteService::new(Arc::new(fixture_source()));
 
         // 第一次获取
         let quote1 = service.get_quote("000001").await.unwrap();
@@ -150,12 +294,140 @@ mod tests {
         assert_eq!(quote1.price, quote2.price);
     }
 
+    #[tokio::test]
+    async fn test_get_quote_not_found() {
+        let service = QuoteService::new(Arc::new(fixture_source()));
+
+        let result = service.get_quote("999999").await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+        assert_eq!(service.cache.read().await.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_feed_updates_cache() {
+        let service = Arc::new(QuoteService::new(Arc::new(fixture_source())));
+        let feed = MarketFeed::default();
+        let consumer = service.spawn_feed_consumer(&feed);
+
+        let mut quote = service.get_quote("000001").await.unwrap();
+        quote.price = 10.8;
+        feed.publish(MarketEvent::Quote(quote));
+        drop(feed);
+        consumer.await.unwrap();
+
+        assert_eq!(service.get_quote("000001").await.unwrap().price, 10.8);
+    }
+
+    #[tokio::test]
+    async fn test_get_kline_adjusted() {
+        use crate::collector::clock;
+        use crate::collector::mock_server::{MockData, MockTdxServer};
+        use crate::collector::tdx::TdxClient;
+
+        let date = |d: u32| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+        let closes = [(3, 10.0), (4, 10.0), (5, 9.2)];
+        let bars: Vec<KLine> = closes
+            .iter()
+            .map(|&(d, close)| KLine {
+                datetime: clock::to_utc(date(d).and_hms_opt(15, 0, 0).unwrap()),
+                code: "600000".to_string(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1000.0,
+                amount: close * 1000.0,
+            })
+            .collect();
+
+        // 6-05 10 派 10 元，除权价 9.0
+        let factors: Vec<FactorData> = closes
+            .iter()
+            .map(|&(d, close)| FactorData {
+                date: date(d),
+                code: "600000".to_string(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                preclose: if d == 5 { 9.0 } else { 10.0 },
+                factor: if d == 5 { 10.0 / 9.0 } else { 1.0 },
+                volume: 1000.0,
+                amount: close * 1000.0,
+                data_source: FactorSource::Api,
+                data_version: 1,
+                quality_score: QualityScore::Good,
+            })
+            .collect();
+        let store = Arc::new(FixedFactors {
+            rows: std::sync::Mutex::new(factors),
+            loads: AtomicUsize::new(0),
+        });
+
+        let server = MockTdxServer::start(MockData::default().with_bars("600000", KLinePeriod::Day, bars)).await;
+        let service = Arc::new(
+            QuoteService::new(Arc::new(TdxClient::new(vec![server.addr()]))).with_factor_store(store.clone()),
+        );
+
+        let raw = service
+            .get_kline("600000", KLinePeriod::Day, date(3), date(5), AdjustMode::None)
+            .await
+            .unwrap();
+        assert_eq!(raw[0].close, 10.0);
+
+        let forward = service
+            .get_kline("600000", KLinePeriod::Day, date(3), date(5), AdjustMode::Forward)
+            .await
+            .unwrap();
+        assert!((forward[0].close - 9.0).abs() < 1e-9);
+        assert!((forward[2].close - 9.2).abs() < 1e-9);
+
+        let backward = service
+            .get_kline("600000", KLinePeriod::Day, date(3), date(5), AdjustMode::Backward)
+            .await
+            .unwrap();
+        assert_eq!(backward[0].close, 10.0);
+        assert!((backward[2].close - 9.2 * 10.0 / 9.0).abs() < 1e-9);
+
+        // 因子只读取一次，因子任务完成后重新读取
+        assert_eq!(store.loads.load(Ordering::SeqCst), 1);
+        store.rows.lock().unwrap().iter_mut().for_each(|row| row.factor *= 2.0);
+        FactorRefreshJob::new(service.clone()).run(date(5)).await.unwrap();
+        let refreshed = service
+            .get_kline("600000", KLinePeriod::Day, date(3), date(5), AdjustMode::Backward)
+            .await
+            .unwrap();
+        assert_eq!(refreshed[0].close, 20.0);
+        assert_eq!(store.loads.load(Ordering::SeqCst), 2);
+
+        // 无因子的股票返回原始价格
+        let plain = QuoteService::new(Arc::new(fixture_source()))
+            .get_kline("000001", KLinePeriod::Day, date(3), date(7), AdjustMode::Forward)
+            .await
+            .unwrap();
+        assert_eq!(plain[4].close, 10.5);
+    }
+
     #[tokio::test]
     async fn test_get_stock_list() {
-        let tdx_client = TdxClient::new(vec!["127.0.0.1:7709".to_string()]);
-        let service = QuoteService::new(tdx_client);
+        let service = QuoteService::new(Arc::new(fixture_source()));
 
         let stocks = service.get_stock_list().await.unwrap();
-        assert_eq!(stocks.len(), 0); // 占位实现返回空列表
+        assert_eq!(stocks.len(), 7);
+        assert!(stocks.iter().all(|s| s.code != "159915"));
+        assert!(!stocks.iter().any(|s| s.market == Market::SH && s.code == "000001"));
+    }
+
+    #[tokio::test]
+    async fn test_get_stock_list_from_security_master() {
+        use crate::db::LocalDb;
+
+        let source = fixture_source();
+        let securities = Arc::new(SecurityService::new(Arc::new(LocalDb::open_in_memory().unwrap())).unwrap());
+        let service = QuoteService::new(Arc::new(source.clone())).with_security_master(securities.clone());
+        assert!(service.get_stock_list().await.unwrap().is_empty());
+
+        securities.sync(&source).await.unwrap();
+        assert_eq!(service.get_stock_list().await.unwrap().len(), 7);
     }
 }
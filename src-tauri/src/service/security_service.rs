@@ -0,0 +1,320 @@
+use crate::collector::source::DataSource;
+use crate::db::LocalDb;
+use crate::error::{AppError, Result};
+use crate::models::stock::{
+    Board, ListingStatus, Market, Security, SecurityType, StStatus, Stock,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::types::Type;
+use rusqlite::{params, Row};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// 主数据同步结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncSummary {
+    pub total: usize,            // 本次数据源返回的证券数
+    pub added: usize,            // 新增证券数
+    pub delisted: usize,         // 新标记为退市的证券数
+    pub finance_errors: usize,   // 财务信息获取失败的股票数
+    pub failed_markets: Vec<Market>,
+}
+
+/// 证券主数据服务
+///
+/// 从数据源同步全市场证券并保存在本地数据库，启动时从本地加载，
+/// 其他模块按代码查询板块、ST、上市日期、股本等属性。
+pub struct SecurityService {
+    db: Arc<LocalDb>,
+    cache: RwLock<HashMap<(Market, String), Security>>,
+}
+
+impl SecurityService {
+    /// 创建服务并加载本地已有的主数据
+    pub fn new(db: Arc<LocalDb>) -> Result<Self> {
+        let rows = db.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT market, code, name, security_type, board, st, list_date, status,
+                        total_shares, float_shares, updated_at
+                 FROM securities",
+            )?;
+            let rows = stmt.query_map([], security_from_row)?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+        })?;
+
+        tracing::info!("加载证券主数据 {} 条", rows.len());
+        let cache = rows
+            .into_iter()
+            .map(|s| ((s.market, s.code.clone()), s))
+            .collect();
+
+        Ok(Self {
+            db,
+            cache: RwLock::new(cache),
+        })
+    }
+
+    /// 从数据源同步沪深京全部证券
+    ///
+    /// 板块由代码前缀判断，ST 与退市整理状态由简称判断，上市日期与股本取自财务信息。
+    /// 成功获取列表的市场中不再出现的证券标记为已退市；获取失败的市场保持原样。
+    pub async fn sync(&self, source: &dyn DataSource) -> Result<SyncSummary> {
+        let now = Utc::now();
+        let previous = self.cache.read().await.clone();
+        let mut summary = SyncSummary::default();
+        let mut synced = Vec::new();
+        let mut seen = HashSet::new();
+        let mut changed = Vec::new();
+
+        for market in [Market::SH, Market::SZ, Market::BJ] {
+            let list = match source.get_all_securities(market).await {
+                Ok(list) => list,
+                Err(e) => {
+                    tracing::warn!("获取 {:?} 证券列表失败: {}", market, e);
+                    summary.failed_markets.push(market);
+                    continue;
+                }
+            };
+            synced.push(market);
+
+            for info in list {
+                let Some(security_type) = SecurityType::classify(market, &info.code) else {
+                    continue;
+                };
+                let old = previous.get(&(market, info.code.clone()));
+                let mut security = Security {
+                    board: Board::classify(market, &info.code),
+                    st: StStatus::from_name(&info.name),
+                    status: ListingStatus::from_name(&info.name),
+                    list_date: old.and_then(|s| s.list_date),
+                    total_shares: old.and_then(|s| s.total_shares),
+                    float_shares: old.and_then(|s| s.float_shares),
+                    code: info.code,
+                    name: info.name.trim().to_string(),
+                    market,
+                    security_type,
+                    updated_at: now,
+                };
+
+                if security_type == SecurityType::Stock {
+                    match source.get_finance_info(market, &security.code).await {
+                        Ok(finance) => {
+                            security.list_date = finance.list_date.or(security.list_date);
+                            if finance.total_shares > 0.0 {
+                                security.total_shares = Some(finance.total_shares);
+                                security.float_shares = Some(finance.float_shares);
+                            }
+                        }
+                        Err(e) => {
+                            tracing::debug!("获取 {} 财务信息失败: {}", security.code, e);
+                            summary.finance_errors += 1;
+                        }
+                    }
+                }
+
+                if old.is_none() {
+                    summary.added += 1;
+                }
+                summary.total += 1;
+                seen.insert((market, security.code.clone()));
+                changed.push(security);
+            }
+        }
+
+        if synced.is_empty() {
+            return Err(AppError::Network(format!("{} 全部市场证券列表获取失败", source.name())));
+        }
+
+        for (key, old) in &previous {
+            if synced.contains(&old.market) && !seen.contains(key) && old.is_listed() {
+                summary.delisted += 1;
+                changed.push(Security {
+                    status: ListingStatus::Delisted,
+                    updated_at: now,
+                    ..old.clone()
+                });
+            }
+        }
+
+        self.save(&changed)?;
+        let mut cache = self.cache.write().await;
+        for security in changed {
+            cache.insert((security.market, security.code.clone()), security);
+        }
+
+        tracing::info!(
+            "证券主数据同步完成: {} 条，新增 {}，退市 {}，财务失败 {}",
+            summary.total,
+            summary.added,
+            summary.delisted,
+            summary.finance_errors
+        );
+        Ok(summary)
+    }
+
+    /// 按市场和代码查询
+    pub async fn get(&self, market: Market, code: &str) -> Option<Security> {
+        self.cache.read().await.get(&(market, code.to_string())).cloned()
+    }
+
+    /// 按股票代码查询（由代码前缀推断市场，指数等需用 [`Self::get`]）
+    pub async fn get_by_code(&self, code: &str) -> Option<Security> {
+        self.get(Market::from_code(code)?, code).await
+    }
+
+    /// 全部证券（含已退市），按市场、代码排序
+    pub async fn all(&self) -> Vec<Security> {
+        let mut all: Vec<_> = self.cache.read().await.values().cloned().collect();
+        all.sort_by(|a, b| (a.market as u8, &a.code).cmp(&(b.market as u8, &b.code)));
+        all
+    }
+
+    /// 仍在交易的股票
+    pub async fn stocks(&self) -> Vec<Stock> {
+        self.all()
+            .await
+            .iter()
+            .filter(|s| s.security_type == SecurityType::Stock && s.is_listed())
+            .map(Security::to_stock)
+            .collect()
+    }
+
+    /// 单个事务写入
+    fn save(&self, securities: &[Security]) -> Result<()> {
+        self.db.with_conn(|conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR REPLACE INTO securities
+                     (market, code, name, security_type, board, st, list_date, status,
+                      total_shares, float_shares, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                )?;
+                for s in securities {
+                    stmt.execute(params![
+                        s.market.as_str(),
+                        s.code,
+                        s.name,
+                        s.security_type.as_str(),
+                        s.board.map(|b| b.as_str()),
+                        s.st.as_str(),
+                        s.list_date.map(|d| d.to_string()),
+                        s.status.as_str(),
+                        s.total_shares,
+                        s.float_shares,
+                        s.updated_at.to_rfc3339(),
+                    ])?;
+                }
+            }
+            tx.commit()
+        })
+    }
+}
+
+/// 枚举按 serde 名称存储（写入见各枚举的 `as_str`）
+fn from_text<T: DeserializeOwned>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(idx)?;
+    serde_json::from_value(serde_json::Value::String(text))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+fn security_from_row(row: &Row) -> rusqlite::Result<Security> {
+    let list_date: Option<String> = row.get(6)?;
+    let updated_at: String = row.get(10)?;
+    let conversion = |idx: usize, e: Box<dyn std::error::Error + Send + Sync>| {
+        rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, e)
+    };
+
+    Ok(Security {
+        market: from_text(row, 0)?,
+        code: row.get(1)?,
+        name: row.get(2)?,
+        security_type: from_text(row, 3)?,
+        board: match row.get::<_, Option<String>>(4)? {
+            Some(_) => Some(from_text(row, 4)?),
+            None => None,
+        },
+        st: from_text(row, 5)?,
+        list_date: list_date
+            .map(|d| d.parse::<NaiveDate>())
+            .transpose()
+            .map_err(|e| conversion(6, Box::new(e)))?,
+        status: from_text(row, 7)?,
+        total_shares: row.get(8)?,
+        float_shares: row.get(9)?,
+        updated_at: DateTime::parse_from_rfc3339(&updated_at)
+            .map_err(|e| conversion(10, Box::new(e)))?
+            .with_timezone(&Utc),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::csv_source::{fixture_source, CsvDataSource};
+
+    fn service() -> SecurityService {
+        SecurityService::new(Arc::new(LocalDb::open_in_memory().unwrap())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sync_attributes() {
+        let service = service();
+        let summary = service.sync(&fixture_source()).await.unwrap();
+        assert_eq!(summary.total, 9);
+        assert_eq!(summary.added, 9);
+        assert!(summary.failed_markets.is_empty());
+
+        let catl = service.get_by_code("300750").await.unwrap();
+        assert_eq!(catl.board, Some(Board::ChiNext));
+        assert_eq!(catl.list_date, NaiveDate::from_ymd_opt(2018, 6, 11));
+        assert_eq!(catl.total_shares, Some(4399041236.0));
+
+        assert_eq!(service.get_by_code("688981").await.unwrap().board, Some(Board::Star));
+        assert_eq!(service.get_by_code("830799").await.unwrap().board, Some(Board::Bse));
+        assert_eq!(service.get_by_code("000004").await.unwrap().st, StStatus::StarSt);
+
+        // 同代码不同市场
+        let index = service.get(Market::SH, "000001").await.unwrap();
+        assert_eq!(index.security_type, SecurityType::Index);
+        assert_eq!(index.board, None);
+        let etf = service.get(Market::SZ, "159915").await.unwrap();
+        assert_eq!(etf.security_type, SecurityType::Etf);
+
+        // 股票列表不含指数与基金
+        assert_eq!(service.stocks().await.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_sync_marks_delisted_and_persists() {
+        let db = Arc::new(LocalDb::open_in_memory().unwrap());
+        let service = SecurityService::new(db.clone()).unwrap();
+        service.sync(&fixture_source()).await.unwrap();
+
+        // 新一轮列表只剩平安银行，且没有财务数据
+        let dir = std::env::temp_dir().join(format!("kaipanla_security_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("securities.csv"), "market,code,name\nSZ,000001,平安银行\n").unwrap();
+
+        let summary = service.sync(&CsvDataSource::new(&dir)).await.unwrap();
+        assert_eq!(summary.total, 1);
+        assert_eq!(summary.added, 0);
+        assert_eq!(summary.delisted, 8);
+        assert_eq!(summary.finance_errors, 1);
+
+        // 财务信息获取失败时保留上次的数据
+        let pingan = service.get_by_code("000001").await.unwrap();
+        assert!(pingan.list_date.is_some());
+        assert_eq!(service.get_by_code("600000").await.unwrap().status, ListingStatus::Delisted);
+
+        // 重新加载后与内存一致
+        let reloaded = SecurityService::new(db).unwrap();
+        assert_eq!(reloaded.all().await, service.all().await);
+        assert_eq!(reloaded.stocks().await.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
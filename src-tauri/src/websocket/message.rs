@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use crate::models::Quote;
+use crate::collector::feed::MarketEvent;
+use crate::models::{Auction, Quote, Transaction};
 
 /// WebSocket 消息类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +12,10 @@ pub enum WsMessage {
     Unsubscribe { channel: String, codes: Vec<String> },
     /// 行情推送
     QuotePush { data: Quote },
+    /// 逐笔成交推送
+    TransactionPush { data: Transaction },
+    /// 竞价推送
+    AuctionPush { data: Auction },
     /// 错误
     Error { message: String },
     /// 心跳
@@ -18,6 +23,16 @@ pub enum WsMessage {
     Pong,
 }
 
+impl From<MarketEvent> for WsMessage {
+    fn from(event: MarketEvent) -> Self {
+        match event {
+            MarketEvent::Quote(data) => WsMessage::QuotePush { data },
+            MarketEvent::Transaction(data) => WsMessage::TransactionPush { data },
+            MarketEvent::Auction(data) => WsMessage::AuctionPush { data },
+        }
+    }
+}
+
 /// 推送频道
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Channel {
@@ -1,14 +1,21 @@
+use crate::collector::feed::{MarketEvent, MarketFeed};
 use crate::websocket::message::WsMessage;
 use crate::{Result, AppError};
 use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::RwLock;
 use futures_util::{SinkExt, StreamExt};
 use tokio_tungstenite::tungstenite::protocol::Message;
 use std::collections::HashSet;
 
+/// 单个连接的订阅（频道, 代码）
+type Subscriptions = HashSet<(String, String)>;
+
 /// WebSocket 服务器
 pub struct WsServer {
     subscribers: Arc<RwLock<HashSet<String>>>,
+    feed: Option<MarketFeed>,
 }
 
 impl WsServer {
@@ -16,6 +23,34 @@ impl WsServer {
     pub fn new() -> Self {
         Self {
             subscribers: Arc::new(RwLock::new(HashSet::new())),
+            feed: None,
+        }
+    }
+
+    /// 设置推送的行情事件流
+    pub fn with_feed(mut self, feed: MarketFeed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    /// 在监听端口上接受连接，每个连接一个任务
+    pub async fn run(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        tracing::info!("WebSocket 服务器启动: ws://{}", listener.local_addr()?);
+
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = Arc::clone(&self);
+
+            tokio::spawn(async move {
+                match tokio_tungstenite::accept_async(stream).await {
+                    Ok(ws) => {
+                        if let Err(e) = server.handle_connection(ws).await {
+                            tracing::error!("WebSocket 连接 {} 异常: {}", peer, e);
+                        }
+                    }
+                    Err(e) => tracing::warn!("WebSocket 握手失败 {}: {}", peer, e),
+                }
+            });
         }
     }
 
@@ -27,22 +62,36 @@ impl WsServer {
         >,
     ) -> Result<()> {
         let mut ws = ws_stream;
+        let mut subscriptions = Subscriptions::new();
+        let mut events = self.feed.as_ref().map(MarketFeed::subscribe);
 
         tracing::info!("WebSocket 客户端已连接");
 
-        // 消息循环
-        while let Some(result) = ws.next().await {
-            match result {
-                Ok(msg) => {
-                    if let Err(e) = self.handle_message(&mut ws, msg).await {
-                        tracing::error!("处理消息失败: {}", e);
+        // 消息循环：客户端消息与行情推送
+        loop {
+            tokio::select! {
+                result = ws.next() => match result {
+                    Some(Ok(msg)) => {
+                        if let Err(e) = self.handle_message(&mut ws, &mut subscriptions, msg).await {
+                            tracing::error!("处理消息失败: {}", e);
+                            break;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        tracing::error!("WebSocket 错误: {}", e);
                         break;
                     }
-                }
-                Err(e) => {
-                    tracing::error!("WebSocket 错误: {}", e);
-                    break;
-                }
+                    None => break,
+                },
+                event = next_event(&mut events) => match event {
+                    Some(event) => {
+                        if let Err(e) = push_event(&mut ws, &subscriptions, event).await {
+                            tracing::error!("推送行情失败: {}", e);
+                            break;
+                        }
+                    }
+                    None => events = None,
+                },
             }
         }
 
@@ -54,12 +103,13 @@ impl WsServer {
     async fn handle_message(
         &self,
         ws: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+        subscriptions: &mut Subscriptions,
         msg: Message,
     ) -> Result<()> {
         match msg {
             Message::Text(text) => {
                 if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
-                    self.handle_ws_message(ws, ws_msg).await?;
+                    self.handle_ws_message(ws, subscriptions, ws_msg).await?;
                 }
             }
             Message::Ping(payload) => {
@@ -82,6 +132,7 @@ impl WsServer {
     async fn handle_ws_message(
         &self,
         ws: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+        subscriptions: &mut Subscriptions,
         msg: WsMessage,
     ) -> Result<()> {
         match msg {
@@ -91,6 +142,7 @@ impl WsServer {
                 // 记录订阅
                 let mut subscribers = self.subscribers.write().await;
                 for code in codes {
+                    subscriptions.insert((channel.to_lowercase(), code.clone()));
                     subscribers.insert(code);
                 }
 
@@ -109,6 +161,7 @@ impl WsServer {
                 // 移除订阅
                 let mut subscribers = self.subscribers.write().await;
                 for code in codes {
+                    subscriptions.remove(&(channel.to_lowercase(), code.clone()));
                     subscribers.remove(&code);
                 }
 
@@ -147,6 +200,38 @@ impl WsServer {
     }
 }
 
+/// 等待下一个行情事件，事件流关闭时返回 None；未配置事件流时永不返回
+async fn next_event(events: &mut Option<broadcast::Receiver<MarketEvent>>) -> Option<MarketEvent> {
+    let Some(rx) = events else {
+        return std::future::pending().await;
+    };
+
+    loop {
+        match rx.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(n)) => tracing::warn!("WebSocket 推送落后，丢失 {} 个事件", n),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// 推送该连接订阅了的事件
+async fn push_event(
+    ws: &mut tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+    subscriptions: &Subscriptions,
+    event: MarketEvent,
+) -> Result<()> {
+    let key = (event.channel().to_string(), event.code().to_string());
+    if !subscriptions.contains(&key) {
+        return Ok(());
+    }
+
+    let json = serde_json::to_string(&WsMessage::from(event))
+        .map_err(|e| AppError::Parse(e.to_string()))?;
+    ws.send(Message::Text(json)).await
+        .map_err(|e| AppError::Network(e.to_string()))
+}
+
 impl Default for WsServer {
     fn default() -> Self {
         Self::new()
@@ -2,54 +2,400 @@
 
 #[cfg(test)]
 mod integration_tests {
-    /// 测试完整的采集流程
-    ///
-    /// TODO: 实现完整的采集流程集成测试
-    /// 1. 启动采集调度器
-    /// 2. 模拟通达信数据获取
-    /// 3. 验证数据验证器
-    /// 4. 批量写入 ClickHouse
-    /// 5. 查询并验证数据
+    use chrono::{DateTime, TimeZone, Utc};
+    use futures_util::{SinkExt, StreamExt};
+    use async_trait::async_trait;
+    use kaipanla::collector::csv_source::CsvDataSource;
+    use kaipanla::collector::feed::{MarketEvent, MarketFeed};
+    use kaipanla::collector::mock_server::{MockData, MockTdxServer};
+    use kaipanla::collector::pipeline::CollectionPipeline;
+    use kaipanla::collector::replay::{
+        load_recording, RecordedEvent, ReplaySpeed, SessionRecorder, SessionReplayer,
+    };
+    use kaipanla::collector::scheduler::CollectionScheduler;
+    use kaipanla::collector::session::SessionPhase;
+    use kaipanla::collector::source::DataSource;
+    use kaipanla::collector::tdx::TdxClient;
+    use kaipanla::collector::writer::{BatchSink, BatchWriter};
+    use kaipanla::config::DataSourceConfig;
+    use kaipanla::monitor::CollectorMonitor;
+    use kaipanla::models::{Auction, Quote, TradeDirection, Transaction};
+    use kaipanla::service::QuoteService;
+    use kaipanla::websocket::{WsMessage, WsServer};
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use tokio_tungstenite::tungstenite::protocol::Message;
+
+    /// 保存写入批次的内存目标
+    #[derive(Default)]
+    struct MemorySink {
+        batches: Mutex<Vec<Vec<Quote>>>,
+    }
+
+    #[async_trait]
+    impl BatchSink<Quote> for MemorySink {
+        async fn write_batch(&self, batch: &[Quote]) -> kaipanla::Result<()> {
+            self.batches.lock().unwrap().push(batch.to_vec());
+            Ok(())
+        }
+    }
+
+    fn fixture_source() -> Arc<CsvDataSource> {
+        Arc::new(CsvDataSource::new(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/csv"),
+        ))
+    }
+
+    /// 北京时间 2024-06-07 的时刻
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 6, 7, hour - 8, minute, second).unwrap()
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("kaipanla_it_{}_{}.gz", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn quote(code: &str, price: f64, ts: DateTime<Utc>) -> MarketEvent {
+        MarketEvent::Quote(plain_quote(code, price, ts))
+    }
+
+    fn plain_quote(code: &str, price: f64, ts: DateTime<Utc>) -> Quote {
+        Quote {
+            code: code.to_string(),
+            name: code.to_string(),
+            price,
+            preclose: 10.0,
+            open: 10.2,
+            high: price.max(10.2),
+            low: 10.1,
+            volume: 100000.0,
+            amount: price * 10000000.0,
+            bid: [price - 0.01; 5],
+            bid_vol: [1000.0; 5],
+            ask: [price + 0.01; 5],
+            ask_vol: [1000.0; 5],
+            timestamp: ts,
+        }
+    }
+
+    fn auction(code: &str, price: f64) -> MarketEvent {
+        MarketEvent::Auction(Auction {
+            code: code.to_string(),
+            name: code.to_string(),
+            price,
+            preclose: 10.0,
+            volume: 5200.0,
+            amount: price * 520000.0,
+            timestamp: at(9, 25, 0),
+        })
+    }
+
+    fn transaction(code: &str, price: f64, ts: DateTime<Utc>) -> MarketEvent {
+        MarketEvent::Transaction(Transaction {
+            code: code.to_string(),
+            datetime: ts,
+            price,
+            volume: 860.0,
+            amount: price * 86000.0,
+            num: 41,
+            direction: TradeDirection::Buy,
+        })
+    }
+
+    /// 一段 10 分钟的交易时段：竞价 → 开盘 → 连续竞价
+    fn session() -> Vec<RecordedEvent> {
+        let events = [
+            (at(9, 25, 0), auction("000001", 10.2)),
+            (at(9, 25, 0), auction("600036", 33.25)),
+            (at(9, 30, 0), quote("000001", 10.2, at(9, 30, 0))),
+            (at(9, 30, 0), quote("600036", 33.3, at(9, 30, 0))),
+            (at(9, 30, 3), transaction("000001", 10.22, at(9, 30, 3))),
+            (at(9, 31, 0), quote("000001", 10.3, at(9, 31, 0))),
+            (at(9, 35, 0), quote("000001", 10.5, at(9, 35, 0))),
+        ];
+        events
+            .into_iter()
+            .map(|(ts, event)| RecordedEvent { ts, event })
+            .collect()
+    }
+
+    /// 录制会话到文件
+    fn record_session(name: &str) -> PathBuf {
+        let path = temp_file(name);
+        let mut recorder = SessionRecorder::open(&path).unwrap();
+        for record in session() {
+            recorder.record(record.ts, &record.event).unwrap();
+        }
+        recorder.flush().unwrap();
+        path
+    }
+
+    /// 测试完整的采集流程：录制 → 回放 → 行情服务
     #[tokio::test]
     async fn test_full_collection_workflow() {
-        // 占位测试 - 完整的采集流程测试
-        // 需要实际的 ClickHouse 实例和通达信服务器连接
-        assert!(true);
+        let path = record_session("workflow");
+        let replayer = Arc::new(SessionReplayer::load(&path).unwrap().with_speed(ReplaySpeed::Max));
+        assert_eq!(replayer.records().len(), 7);
+        assert_eq!(replayer.span(), Duration::from_secs(600));
+
+        // 行情服务以回放器为数据源，同时订阅事件流
+        let feed = MarketFeed::default();
+        let service = Arc::new(QuoteService::new(replayer.clone()));
+        let consumer = service.spawn_feed_consumer(&feed);
+
+        assert_eq!(replayer.replay(&feed).await.unwrap(), 7);
+        drop(feed);
+        consumer.await.unwrap();
+
+        assert_eq!(service.get_quote("000001").await.unwrap().price, 10.5);
+        assert_eq!(service.get_quote("600036").await.unwrap().price, 33.3);
+
+        // 回放结束后数据源可查询全天逐笔与竞价
+        let transactions = replayer.get_transactions("000001", None).await.unwrap();
+        assert_eq!(transactions.len(), 1);
+        let auctions = replayer
+            .get_auctions(&["600036".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(auctions[0].price, 33.25);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// 测试录制实时事件流
+    #[tokio::test]
+    async fn test_record_live_feed() {
+        let path = temp_file("live");
+        let feed = MarketFeed::default();
+        let recorder = SessionRecorder::open(&path).unwrap();
+        let task = tokio::spawn(recorder.record_feed(feed.subscribe()));
+
+        for record in session() {
+            feed.publish(record.event);
+        }
+        drop(feed);
+
+        assert_eq!(task.await.unwrap().unwrap(), 7);
+        let records = load_recording(&path).unwrap();
+        assert_eq!(records.len(), 7);
+        assert!(records.windows(2).all(|w| w[0].ts <= w[1].ts));
+        assert_eq!(records[4].event.channel(), "transaction");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// 测试回放倍速：事件按录制间隔除以倍速发布
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_speeds() {
+        for (speed, expected) in [
+            (ReplaySpeed::X1, Duration::from_secs(600)),
+            (ReplaySpeed::X10, Duration::from_secs(60)),
+            (ReplaySpeed::X100, Duration::from_secs(6)),
+        ] {
+            let replayer = SessionReplayer::from_records(session()).with_speed(speed);
+            let feed = MarketFeed::default();
+            let mut events = feed.subscribe();
+
+            let started = tokio::time::Instant::now();
+            replayer.replay(&feed).await.unwrap();
+            assert_eq!(started.elapsed(), expected, "{:?}", speed);
+
+            let mut count = 0;
+            while events.try_recv().is_ok() {
+                count += 1;
+            }
+            assert_eq!(count, 7);
+        }
+    }
+
+    /// 读取下一条 WebSocket 业务消息
+    async fn next_message<S>(client: &mut S) -> WsMessage
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        let msg = tokio::time::timeout(Duration::from_secs(5), client.next())
+            .await
+            .expect("等待推送超时")
+            .unwrap()
+            .unwrap();
+        serde_json::from_str(msg.to_text().unwrap()).unwrap()
+    }
+
+    /// 测试回放经 WebSocket 推送给订阅的客户端
+    #[tokio::test]
+    async fn test_replay_to_websocket() {
+        let feed = MarketFeed::default();
+        let server = Arc::new(WsServer::new().with_feed(feed.clone()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(server.run(listener));
+
+        let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{}", addr))
+            .await
+            .unwrap();
+        let subscribe = WsMessage::Subscribe {
+            channel: "quote".to_string(),
+            codes: vec!["000001".to_string()],
+        };
+        client
+            .send(Message::Text(serde_json::to_string(&subscribe).unwrap()))
+            .await
+            .unwrap();
+
+        // 订阅确认后再回放
+        assert!(matches!(next_message(&mut client).await, WsMessage::Pong));
+        let replayer = SessionReplayer::from_records(session()).with_speed(ReplaySpeed::Max);
+        replayer.replay(&feed).await.unwrap();
+
+        // 只收到订阅的 000001 行情，竞价、逐笔和 600036 不推送
+        let mut prices = Vec::new();
+        for _ in 0..3 {
+            match next_message(&mut client).await {
+                WsMessage::QuotePush { data } => {
+                    assert_eq!(data.code, "000001");
+                    prices.push(data.price);
+                }
+                other => panic!("意外的推送: {:?}", other),
+            }
+        }
+        assert_eq!(prices, vec![10.2, 10.3, 10.5]);
     }
 
-    /// 测试数据验证流程
-    ///
-    /// TODO: 测试数据验证器在完整流程中的作用
+    /// 测试数据验证流程：回放中的错误行情经采集流水线验证后丢弃，不写入也不发布
     #[tokio::test]
     async fn test_data_validation_workflow() {
-        // 占位测试 - 数据验证流程
-        assert!(true);
+        // 600036 最后一笔行情现价高于最高价
+        let mut broken = plain_quote("600036", 33.3, at(9, 36, 0));
+        broken.high = 30.0;
+        let mut records = session();
+        records.push(RecordedEvent {
+            ts: at(9, 36, 0),
+            event: MarketEvent::Quote(broken),
+        });
+        let replayer = Arc::new(SessionReplayer::from_records(records).with_speed(ReplaySpeed::Max));
+        replayer.replay(&MarketFeed::default()).await.unwrap();
+
+        let sink = Arc::new(MemorySink::default());
+        let monitor = Arc::new(CollectorMonitor::new(Vec::new()));
+        let feed = MarketFeed::default();
+        let mut events = feed.subscribe();
+        let pipeline = CollectionPipeline::new(replayer, sink.clone(), monitor)
+            .with_codes(vec!["000001".to_string(), "600036".to_string()])
+            .with_feed(feed);
+
+        pipeline.open().await.unwrap();
+        let summary = pipeline.tick().await.unwrap();
+        assert_eq!((summary.fetched, summary.accepted, summary.rejected), (2, 1, 1));
+        pipeline.close().await.unwrap();
+
+        let written: Vec<(String, f64)> = sink
+            .batches
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .map(|q| (q.code.clone(), q.price))
+            .collect();
+        assert_eq!(written, vec![("000001".to_string(), 10.5)]);
+        assert_eq!(events.recv().await.unwrap().code(), "000001");
+        assert!(events.try_recv().is_err());
     }
 
-    /// 测试批量写入流程
-    ///
-    /// TODO: 测试批量写入 ClickHouse 的完整流程
+    /// 测试批量写入流程：按批量大小分批，通道关闭时写完剩余数据
     #[tokio::test]
     async fn test_batch_write_workflow() {
-        // 占位测试 - 批量写入流程
-        assert!(true);
+        let sink = Arc::new(MemorySink::default());
+        let writer = BatchWriter::new().with_sink(sink.clone()).with_batch_size(3);
+        let (sender, receiver) = tokio::sync::mpsc::channel(16);
+        let task = tokio::spawn(async move { writer.start(receiver).await });
+
+        for record in session() {
+            if let MarketEvent::Quote(quote) = record.event {
+                sender.send(quote).await.unwrap();
+            }
+        }
+        drop(sender);
+        task.await.unwrap().unwrap();
+
+        let sizes: Vec<_> = sink.batches.lock().unwrap().iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![3, 1]);
     }
 
-    /// 测试多服务器切换流程
-    ///
-    /// TODO: 测试通达信客户端在服务器故障时的切换流程
+    /// 测试多服务器切换流程：采集过程中主服务器下线，连接池切换到备用服务器继续采集
     #[tokio::test]
     async fn test_server_failover_workflow() {
-        // 占位测试 - 服务器故障切换流程
-        assert!(true);
+        let data = MockData {
+            quotes: vec![
+                plain_quote("000001", 10.5, at(9, 35, 0)),
+                plain_quote("600036", 10.3, at(9, 35, 0)),
+            ],
+            ..Default::default()
+        };
+        let primary = MockTdxServer::start(data.clone()).await;
+        let backup = MockTdxServer::start(data).await;
+        let client = Arc::new(
+            TdxClient::new(vec![primary.addr(), backup.addr()]).with_timeout(Duration::from_millis(500)),
+        );
+
+        let sink = Arc::new(MemorySink::default());
+        let monitor = Arc::new(CollectorMonitor::new(Vec::new()));
+        let pipeline = CollectionPipeline::new(client.clone(), sink.clone(), monitor.clone())
+            .with_codes(vec!["000001".to_string(), "600036".to_string()]);
+        pipeline.open().await.unwrap();
+
+        assert_eq!(pipeline.tick().await.unwrap().accepted, 2);
+        assert_eq!(client.current_server(), primary.addr());
+
+        // 主服务器下线，下一次采集自动切换
+        drop(primary);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(pipeline.tick().await.unwrap().accepted, 2);
+        assert_eq!(client.current_server(), backup.addr());
+        pipeline.close().await.unwrap();
+
+        let written: usize = sink.batches.lock().unwrap().iter().map(Vec::len).sum();
+        assert_eq!(written, 4);
+        let metrics = monitor.get_metrics().await;
+        assert_eq!((metrics.success_count, metrics.failed_count), (2, 0));
     }
 
-    /// 测试调度器启停流程
-    ///
-    /// TODO: 测试采集调度器的启动和停止
+    /// 测试调度器启停流程：采集 → 验证 → 缓冲 → 写入，停止时写完缓冲区
     #[tokio::test]
     async fn test_scheduler_lifecycle() {
-        // 占位测试 - 调度器生命周期
-        assert!(true);
+        let sink = Arc::new(MemorySink::default());
+        let monitor = Arc::new(CollectorMonitor::new(Vec::new()));
+        let feed = MarketFeed::default();
+        let service = Arc::new(QuoteService::new(fixture_source()));
+        let consumer = service.spawn_feed_consumer(&feed);
+
+        let pipeline = CollectionPipeline::new(fixture_source(), sink.clone(), monitor.clone())
+            .with_codes(vec!["000001".to_string(), "600036".to_string()])
+            .with_feed(feed);
+        let mut scheduler = CollectionScheduler::new(Arc::new(RwLock::new(DataSourceConfig::default())))
+            .with_pipeline(Arc::new(pipeline));
+        for phase in SessionPhase::ALL {
+            scheduler = scheduler.with_cadence(phase, Some(Duration::from_millis(20)));
+        }
+
+        scheduler.start().await.unwrap();
+        assert!(scheduler.is_running().await);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        scheduler.stop().await.unwrap();
+        assert!(!scheduler.is_running().await);
+
+        // 停止后缓冲区已全部写入
+        let ticks = monitor.get_metrics().await.success_count as usize;
+        let written: usize = sink.batches.lock().unwrap().iter().map(Vec::len).sum();
+        assert!(ticks >= 2);
+        assert_eq!(written, ticks * 2);
+
+        drop(scheduler);
+        consumer.await.unwrap();
+        assert_eq!(service.get_quote("600036").await.unwrap().code, "600036");
     }
 }